@@ -0,0 +1,107 @@
+//! Check an sdkconfig file against a Kconfig tree.
+//!
+//! Reports assignments to unknown symbols, malformed values, values outside their `range`, and values that cannot hold
+//! because their dependencies are not met; all of these are silently dropped or reset by the next configuration run.
+//! The exit status is 0 if there are no problems, 1 if there are, and 2 on error.
+
+use {
+    clap::{builder::PossibleValue, Parser, ValueEnum},
+    log::error,
+    modular_esp_idf_kconfig_lib::{
+        eval::{ConfigCheck, Evaluation, SymbolTable},
+        parser::KConfig,
+        sdkconfig::{Renames, SdkConfig},
+        srctree, ContextArgs,
+    },
+    std::{path::PathBuf, process::ExitCode},
+};
+
+#[derive(Clone, Copy, Debug, Default)]
+enum OutputFormat {
+    /// Output as text.
+    #[default]
+    Text,
+
+    /// Output as JSON.
+    Json,
+}
+
+impl ValueEnum for OutputFormat {
+    fn value_variants<'a>() -> &'a [Self] {
+        &[Self::Text, Self::Json]
+    }
+
+    fn to_possible_value(&self) -> Option<PossibleValue> {
+        Some(match self {
+            Self::Text => PossibleValue::new("text").help("Output as text"),
+            Self::Json => PossibleValue::new("json").help("Output as JSON"),
+        })
+    }
+}
+
+/// Command line options for the check.
+#[derive(Debug, Parser)]
+#[command(version, about)]
+struct Options {
+    /// The configuration to check.
+    #[arg(env = "KCONFIG_CONFIG", default_value = "sdkconfig")]
+    config: PathBuf,
+
+    /// The top-level Kconfig file.
+    #[arg(long, default_value = "Kconfig")]
+    kconfig: PathBuf,
+
+    /// `sdkconfig.rename` files listing deprecated option names. Deprecated names are not reported as unknown.
+    #[arg(long)]
+    sdkconfig_rename: Vec<PathBuf>,
+
+    /// The variables to evaluate the Kconfig files with.
+    #[command(flatten)]
+    env: ContextArgs,
+
+    /// The output format.
+    #[arg(long, default_value = "text")]
+    format: OutputFormat,
+}
+
+fn main() -> ExitCode {
+    env_logger::init();
+    let options = Options::parse();
+
+    match run(&options) {
+        Ok(true) => ExitCode::SUCCESS,
+        Ok(false) => ExitCode::from(1),
+        Err(e) => {
+            error!("{e}");
+            ExitCode::from(2)
+        }
+    }
+}
+
+/// Check the configuration, returning `true` if there are no problems.
+fn run(options: &Options) -> Result<bool, Box<dyn std::error::Error>> {
+    let context = options.env.context()?;
+    let base_dir = srctree(&context)?;
+
+    let kconfig = KConfig::from_file(&options.kconfig, &base_dir, &context)?;
+
+    let mut renames = Renames::new();
+    for filename in &options.sdkconfig_rename {
+        renames.extend(Renames::from_file(filename)?)?;
+    }
+
+    let mut sdkconfig = SdkConfig::from_file(&options.config)?;
+    renames.apply(&mut sdkconfig);
+
+    let mut eval = Evaluation::new(SymbolTable::new(&kconfig));
+    eval.load_sdkconfig(&sdkconfig);
+    eval.evaluate(&context)?;
+
+    let check = ConfigCheck::new(&eval, &sdkconfig);
+    match options.format {
+        OutputFormat::Text => print!("{check}"),
+        OutputFormat::Json => println!("{}", serde_json::to_string_pretty(&check.to_json())?),
+    }
+
+    Ok(check.is_empty())
+}
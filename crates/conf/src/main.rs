@@ -0,0 +1,205 @@
+//! Generate an sdkconfig file using one of the standard Kconfig configuration targets.
+
+use {
+    clap::{builder::PossibleValue, Parser, ValueEnum},
+    log::error,
+    modular_esp_idf_kconfig_lib::{
+        eval::{ConfigGenerator, Evaluation, SymbolTable},
+        parser::KConfig,
+        sdkconfig::{Renames, SdkConfig, SdkConfigWriter},
+        srctree, ContextArgs, IncludeGraph, IncludeRecorder,
+    },
+    std::{
+        fs::File,
+        io::{BufWriter, Write},
+        path::{Path, PathBuf},
+        process::ExitCode,
+        time::{SystemTime, UNIX_EPOCH},
+    },
+};
+
+/// The configuration to generate.
+#[derive(Clone, Copy, Debug)]
+enum Target {
+    /// Keep the existing configuration and use defaults for new options.
+    OldDef,
+
+    /// Enable as many options as possible.
+    AllYes,
+
+    /// Disable as many options as possible.
+    AllNo,
+
+    /// Use the default value for every option.
+    AllDef,
+
+    /// Choose random values.
+    Rand,
+
+    /// Write the minimal defaults file that reproduces the existing configuration.
+    SaveDef,
+}
+
+impl ValueEnum for Target {
+    fn value_variants<'a>() -> &'a [Self] {
+        &[Self::OldDef, Self::AllYes, Self::AllNo, Self::AllDef, Self::Rand, Self::SaveDef]
+    }
+
+    fn to_possible_value(&self) -> Option<PossibleValue> {
+        Some(match self {
+            Self::OldDef => PossibleValue::new("olddefconfig")
+                .help("Keep the existing configuration and use defaults for new options"),
+            Self::AllYes => PossibleValue::new("allyesconfig").help("Enable as many options as possible"),
+            Self::AllNo => PossibleValue::new("allnoconfig").help("Disable as many options as possible"),
+            Self::AllDef => PossibleValue::new("alldefconfig").help("Use the default value for every option"),
+            Self::Rand => PossibleValue::new("randconfig").help("Choose random values"),
+            Self::SaveDef => PossibleValue::new("savedefconfig")
+                .help("Write the minimal defaults file that reproduces the existing configuration"),
+        })
+    }
+}
+
+/// Command line options for the generator.
+#[derive(Debug, Parser)]
+#[command(version, about)]
+struct Options {
+    /// The configuration to generate.
+    target: Target,
+
+    /// The top-level Kconfig file.
+    #[arg(long, default_value = "Kconfig")]
+    kconfig: PathBuf,
+
+    /// The project configuration (`sdkconfig`) file to write. `olddefconfig` and `savedefconfig` read it instead.
+    #[arg(long, env = "KCONFIG_CONFIG", default_value = "sdkconfig")]
+    config: PathBuf,
+
+    /// The defaults file written by `savedefconfig`.
+    #[arg(long, default_value = "sdkconfig.defaults")]
+    defconfig: PathBuf,
+
+    /// An sdkconfig file whose values are applied on top of the generated configuration.
+    #[arg(long, env = "KCONFIG_ALLCONFIG")]
+    allconfig: Option<PathBuf>,
+
+    /// The seed for `randconfig`. If not specified, a seed is chosen and printed.
+    #[arg(long, env = "KCONFIG_SEED", value_parser = parse_seed)]
+    seed: Option<u64>,
+
+    /// `sdkconfig.rename` files listing deprecated option names.
+    #[arg(long)]
+    sdkconfig_rename: Vec<PathBuf>,
+
+    /// The variables to evaluate the Kconfig files with.
+    #[command(flatten)]
+    env: ContextArgs,
+
+    /// Write a Makefile-style dependency file listing the Kconfig files read (and the directories of missing optional
+    /// sources) as prerequisites of the generated config file.
+    #[arg(long)]
+    depfile: Option<PathBuf>,
+}
+
+fn main() -> ExitCode {
+    env_logger::init();
+    let options = Options::parse();
+
+    match run(&options) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            error!("{e}");
+            ExitCode::FAILURE
+        }
+    }
+}
+
+fn run(options: &Options) -> Result<(), Box<dyn std::error::Error>> {
+    let context = options.env.context()?;
+    let base_dir = srctree(&context)?;
+
+    let recorder = IncludeRecorder::new(&context);
+    let kconfig = KConfig::from_file(&options.kconfig, &base_dir, &recorder)?;
+
+    let graph = recorder.into_graph();
+
+    let mut renames = Renames::new();
+    for filename in &options.sdkconfig_rename {
+        renames.extend(Renames::from_file(filename)?)?;
+    }
+
+    let mut eval = Evaluation::new(SymbolTable::new(&kconfig));
+    if matches!(options.target, Target::OldDef | Target::SaveDef) && options.config.exists() {
+        let mut sdkconfig = SdkConfig::from_file(&options.config)?;
+        renames.apply(&mut sdkconfig);
+        eval.load_sdkconfig(&sdkconfig);
+    }
+
+    let writer = SdkConfigWriter {
+        idf_version: None,
+        renames,
+    };
+
+    let generator = match options.target {
+        Target::OldDef => ConfigGenerator::OldDefConfig,
+        Target::AllYes => ConfigGenerator::AllYesConfig,
+        Target::AllNo => ConfigGenerator::AllNoConfig,
+        Target::AllDef => ConfigGenerator::AllDefConfig,
+        Target::Rand => {
+            let seed = match options.seed {
+                Some(seed) => seed,
+                None => {
+                    let seed = SystemTime::now().duration_since(UNIX_EPOCH)?.as_nanos() as u64;
+                    eprintln!("KCONFIG_SEED={seed:#x}");
+                    seed
+                }
+            };
+            ConfigGenerator::RandConfig(seed)
+        }
+        Target::SaveDef => {
+            eval.evaluate(&context)?;
+            let minimal = eval.savedefconfig(&context)?;
+
+            let mut output = BufWriter::new(File::create(&options.defconfig)?);
+            writer.write_defaults(&minimal, &mut output)?;
+            output.flush()?;
+            return write_depfile(options, &graph, &options.defconfig);
+        }
+    };
+
+    eval.generate(generator, &context)?;
+
+    // Values from the allconfig file override the generated ones.
+    if let Some(allconfig) = &options.allconfig {
+        let mut sdkconfig = SdkConfig::from_file(allconfig)?;
+        writer.renames.apply(&mut sdkconfig);
+        eval.load_sdkconfig(&sdkconfig);
+        eval.evaluate(&context)?;
+    }
+
+    let mut output = BufWriter::new(File::create(&options.config)?);
+    writer.write_config(&eval, &mut output)?;
+    output.flush()?;
+    write_depfile(options, &graph, &options.config)
+}
+
+/// Write the dependency file, if requested, once `target` has been written. Writing it earlier would let a failed run
+/// leave a dependency file behind for an output that is missing or stale.
+fn write_depfile(options: &Options, graph: &IncludeGraph, target: &Path) -> Result<(), Box<dyn std::error::Error>> {
+    if let Some(depfile) = &options.depfile {
+        let mut output = BufWriter::new(File::create(depfile)?);
+        graph.write_depfile(&mut output, target)?;
+        output.flush()?;
+    }
+
+    Ok(())
+}
+
+/// Parse a seed in decimal or, with a `0x` prefix, hexadecimal.
+fn parse_seed(s: &str) -> Result<u64, String> {
+    let result = match s.strip_prefix("0x").or_else(|| s.strip_prefix("0X")) {
+        Some(hex) => u64::from_str_radix(hex, 16),
+        None => s.parse(),
+    };
+
+    result.map_err(|e| format!("invalid seed {s:?}: {e}"))
+}
@@ -0,0 +1,80 @@
+//! Serve a Kconfig configuration over ESP-IDF's `confserver` JSON protocol.
+//!
+//! Requests are read from stdin and responses written to stdout, one JSON object per line.
+
+use {
+    clap::{value_parser, Parser},
+    log::error,
+    modular_esp_idf_kconfig_lib::{
+        eval::{Evaluation, SymbolTable},
+        parser::KConfig,
+        sdkconfig::Renames,
+        srctree, ConfServer, ContextArgs, CONFSERVER_MAX_VERSION, CONFSERVER_MIN_VERSION,
+    },
+    std::{
+        io::{stdin, stdout},
+        path::PathBuf,
+        process::ExitCode,
+    },
+};
+
+/// Command line options for the server. These match ESP-IDF's `confserver.py`.
+#[derive(Debug, Parser)]
+#[command(about)]
+struct Options {
+    /// The top-level Kconfig file.
+    #[arg(long, default_value = "Kconfig")]
+    kconfig: PathBuf,
+
+    /// `sdkconfig.rename` files listing deprecated option names.
+    #[arg(long)]
+    sdkconfig_rename: Vec<PathBuf>,
+
+    /// The variables to evaluate the Kconfig files with.
+    #[command(flatten)]
+    env: ContextArgs,
+
+    /// The protocol version to use for the initial response.
+    #[arg(
+        long,
+        default_value_t = CONFSERVER_MAX_VERSION,
+        value_parser = value_parser!(u64).range(CONFSERVER_MIN_VERSION..=CONFSERVER_MAX_VERSION),
+    )]
+    version: u64,
+
+    /// The project configuration (`sdkconfig`) file.
+    #[arg(long)]
+    config: PathBuf,
+}
+
+fn main() -> ExitCode {
+    env_logger::init();
+    let options = Options::parse();
+
+    match run(&options) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            error!("{e}");
+            ExitCode::FAILURE
+        }
+    }
+}
+
+fn run(options: &Options) -> Result<(), Box<dyn std::error::Error>> {
+    let context = options.env.context()?;
+    let base_dir = srctree(&context)?;
+
+    let kconfig = KConfig::from_file(&options.kconfig, &base_dir, &context)?;
+
+    let mut renames = Renames::new();
+    for filename in &options.sdkconfig_rename {
+        renames.extend(Renames::from_file(filename)?)?;
+    }
+
+    let eval = Evaluation::new(SymbolTable::new(&kconfig));
+    let mut server = ConfServer::new(eval, context, options.config.clone(), renames)?.with_version(options.version);
+
+    eprintln!("Server running, waiting for requests on stdin...");
+    server.run(stdin().lock(), &mut stdout().lock())?;
+    Ok(())
+}
@@ -5,15 +5,15 @@ use {
     clap::{builder::PossibleValue, Parser, ValueEnum},
     modular_esp_idf_kconfig_lib::{
         parser::{Block, Choice, Config, Expr, KConfig, LocExpr},
-        Target, KCONFIGS_IN, KCONFIGS_PROJBUILD_IN,
+        IdfProject, Target,
     },
     std::{
         cell::RefCell,
-        collections::HashMap,
+        env,
         fmt::{self, Display, Result as FmtResult},
         fs::File,
-        io::{stdout, Result as IoResult, Write},
-        path::Path,
+        io::{stdout, Error as IoError, Result as IoResult, Write},
+        path::{Path, PathBuf},
         rc::Rc,
     },
 };
@@ -54,7 +54,8 @@ struct Options {
     #[arg(long, env = "IDF_PATH")]
     idf_path: String,
 
-    /// The path to the component Kconfigs source file. Defaults to the in-tree version if not present.
+    /// The path to the component Kconfigs source file. Defaults to the Kconfig files of the components in the ESP-IDF
+    /// tree and the project.
     #[arg(long)]
     component_kconfig: Option<String>,
 
@@ -62,10 +63,16 @@ struct Options {
     #[arg(long, short, default_value = "graphviz")]
     format: OutputFormat,
 
-    /// The path to the project Kconfigs source file. Defaults to the in-tree version if not present.
+    /// The path to the project Kconfigs source file. Defaults to the Kconfig.projbuild files of the components in the
+    /// ESP-IDF tree and the project.
     #[arg(long)]
     project_kconfig: Option<String>,
 
+    /// The project directory. Its `main` component and the components in its `components` and `managed_components`
+    /// directories are included.
+    #[arg(long, default_value = ".")]
+    project_dir: PathBuf,
+
     /// The background color to use for choice nodes.
     #[arg(long, default_value = "#aaffaa")]
     choice_bgcolor: String,
@@ -89,32 +96,30 @@ struct Options {
 
 fn main() -> IoResult<()> {
     env_logger::init();
-    let mut context = HashMap::<String, String>::default();
+
+    // Accept targets supported by the ESP-IDF tree that this crate does not know about.
+    if let Some(idf_path) = env::var_os("IDF_PATH") {
+        if let Err(e) = Target::discover(Path::new(&idf_path)) {
+            log::warn!("Unable to find targets in {idf_path:?}: {e}");
+        }
+    }
+
     let options = Options::parse();
 
-    context.insert("IDF_PATH".to_string(), options.idf_path.clone());
+    let project =
+        IdfProject::new(&options.project_dir, options.target).with_idf_path(&options.idf_path).with_vars(env::vars());
+    let mut context = project.context().map_err(IoError::other)?;
 
+    // Override the generated component source lists as requested.
     if let Some(component_kconfig) = &options.component_kconfig {
         context.insert("COMPONENT_KCONFIGS_SOURCE_FILE".to_string(), component_kconfig.to_string());
-    } else {
-        context.insert("COMPONENT_KCONFIGS_SOURCE_FILE".to_string(), format!("inline:{KCONFIGS_IN}"));
     }
 
     if let Some(project_kconfig) = &options.project_kconfig {
         context.insert("COMPONENT_KCONFIGS_PROJBUILD_SOURCE_FILE".to_string(), project_kconfig.to_string());
-    } else {
-        context
-            .insert("COMPONENT_KCONFIGS_PROJBUILD_SOURCE_FILE".to_string(), format!("inline:{KCONFIGS_PROJBUILD_IN}"));
     }
 
-    context.insert("IDF_ENV_FPGA".to_string(), "n".to_string());
-    context.insert("IDF_CI_BUILD".to_string(), "n".to_string());
-
-    let base_dir = Path::new(&options.idf_path);
-    let kconfig_top = base_dir.join("Kconfig");
-
-    context.insert("IDF_TARGET".to_string(), options.target.name().to_string());
-    let kconfig = KConfig::from_file(&kconfig_top, base_dir, &context).unwrap();
+    let kconfig = project.load(&context).map_err(IoError::other)?;
 
     if options.output == "-" {
         write_graph(&mut stdout(), &kconfig, &options)
@@ -0,0 +1,137 @@
+//! Compare two sdkconfig files using Kconfig semantics.
+//!
+//! Changed values are reported with their menu paths and prompts, along with whether each value was set by the user or
+//! derived from defaults. The exit status is 0 if the configurations are the same, 1 if they differ, and 2 on error.
+
+use {
+    clap::{builder::PossibleValue, Parser, ValueEnum},
+    log::error,
+    modular_esp_idf_kconfig_lib::{
+        eval::{ConfigDiff, Evaluation, SymbolTable},
+        parse_var_assignment,
+        parser::KConfig,
+        sdkconfig::{Renames, SdkConfig},
+        srctree, ContextArgs,
+    },
+    std::{
+        collections::HashMap,
+        path::{Path, PathBuf},
+        process::ExitCode,
+    },
+};
+
+#[derive(Clone, Copy, Debug, Default)]
+enum OutputFormat {
+    /// Output as text.
+    #[default]
+    Text,
+
+    /// Output as JSON.
+    Json,
+}
+
+impl ValueEnum for OutputFormat {
+    fn value_variants<'a>() -> &'a [Self] {
+        &[Self::Text, Self::Json]
+    }
+
+    fn to_possible_value(&self) -> Option<PossibleValue> {
+        Some(match self {
+            Self::Text => PossibleValue::new("text").help("Output as text"),
+            Self::Json => PossibleValue::new("json").help("Output as JSON"),
+        })
+    }
+}
+
+/// Command line options for the comparison.
+#[derive(Debug, Parser)]
+#[command(version, about)]
+struct Options {
+    /// The old configuration.
+    old: PathBuf,
+
+    /// The new configuration.
+    new: PathBuf,
+
+    /// The top-level Kconfig file.
+    #[arg(long, default_value = "Kconfig")]
+    kconfig: PathBuf,
+
+    /// The top-level Kconfig file for the new configuration, if it differs from the old one.
+    #[arg(long)]
+    new_kconfig: Option<PathBuf>,
+
+    /// `sdkconfig.rename` files listing deprecated option names.
+    #[arg(long)]
+    sdkconfig_rename: Vec<PathBuf>,
+
+    /// The variables to evaluate the Kconfig files with.
+    #[command(flatten)]
+    env: ContextArgs,
+
+    /// Environment variables to set only for the new configuration, as `NAME=VALUE`.
+    #[arg(long, value_parser = parse_var_assignment)]
+    new_env: Vec<(String, String)>,
+
+    /// The output format.
+    #[arg(long, default_value = "text")]
+    format: OutputFormat,
+}
+
+fn main() -> ExitCode {
+    env_logger::init();
+    let options = Options::parse();
+
+    match run(&options) {
+        Ok(true) => ExitCode::SUCCESS,
+        Ok(false) => ExitCode::from(1),
+        Err(e) => {
+            error!("{e}");
+            ExitCode::from(2)
+        }
+    }
+}
+
+/// Compare the configurations, returning `true` if they are the same.
+fn run(options: &Options) -> Result<bool, Box<dyn std::error::Error>> {
+    let context = options.env.context()?;
+
+    let mut new_context = context.clone();
+    new_context.extend(options.new_env.iter().cloned());
+
+    let mut renames = Renames::new();
+    for filename in &options.sdkconfig_rename {
+        renames.extend(Renames::from_file(filename)?)?;
+    }
+
+    let old = load(&options.kconfig, &options.old, &renames, &context)?;
+    let new = load(options.new_kconfig.as_ref().unwrap_or(&options.kconfig), &options.new, &renames, &new_context)?;
+    let diff = ConfigDiff::new(&old, &new);
+
+    match options.format {
+        OutputFormat::Text => print!("{diff}"),
+        OutputFormat::Json => println!("{}", serde_json::to_string_pretty(&diff.to_json())?),
+    }
+
+    Ok(diff.is_empty())
+}
+
+/// Evaluate a configuration, reducing it to its minimal user values so values equal to their defaults are reported as
+/// derived from them.
+fn load(
+    kconfig: &Path,
+    config: &Path,
+    renames: &Renames,
+    context: &HashMap<String, String>,
+) -> Result<Evaluation, Box<dyn std::error::Error>> {
+    let base_dir = srctree(context)?;
+
+    let kconfig = KConfig::from_file(kconfig, &base_dir, context)?;
+    let mut sdkconfig = SdkConfig::from_file(config)?;
+    renames.apply(&mut sdkconfig);
+
+    let mut eval = Evaluation::new(SymbolTable::new(&kconfig));
+    eval.load_sdkconfig(&sdkconfig);
+    eval.evaluate(context)?;
+    Ok(eval.savedefconfig(context)?)
+}
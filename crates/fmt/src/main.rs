@@ -0,0 +1,125 @@
+//! Check or rewrite Kconfig files in ESP-IDF style.
+
+use {
+    clap::{builder::PossibleValue, Parser, ValueEnum},
+    log::error,
+    modular_esp_idf_kconfig_lib::{format_kconfig, parser::CstFile, FormatStyle},
+    std::{
+        fs,
+        io::{stdout, Result as IoResult, Write},
+        path::{Path, PathBuf},
+        process::ExitCode,
+    },
+};
+
+/// Wrapper around [`FormatStyle`] so it can be used as a command line value.
+#[derive(Clone, Copy, Debug, Default)]
+struct Style(FormatStyle);
+
+impl ValueEnum for Style {
+    fn value_variants<'a>() -> &'a [Self] {
+        &[Self(FormatStyle::EspIdf), Self(FormatStyle::Lossless)]
+    }
+
+    fn to_possible_value(&self) -> Option<PossibleValue> {
+        Some(match self.0 {
+            FormatStyle::EspIdf => PossibleValue::new("esp-idf").alias("espidf").help("Normalize to ESP-IDF style"),
+            FormatStyle::Lossless => {
+                PossibleValue::new("lossless").help("Re-emit the input unchanged (only checks that it parses)")
+            }
+        })
+    }
+}
+
+/// Command line options for the formatter.
+#[derive(Debug, Parser)]
+#[command(version, about)]
+struct Options {
+    /// Kconfig files to format. Directories are searched recursively for files whose names start with `Kconfig`.
+    #[arg(required = true)]
+    paths: Vec<PathBuf>,
+
+    /// Don't write anything; exit with a non-zero status if any file is not formatted.
+    #[arg(long)]
+    check: bool,
+
+    /// Write the formatted output to stdout instead of rewriting the files.
+    #[arg(long, conflicts_with = "check")]
+    stdout: bool,
+
+    /// The style to format in.
+    #[arg(long, short, default_value = "esp-idf")]
+    style: Style,
+}
+
+fn main() -> ExitCode {
+    env_logger::init();
+    let options = Options::parse();
+    let mut files = Vec::new();
+
+    for path in &options.paths {
+        if let Err(e) = find_kconfigs(path, &mut files) {
+            error!("Unable to read {}: {e}", path.display());
+            return ExitCode::FAILURE;
+        }
+    }
+
+    let mut ok = true;
+
+    for file in &files {
+        match format_file(file, &options) {
+            Ok(formatted) => ok &= formatted,
+            Err(e) => {
+                eprintln!("{}: {e}", file.display());
+                ok = false;
+            }
+        }
+    }
+
+    if ok {
+        ExitCode::SUCCESS
+    } else {
+        ExitCode::FAILURE
+    }
+}
+
+/// Add `path` to `files` if it is a file, or all Kconfig files underneath it if it is a directory.
+fn find_kconfigs(path: &Path, files: &mut Vec<PathBuf>) -> IoResult<()> {
+    if !path.is_dir() {
+        files.push(path.to_path_buf());
+        return Ok(());
+    }
+
+    let mut entries = fs::read_dir(path)?.map(|entry| entry.map(|e| e.path())).collect::<IoResult<Vec<_>>>()?;
+    entries.sort();
+
+    for entry in entries {
+        if entry.is_dir() {
+            find_kconfigs(&entry, files)?;
+        } else if entry.file_name().map(|name| name.to_string_lossy().starts_with("Kconfig")).unwrap_or(false) {
+            files.push(entry);
+        }
+    }
+
+    Ok(())
+}
+
+/// Format a single file. This returns `false` if `--check` was specified and the file is not formatted.
+fn format_file(file: &Path, options: &Options) -> Result<bool, Box<dyn std::error::Error>> {
+    let input = fs::read_to_string(file)?;
+    let cst = CstFile::from_file(file)?;
+    let formatted = format_kconfig(&cst, options.style.0);
+
+    if options.stdout {
+        stdout().write_all(formatted.as_bytes())?;
+    } else if formatted != input {
+        if options.check {
+            println!("Would reformat: {}", file.display());
+            return Ok(false);
+        }
+
+        fs::write(file, formatted)?;
+    }
+
+    Ok(true)
+}
@@ -0,0 +1,361 @@
+use {
+    crate::parser::KConfigError,
+    log::debug,
+    std::{
+        collections::{BTreeMap, HashMap},
+        fs,
+        io::ErrorKind as IoErrorKind,
+        path::{Path, PathBuf},
+    },
+};
+
+/// The name of a component's Kconfig file.
+const KCONFIG: &str = "Kconfig";
+
+/// The name of a component's project-level Kconfig file.
+const KCONFIG_PROJBUILD: &str = "Kconfig.projbuild";
+
+/// The name of a component's CMake file.
+const CMAKELISTS: &str = "CMakeLists.txt";
+
+/// Directory names holding test applications inside components.
+const TEST_APP_DIRS: &[&str] = &["host_test", "test", "test_apps"];
+
+/// Finds the components of an ESP-IDF project and the Kconfig files they provide.
+///
+/// Components are searched for in the same places and with the same priority as the ESP-IDF CMake build: a component
+/// in the project's `components` directory (or `main`) overrides one of the same name in an extra component directory,
+/// which overrides one in `managed_components`, which overrides one in `$IDF_PATH/components`.
+#[derive(Clone, Debug)]
+pub struct ComponentDiscovery {
+    idf_path: PathBuf,
+    extra_component_dirs: Vec<PathBuf>,
+    project_dir: Option<PathBuf>,
+    include_test_apps: bool,
+}
+
+/// A component found by [`ComponentDiscovery`].
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Component {
+    /// The name of the component (the name of its directory).
+    pub name: String,
+
+    /// The component directory.
+    pub dir: PathBuf,
+
+    /// The Kconfig files to include in the `Component config` menu.
+    pub kconfigs: Vec<PathBuf>,
+
+    /// The Kconfig files to include at the top level of the configuration.
+    pub kconfig_projbuilds: Vec<PathBuf>,
+}
+
+/// The components of a project, sorted by name.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct Components {
+    /// The components.
+    pub components: Vec<Component>,
+}
+
+impl ComponentDiscovery {
+    /// Create a discovery for the ESP-IDF tree at `idf_path`.
+    pub fn new(idf_path: impl Into<PathBuf>) -> Self {
+        Self {
+            idf_path: idf_path.into(),
+            extra_component_dirs: Vec::new(),
+            project_dir: None,
+            include_test_apps: false,
+        }
+    }
+
+    /// Add an extra component directory, as in `EXTRA_COMPONENT_DIRS`. The directory is either a component itself (if
+    /// it contains `CMakeLists.txt`) or a directory of components.
+    pub fn with_extra_component_dir(mut self, dir: impl Into<PathBuf>) -> Self {
+        self.extra_component_dirs.push(dir.into());
+        self
+    }
+
+    /// Set the project directory, adding its `main` component along with the components in `components` and
+    /// `managed_components`.
+    pub fn with_project_dir(mut self, dir: impl Into<PathBuf>) -> Self {
+        self.project_dir = Some(dir.into());
+        self
+    }
+
+    /// Also include Kconfig files from test applications (`test_apps`, `host_test`, and `test` directories) inside
+    /// components. ESP-IDF only includes these when building the test application itself.
+    pub fn with_test_apps(mut self, include_test_apps: bool) -> Self {
+        self.include_test_apps = include_test_apps;
+        self
+    }
+
+    /// Find the components.
+    pub fn discover(&self) -> Result<Components, KConfigError> {
+        let mut dirs = BTreeMap::new();
+
+        // Lowest priority first; later directories replace components of the same name.
+        add_components_in(&mut dirs, &self.idf_path.join("components"))?;
+
+        if let Some(project_dir) = &self.project_dir {
+            add_components_in(&mut dirs, &project_dir.join("managed_components"))?;
+        }
+
+        for dir in &self.extra_component_dirs {
+            if dir.join(CMAKELISTS).is_file() {
+                add_component(&mut dirs, dir);
+            } else {
+                add_components_in(&mut dirs, dir)?;
+            }
+        }
+
+        if let Some(project_dir) = &self.project_dir {
+            add_components_in(&mut dirs, &project_dir.join("components"))?;
+            let main = project_dir.join("main");
+            if main.is_dir() {
+                add_component(&mut dirs, &main);
+            }
+        }
+
+        let mut components = Vec::with_capacity(dirs.len());
+        for (name, dir) in dirs {
+            let component = self.component(name, dir)?;
+            if !component.kconfigs.is_empty() || !component.kconfig_projbuilds.is_empty() {
+                components.push(component);
+            }
+        }
+
+        Ok(Components {
+            components,
+        })
+    }
+
+    /// Find the Kconfig files of a component.
+    fn component(&self, name: String, dir: PathBuf) -> Result<Component, KConfigError> {
+        let (mut kconfigs, mut kconfig_projbuilds) = cmake_kconfigs(&dir)?;
+
+        if kconfigs.is_empty() && dir.join(KCONFIG).is_file() {
+            kconfigs.push(dir.join(KCONFIG));
+        }
+
+        if kconfig_projbuilds.is_empty() && dir.join(KCONFIG_PROJBUILD).is_file() {
+            kconfig_projbuilds.push(dir.join(KCONFIG_PROJBUILD));
+        }
+
+        if self.include_test_apps {
+            let mut test_files = Vec::new();
+            find_test_app_kconfigs(&dir, false, &mut test_files)?;
+            test_files.sort();
+
+            for filename in test_files {
+                let files = if filename.file_name().is_some_and(|name| name == KCONFIG) {
+                    &mut kconfigs
+                } else {
+                    &mut kconfig_projbuilds
+                };
+
+                if !files.contains(&filename) {
+                    files.push(filename);
+                }
+            }
+        }
+
+        debug!("Component {name} at {}", dir.display());
+        Ok(Component {
+            name,
+            dir,
+            kconfigs,
+            kconfig_projbuilds,
+        })
+    }
+}
+
+impl Components {
+    /// Returns the component with the given name.
+    pub fn get(&self, name: &str) -> Option<&Component> {
+        self.components.iter().find(|component| component.name == name)
+    }
+
+    /// Returns the component directories, e.g. for [`Renames::discover()`][crate::sdkconfig::Renames::discover].
+    pub fn dirs(&self) -> impl Iterator<Item = &Path> {
+        self.components.iter().map(|component| component.dir.as_path())
+    }
+
+    /// Returns the contents of the file named by `COMPONENT_KCONFIGS_SOURCE_FILE`.
+    pub fn kconfigs_in(&self) -> String {
+        source_lines(self.components.iter().flat_map(|component| &component.kconfigs))
+    }
+
+    /// Returns the contents of the file named by `COMPONENT_KCONFIGS_PROJBUILD_SOURCE_FILE`.
+    pub fn kconfigs_projbuild_in(&self) -> String {
+        source_lines(self.components.iter().flat_map(|component| &component.kconfig_projbuilds))
+    }
+
+    /// Set `COMPONENT_KCONFIGS_SOURCE_FILE` and `COMPONENT_KCONFIGS_PROJBUILD_SOURCE_FILE` in a context to inline
+    /// copies of the generated source files.
+    pub fn insert_context(&self, context: &mut HashMap<String, String>) {
+        context.insert("COMPONENT_KCONFIGS_SOURCE_FILE".to_string(), format!("inline:{}", self.kconfigs_in()));
+        context.insert(
+            "COMPONENT_KCONFIGS_PROJBUILD_SOURCE_FILE".to_string(),
+            format!("inline:{}", self.kconfigs_projbuild_in()),
+        );
+    }
+}
+
+/// Add a component directory, replacing any component with the same name.
+fn add_component(dirs: &mut BTreeMap<String, PathBuf>, dir: &Path) {
+    if let Some(name) = dir.file_name().and_then(|name| name.to_str()) {
+        dirs.insert(name.to_string(), dir.to_path_buf());
+    }
+}
+
+/// Add the components in a directory. Subdirectories are components if they contain `CMakeLists.txt`, `Kconfig`, or
+/// `Kconfig.projbuild`. A missing directory has no components.
+fn add_components_in(dirs: &mut BTreeMap<String, PathBuf>, parent: &Path) -> Result<(), KConfigError> {
+    let entries = match fs::read_dir(parent) {
+        Ok(entries) => entries,
+        Err(e) if e.kind() == IoErrorKind::NotFound => return Ok(()),
+        Err(e) => return Err(e.into()),
+    };
+
+    for entry in entries {
+        let dir = entry?.path();
+        if [CMAKELISTS, KCONFIG, KCONFIG_PROJBUILD].iter().any(|name| dir.join(name).is_file()) {
+            add_component(dirs, &dir);
+        }
+    }
+
+    Ok(())
+}
+
+/// Returns the files named by the `KCONFIG` and `KCONFIG_PROJBUILD` arguments of `idf_component_register()` in the
+/// component's `CMakeLists.txt`, if any.
+fn cmake_kconfigs(dir: &Path) -> Result<(Vec<PathBuf>, Vec<PathBuf>), KConfigError> {
+    let mut kconfigs = Vec::new();
+    let mut kconfig_projbuilds = Vec::new();
+
+    let cmakelists = match fs::read_to_string(dir.join(CMAKELISTS)) {
+        Ok(cmakelists) => cmakelists,
+        Err(e) if e.kind() == IoErrorKind::NotFound => return Ok((kconfigs, kconfig_projbuilds)),
+        Err(e) => return Err(e.into()),
+    };
+
+    let dir_str = dir.to_string_lossy();
+    let mut words = cmakelists
+        .lines()
+        .map(|line| line.split('#').next().unwrap_or_default())
+        .flat_map(|line| line.split(|c: char| c.is_whitespace() || c == '(' || c == ')'))
+        .filter(|word| !word.is_empty());
+
+    while let Some(word) = words.next() {
+        let files = match word {
+            "KCONFIG" => &mut kconfigs,
+            "KCONFIG_PROJBUILD" => &mut kconfig_projbuilds,
+            _ => continue,
+        };
+
+        let Some(filename) = words.next() else {
+            break;
+        };
+
+        let filename = filename
+            .trim_matches('"')
+            .replace("${CMAKE_CURRENT_LIST_DIR}", &dir_str)
+            .replace("${CMAKE_CURRENT_SOURCE_DIR}", &dir_str)
+            .replace("${COMPONENT_DIR}", &dir_str);
+        let filename = dir.join(filename);
+        if filename.is_file() {
+            files.push(filename);
+        }
+    }
+
+    Ok((kconfigs, kconfig_projbuilds))
+}
+
+/// Find `Kconfig` and `Kconfig.projbuild` files inside test application directories under `dir`.
+fn find_test_app_kconfigs(dir: &Path, in_test_app: bool, result: &mut Vec<PathBuf>) -> Result<(), KConfigError> {
+    for entry in fs::read_dir(dir)? {
+        let entry = entry?;
+        let path = entry.path();
+
+        if entry.file_type()?.is_dir() {
+            let is_test_app = TEST_APP_DIRS.iter().any(|name| entry.file_name() == *name);
+            find_test_app_kconfigs(&path, in_test_app || is_test_app, result)?;
+        } else if in_test_app && (entry.file_name() == KCONFIG || entry.file_name() == KCONFIG_PROJBUILD) {
+            result.push(path);
+        }
+    }
+
+    Ok(())
+}
+
+/// Returns `source` statements for each file.
+fn source_lines<'a>(filenames: impl Iterator<Item = &'a PathBuf>) -> String {
+    filenames.map(|filename| format!("source \"{}\"\n", filename.display())).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        crate::{ComponentDiscovery, KCONFIGS_IN, KCONFIGS_PROJBUILD_IN},
+        std::{env, fs, path::Path},
+    };
+
+    #[test_log::test]
+    fn discover_esp_idf() {
+        let idf_path = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests").join("esp-idf");
+        let idf_path_str = idf_path.to_str().unwrap();
+
+        let components = ComponentDiscovery::new(&idf_path).with_test_apps(true).discover().unwrap();
+        assert_eq!(components.kconfigs_in().replace(idf_path_str, "$IDF_PATH").trim_end(), KCONFIGS_IN.trim_end());
+        assert_eq!(components.kconfigs_projbuild_in().replace(idf_path_str, "$IDF_PATH").trim_end(),
+            KCONFIGS_PROJBUILD_IN.trim_end()
+        );
+
+        let components = ComponentDiscovery::new(&idf_path).discover().unwrap();
+        let kconfigs_in = components.kconfigs_in();
+        assert!(kconfigs_in.contains("/components/mqtt/esp-mqtt/Kconfig\"\n"));
+        assert!(!kconfigs_in.contains("host_test"));
+        assert!(!components.kconfigs_projbuild_in().contains("test_apps"));
+        assert!(components.get("soc").is_some());
+    }
+
+    #[test_log::test]
+    fn discover_project() {
+        let dir = env::temp_dir().join(format!("kconfig-components-{}", std::process::id()));
+        let idf_path = dir.join("idf");
+        let project = dir.join("project");
+        let extra = dir.join("extra");
+
+        for component in [
+            idf_path.join("components/log"),
+            idf_path.join("components/esp_wifi"),
+            project.join("managed_components/espressif__led_strip"),
+            project.join("components/log"),
+            project.join("main"),
+            extra.join("sensor"),
+        ] {
+            fs::create_dir_all(&component).unwrap();
+            fs::write(component.join("CMakeLists.txt"), "idf_component_register()\n").unwrap();
+            fs::write(component.join("Kconfig"), "").unwrap();
+        }
+        fs::write(project.join("main/Kconfig.projbuild"), "").unwrap();
+        fs::create_dir_all(idf_path.join("components/esp_wifi/test_apps/main")).unwrap();
+        fs::write(idf_path.join("components/esp_wifi/test_apps/main/Kconfig"), "").unwrap();
+
+        let components = ComponentDiscovery::new(&idf_path)
+            .with_project_dir(&project)
+            .with_extra_component_dir(&extra)
+            .discover()
+            .unwrap();
+        fs::remove_dir_all(&dir).unwrap();
+
+        let names: Vec<&str> = components.components.iter().map(|component| component.name.as_str()).collect();
+        assert_eq!(names, vec!["esp_wifi", "espressif__led_strip", "log", "main", "sensor"]);
+        assert_eq!(components.get("log").unwrap().dir, project.join("components/log"));
+        assert_eq!(components.get("esp_wifi").unwrap().kconfigs, vec![idf_path.join("components/esp_wifi/Kconfig")]);
+        assert_eq!(
+            components.kconfigs_projbuild_in(),
+            format!("source \"{}\"\n", project.join("main/Kconfig.projbuild").display())
+        );
+    }
+}
@@ -0,0 +1,615 @@
+use {
+    crate::{
+        eval::{Evaluation, MenuItem, MenuNode},
+        parser::{KConfigError, Tristate, Type},
+        sdkconfig::{json_number, json_values, slug, Renames, SdkConfig, SdkConfigWriter},
+        Context,
+    },
+    log::{debug, error, info},
+    serde_json::{Map as JsonMap, Value as JsonValue},
+    std::{
+        fs::File,
+        io::{BufRead, BufWriter, Result as IoResult, Write},
+        path::{Path, PathBuf},
+    },
+};
+
+/// The oldest `confserver` protocol version supported.
+pub const CONFSERVER_MIN_VERSION: u64 = 1;
+
+/// The newest `confserver` protocol version supported.
+pub const CONFSERVER_MAX_VERSION: u64 = 2;
+
+/// A server speaking ESP-IDF's `confserver` JSON protocol.
+///
+/// Requests and responses are JSON objects, one per line. The server first sends the values, ranges, and (in version
+/// 2) visibility of every item. Each request may then `load` an `sdkconfig` file, `set` symbol values, and `save` the
+/// configuration; the response contains only the values, ranges, and visibility that changed.
+pub struct ConfServer<C> {
+    eval: Evaluation,
+    context: C,
+
+    /// The `sdkconfig` file used when `load` or `save` is requested without a filename.
+    sdkconfig: PathBuf,
+
+    /// Writes the configuration for `save` requests, including deprecated option names.
+    writer: SdkConfigWriter,
+
+    /// The protocol version used for the initial response and for requests that don't specify one.
+    version: u64,
+}
+
+/// The state reported to clients, used to compute the changes made by a request.
+struct Snapshot {
+    values: JsonMap<String, JsonValue>,
+    ranges: JsonMap<String, JsonValue>,
+    visible: JsonMap<String, JsonValue>,
+}
+
+impl Snapshot {
+    /// Returns the values as reported by version 1, which has no visibility information and reports invisible items
+    /// as false.
+    fn v1_values(&self) -> JsonMap<String, JsonValue> {
+        let mut values = self.values.clone();
+        for (name, _) in self.visible.iter().filter(|(_, visible)| **visible == JsonValue::Bool(false)) {
+            values.insert(name.clone(), JsonValue::Bool(false));
+        }
+
+        values
+    }
+}
+
+impl<C> ConfServer<C>
+where
+    C: Context,
+{
+    /// Create a server for an evaluation, loading `sdkconfig` if it exists.
+    ///
+    /// Deprecated option names in loaded files are replaced using `renames`, and are written back alongside the new
+    /// names when saving.
+    pub fn new(eval: Evaluation, context: C, sdkconfig: PathBuf, renames: Renames) -> Result<Self, KConfigError> {
+        let mut server = Self {
+            eval,
+            context,
+            sdkconfig,
+            writer: SdkConfigWriter {
+                idf_version: None,
+                renames,
+            },
+            version: CONFSERVER_MAX_VERSION,
+        };
+
+        if server.sdkconfig.exists() {
+            let sdkconfig = server.sdkconfig.clone();
+            server.load(&sdkconfig)?;
+        } else {
+            info!("{} does not exist; using default values", server.sdkconfig.display());
+            server.eval.evaluate(&server.context)?;
+        }
+
+        Ok(server)
+    }
+
+    /// Set the protocol version used for the initial response and for requests that don't specify one.
+    pub fn with_version(mut self, version: u64) -> Self {
+        self.version = version;
+        self
+    }
+
+    /// Returns the current evaluation.
+    #[inline(always)]
+    pub fn evaluation(&self) -> &Evaluation {
+        &self.eval
+    }
+
+    /// Serve requests from `input` until it is closed, writing responses to `output`.
+    pub fn run<R: BufRead, W: Write>(&mut self, input: R, output: &mut W) -> IoResult<()> {
+        write_response(&self.initial_response(), output)?;
+
+        for line in input.lines() {
+            let line = line?;
+            if line.trim().is_empty() {
+                continue;
+            }
+
+            let response = self.handle_line(&line);
+            write_response(&response, output)?;
+        }
+
+        Ok(())
+    }
+
+    /// Returns the response sent when a client connects: the full state of the configuration.
+    pub fn initial_response(&self) -> JsonValue {
+        let snapshot = self.snapshot();
+
+        let mut response = JsonMap::new();
+        response.insert("version".to_string(), self.version.into());
+
+        // Version 1 has no visibility information.
+        if self.version == 1 {
+            response.insert("values".to_string(), snapshot.v1_values().into());
+            response.insert("ranges".to_string(), snapshot.ranges.into());
+        } else {
+            response.insert("values".to_string(), snapshot.values.into());
+            response.insert("ranges".to_string(), snapshot.ranges.into());
+            response.insert("visible".to_string(), snapshot.visible.into());
+        }
+
+        response.into()
+    }
+
+    /// Handle a single request line, returning the response.
+    pub fn handle_line(&mut self, line: &str) -> JsonValue {
+        let request: JsonMap<String, JsonValue> = match serde_json::from_str(line) {
+            Ok(request) => request,
+            Err(e) => {
+                let mut response = JsonMap::new();
+                response.insert("version".to_string(), self.version.into());
+                response.insert("error".to_string(), vec![format!("JSON formatting error: {e}")].into());
+                return response.into();
+            }
+        };
+
+        let version = request.get("version").and_then(JsonValue::as_u64);
+        let mut before = self.snapshot();
+
+        if request.contains_key("load") && version.unwrap_or(self.version) == 1 {
+            // Version 1 sends every item after loading a new file rather than just the changes.
+            before = Snapshot {
+                values: JsonMap::new(),
+                ranges: JsonMap::new(),
+                visible: JsonMap::new(),
+            };
+        }
+
+        let errors = self.handle_request(&request, version);
+        let after = self.snapshot();
+
+        let ranges = diff(&before.ranges, &after.ranges);
+        let visible = diff(&before.visible, &after.visible);
+
+        let version = version.unwrap_or(self.version);
+        let mut response = JsonMap::new();
+        response.insert("version".to_string(), version.into());
+
+        if version == 1 {
+            // Version 1 reports invisible items as false, so items that became invisible show up as changed.
+            let values = diff(&before.v1_values(), &after.v1_values());
+            response.insert("values".to_string(), values.into());
+            response.insert("ranges".to_string(), ranges.into());
+        } else {
+            let values = diff(&before.values, &after.values);
+            response.insert("values".to_string(), values.into());
+            response.insert("ranges".to_string(), ranges.into());
+            response.insert("visible".to_string(), visible.into());
+        }
+
+        if !errors.is_empty() {
+            for e in &errors {
+                error!("{e}");
+            }
+
+            response.insert("error".to_string(), errors.into());
+        }
+
+        response.into()
+    }
+
+    /// Apply a request, returning any errors.
+    fn handle_request(&mut self, request: &JsonMap<String, JsonValue>, version: Option<u64>) -> Vec<String> {
+        let Some(version) = version else {
+            return vec!["All requests must have a 'version'".to_string()];
+        };
+
+        if !(CONFSERVER_MIN_VERSION..=CONFSERVER_MAX_VERSION).contains(&version) {
+            return vec![format!(
+                "Unsupported request version {version}. Server supports versions \
+                 {CONFSERVER_MIN_VERSION}-{CONFSERVER_MAX_VERSION}"
+            )];
+        }
+
+        let mut errors = Vec::new();
+
+        if let Some(load) = request.get("load") {
+            if let Some(filename) = load.as_str() {
+                self.sdkconfig = PathBuf::from(filename);
+            }
+
+            let sdkconfig = self.sdkconfig.clone();
+            info!("Loading config from {}...", sdkconfig.display());
+            if let Err(e) = self.load(&sdkconfig) {
+                errors.push(format!("Failed to load from {}: {e}", sdkconfig.display()));
+            }
+        }
+
+        if let Some(to_set) = request.get("set") {
+            match to_set.as_object() {
+                Some(to_set) => self.set(to_set, &mut errors),
+                None => errors.push("'set' must be an object mapping symbol names to values".to_string()),
+            }
+        }
+
+        if let Some(save) = request.get("save") {
+            if let Some(filename) = save.as_str() {
+                self.sdkconfig = PathBuf::from(filename);
+            }
+
+            let sdkconfig = self.sdkconfig.clone();
+            info!("Saving config to {}...", sdkconfig.display());
+            if let Err(e) = self.save(&sdkconfig) {
+                errors.push(format!("Failed to save to {}: {e}", sdkconfig.display()));
+            }
+        }
+
+        errors
+    }
+
+    /// Replace the user values with those from an `sdkconfig` file.
+    fn load(&mut self, filename: &Path) -> Result<(), KConfigError> {
+        let mut sdkconfig = SdkConfig::from_file(filename)?;
+        self.writer.renames.apply(&mut sdkconfig);
+
+        self.eval.clear_user_values();
+        self.eval.load_sdkconfig(&sdkconfig);
+        self.eval.evaluate(&self.context)
+    }
+
+    /// Write the configuration to an `sdkconfig` file.
+    fn save(&self, filename: &Path) -> IoResult<()> {
+        let mut writer = BufWriter::new(File::create(filename)?);
+        self.writer.write_config(&self.eval, &mut writer)?;
+        writer.flush()
+    }
+
+    /// Set symbol values.
+    ///
+    /// A symbol can only be set while it is visible, but setting one symbol can make others visible. Values are set in
+    /// passes until no more of the remaining symbols are visible.
+    fn set(&mut self, to_set: &JsonMap<String, JsonValue>, errors: &mut Vec<String>) {
+        let missing: Vec<&str> =
+            to_set.keys().filter(|name| self.eval.table().symbol_id(name).is_none()).map(String::as_str).collect();
+        if !missing.is_empty() {
+            errors.push(format!("The following config symbol(s) were not found: {}", missing.join(", ")));
+        }
+
+        let mut pending: Vec<(&str, &JsonValue)> =
+            to_set.iter().filter(|(name, _)| !missing.contains(&name.as_str())).map(|(k, v)| (k.as_str(), v)).collect();
+
+        while !pending.is_empty() {
+            let (visible, hidden): (Vec<_>, Vec<_>) = pending.into_iter().partition(|(name, _)| {
+                self.eval.state(name).map(|state| state.visibility != Tristate::False).unwrap_or(false)
+            });
+            pending = hidden;
+
+            if visible.is_empty() {
+                break;
+            }
+
+            for (name, value) in visible {
+                let r#type = self.eval.table().symbol(name).map(|symbol| symbol.r#type).unwrap_or_default();
+                match set_value(r#type, name, value) {
+                    Ok(value) => {
+                        debug!("Set {name}");
+                        self.eval.set_user_value(name, value);
+                    }
+                    Err(e) => errors.push(e),
+                }
+            }
+
+            if let Err(e) = self.eval.evaluate(&self.context) {
+                errors.push(e.to_string());
+                return;
+            }
+        }
+
+        if !pending.is_empty() {
+            let names: Vec<&str> = pending.iter().map(|(name, _)| *name).collect();
+            errors.push(format!(
+                "The following config symbol(s) were not visible so were not updated: {}",
+                names.join(", ")
+            ));
+        }
+    }
+
+    /// Capture the values, ranges, and visibility reported to clients.
+    fn snapshot(&self) -> Snapshot {
+        let mut ranges = JsonMap::new();
+        for (id, symbol) in self.eval.table().symbols().iter().enumerate() {
+            if let Some((low, high)) = self.eval.symbol_state(id).range {
+                ranges.insert(symbol.name.clone(), JsonValue::Array(vec![json_number(low), json_number(high)]));
+            }
+        }
+
+        let mut visible = JsonMap::new();
+        self.visible(self.eval.table().menu(), &mut Vec::new(), &mut visible);
+
+        Snapshot {
+            values: json_values(&self.eval),
+            ranges,
+            visible,
+        }
+    }
+
+    /// Record the visibility of each node by id, returning whether any of them is visible. A menu is visible if any of
+    /// its children are.
+    fn visible(&self, nodes: &[MenuNode], slugs: &mut Vec<String>, result: &mut JsonMap<String, JsonValue>) -> bool {
+        let mut any_visible = false;
+
+        for node in nodes {
+            let (id, visible) = match &node.item {
+                MenuItem::Symbol {
+                    symbol,
+                    ..
+                } => (
+                    self.eval.table().symbols()[*symbol].name.clone(),
+                    self.eval.symbol_state(*symbol).visibility != Tristate::False,
+                ),
+
+                MenuItem::Choice(choice) => {
+                    let title = self.eval.table().choices()[*choice]
+                        .choice
+                        .prompt
+                        .as_ref()
+                        .map(|prompt| prompt.title.to_string())
+                        .unwrap_or_default();
+
+                    slugs.push(slug(&title));
+                    let id = slugs.join("-");
+                    self.visible(&node.children, slugs, result);
+                    slugs.pop();
+
+                    (id, self.eval.choice_state(*choice).visibility != Tristate::False)
+                }
+
+                MenuItem::Menu {
+                    prompt,
+                    ..
+                } => {
+                    slugs.push(slug(prompt));
+                    let id = slugs.join("-");
+                    let visible = self.visible(&node.children, slugs, result);
+                    slugs.pop();
+
+                    (id, visible)
+                }
+            };
+
+            result.insert(id, visible.into());
+            any_visible |= visible;
+        }
+
+        any_visible
+    }
+}
+
+/// Convert a value from a `set` request to the form used in `sdkconfig`.
+fn set_value(r#type: Type, name: &str, value: &JsonValue) -> Result<String, String> {
+    match (r#type, value) {
+        (Type::Bool | Type::Tristate, JsonValue::Bool(true)) => Ok("y".to_string()),
+        (Type::Bool | Type::Tristate, JsonValue::Bool(false)) => Ok("n".to_string()),
+        (Type::Bool | Type::Tristate, _) => Err(format!("Boolean symbol {name} only accepts true/false values")),
+        (Type::Hex, value) => {
+            let parsed = match value {
+                JsonValue::Number(n) => n.as_i64().map(i128::from),
+                JsonValue::String(s) => {
+                    let digits = s.strip_prefix("0x").or_else(|| s.strip_prefix("0X")).unwrap_or(s);
+                    i128::from_str_radix(digits, 16).ok()
+                }
+                _ => None,
+            };
+
+            match parsed {
+                Some(n) if n >= 0 => Ok(format!("{n:#x}")),
+                Some(n) => Err(format!(
+                    "Hex symbol {name} can accept a decimal integer or a string of hex digits, only. {name} value {n} \
+                     is negative"
+                )),
+                None => Err(format!(
+                    "Hex symbol {name} can accept a decimal integer or a string of hex digits, only. Invalid value \
+                     {value}"
+                )),
+            }
+        }
+        (_, JsonValue::String(s)) => Ok(s.clone()),
+        (_, value) => Ok(value.to_string()),
+    }
+}
+
+/// Returns the entries of `after` that are missing from or different in `before`.
+fn diff(before: &JsonMap<String, JsonValue>, after: &JsonMap<String, JsonValue>) -> JsonMap<String, JsonValue> {
+    after.iter().filter(|(k, v)| before.get(*k) != Some(*v)).map(|(k, v)| (k.clone(), v.clone())).collect()
+}
+
+/// Write a response followed by a blank line, as `confserver` does.
+fn write_response<W: Write>(response: &JsonValue, output: &mut W) -> IoResult<()> {
+    serde_json::to_writer(&mut *output, response)?;
+    output.write_all(b"\n\n")?;
+    output.flush()
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        crate::{
+            eval::{Evaluation, SymbolTable},
+            parser::{KConfig, PeekableChars},
+            sdkconfig::Renames,
+            ConfServer,
+        },
+        serde_json::{json, Value as JsonValue},
+        std::{collections::HashMap, env, fs, io::Cursor, path::Path},
+    };
+
+    const KCONFIG: &str = r##"
+menu "Serial"
+    config UART
+        bool "Enable UART"
+
+    config BAUD
+        int "Baud rate"
+        depends on UART
+        range 1200 115200 if !FAST
+        range 1200 921600
+        default 115200
+
+    config FAST
+        bool "Fast"
+        depends on UART
+
+    config BASE
+        hex "Base address"
+        default 0x1000
+endmenu
+"##;
+
+    fn server(dir: &Path) -> ConfServer<HashMap<String, String>> {
+        let context = HashMap::new();
+        let kconfig =
+            KConfig::from_str(PeekableChars::new(KCONFIG, Path::new("test")), Path::new("/tmp"), &context).unwrap();
+        let renames = Renames::parse("CONFIG_OLD_UART CONFIG_UART\n", Path::new("sdkconfig.rename")).unwrap();
+        let eval = Evaluation::new(SymbolTable::new(&kconfig));
+        ConfServer::new(eval, context, dir.join("sdkconfig"), renames).unwrap()
+    }
+
+    fn responses(output: Vec<u8>) -> Vec<JsonValue> {
+        String::from_utf8(output)
+            .unwrap()
+            .lines()
+            .filter(|line| !line.is_empty())
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect()
+    }
+
+    #[test_log::test]
+    fn confserver_v2() {
+        let dir = env::temp_dir().join(format!("kconfig-confserver-v2-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(dir.join("sdkconfig"), "CONFIG_OLD_UART=y\n").unwrap();
+        fs::write(dir.join("other"), "# CONFIG_UART is not set\n").unwrap();
+
+        let input = [
+            r#"{"version": 2, "set": {"FAST": true, "BAUD": 460800}}"#,
+            r#"{"version": 2, "set": {"BASE": "2000", "NOPE": 1}, "save": null}"#,
+            r#"{"version": 2, "set": {"UART": "yes"}}"#,
+            r#"{"version": 2, "load": "OTHER"}"#,
+            "not json",
+            r#"{"set": {"UART": false}}"#,
+            r#"{"version": 3}"#,
+        ]
+        .join("\n")
+        .replace("OTHER", dir.join("other").to_str().unwrap());
+
+        let mut output = Vec::new();
+        server(&dir).run(Cursor::new(input), &mut output).unwrap();
+        let responses = responses(output);
+
+        assert_eq!(
+            responses[0],
+            json!({
+                "version": 2,
+                "values": {"UART": true, "BAUD": 115200, "FAST": false, "BASE": 4096},
+                "ranges": {"BAUD": [1200, 115200]},
+                "visible": {"UART": true, "BAUD": true, "FAST": true, "BASE": true, "serial": true},
+            })
+        );
+
+        assert_eq!(
+            responses[1],
+            json!({
+                "version": 2,
+                "values": {"BAUD": 460800, "FAST": true},
+                "ranges": {"BAUD": [1200, 921600]},
+                "visible": {},
+            })
+        );
+
+        assert_eq!(
+            responses[2],
+            json!({
+                "version": 2,
+                "values": {"BASE": 8192},
+                "ranges": {},
+                "visible": {},
+                "error": ["The following config symbol(s) were not found: NOPE"],
+            })
+        );
+        let saved = fs::read_to_string(dir.join("sdkconfig")).unwrap();
+        assert!(saved.contains("CONFIG_BASE=0x2000\n"));
+        assert!(saved.contains("CONFIG_OLD_UART=y\n"));
+
+        assert_eq!(responses[3]["error"], json!(["Boolean symbol UART only accepts true/false values"]));
+
+        assert_eq!(
+            responses[4],
+            json!({
+                "version": 2,
+                "values": {"UART": false, "BASE": 4096},
+                "ranges": {},
+                "visible": {"BAUD": false, "FAST": false},
+            })
+        );
+
+        assert!(responses[5]["error"][0].as_str().unwrap().starts_with("JSON formatting error"));
+        assert_eq!(responses[6]["error"], json!(["All requests must have a 'version'"]));
+        assert_eq!(responses[7]["error"], json!(["Unsupported request version 3. Server supports versions 1-2"]));
+
+        _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test_log::test]
+    fn confserver_v1() {
+        let dir = env::temp_dir().join(format!("kconfig-confserver-v1-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+
+        let input = r#"{"version": 1, "set": {"UART": true}}
+{"version": 1, "set": {"BAUD": 9600, "UART": false}}
+"#;
+
+        let mut output = Vec::new();
+        server(&dir).with_version(1).run(Cursor::new(input), &mut output).unwrap();
+        let responses = responses(output);
+
+        assert_eq!(
+            responses[0],
+            json!({
+                "version": 1,
+                "values": {"UART": false, "BAUD": false, "FAST": false, "BASE": 4096},
+                "ranges": {},
+            })
+        );
+        assert_eq!(
+            responses[1],
+            json!({
+                "version": 1,
+                "values": {"UART": true, "BAUD": 115200},
+                "ranges": {"BAUD": [1200, 115200]},
+            })
+        );
+
+        // BAUD is set before UART is disabled, which hides it.
+        assert_eq!(
+            responses[2],
+            json!({
+                "version": 1,
+                "values": {"UART": false, "BAUD": false},
+                "ranges": {},
+            })
+        );
+
+        // Ranges of symbols visible at connect time are sent under their own key.
+        fs::write(dir.join("sdkconfig"), "CONFIG_UART=y\n").unwrap();
+        let mut output = Vec::new();
+        server(&dir).with_version(1).run(Cursor::new(""), &mut output).unwrap();
+        assert_eq!(
+            self::responses(output)[0],
+            json!({
+                "version": 1,
+                "values": {"UART": true, "BAUD": 115200, "FAST": false, "BASE": 4096},
+                "ranges": {"BAUD": [1200, 115200]},
+            })
+        );
+
+        _ = fs::remove_dir_all(&dir);
+    }
+}
@@ -1,12 +1,35 @@
-use std::{
-    collections::{BTreeMap, HashMap},
-    env::VarError,
+use {
+    crate::{
+        parser::{KConfigError, KConfigErrorKind, Location},
+        DiskFs, Include, KConfigFs,
+    },
+    log::debug,
+    serde_json::Value as JsonValue,
+    std::{
+        cell::RefCell,
+        collections::{BTreeMap, HashMap},
+        env::{self, VarError},
+        fmt::{Debug, Formatter, Result as FmtResult},
+        fs,
+        path::PathBuf,
+    },
 };
 
 /// A trait for performing variable lookups.
 pub trait Context {
     /// Returns the value of the given variable, or an error if the variable could not be found.
     fn var(&self, name: &str) -> Result<String, VarError>;
+
+    /// Returns the filesystem to read Kconfig files from. Defaults to the local disk.
+    fn fs(&self) -> &dyn KConfigFs {
+        &DiskFs
+    }
+
+    /// Called when a Kconfig file is read or an optional source is probed while loading a tree. Does nothing by
+    /// default; see [`IncludeRecorder`][crate::IncludeRecorder].
+    fn record_include(&self, include: Include) {
+        let _ = include;
+    }
 }
 
 /// A [context][Context] that uses the environment for variable lookups.
@@ -30,6 +53,200 @@ impl Context for HashMap<String, String> {
     }
 }
 
+impl<C: Context + ?Sized> Context for &C {
+    fn var(&self, name: &str) -> Result<String, VarError> {
+        (**self).var(name)
+    }
+
+    fn fs(&self) -> &dyn KConfigFs {
+        (**self).fs()
+    }
+
+    fn record_include(&self, include: Include) {
+        (**self).record_include(include)
+    }
+}
+
+/// A stack of contexts. Variables are looked up in the first layer that has them, so earlier layers override later
+/// ones; for example, command line assignments over a project file over the [system environment][SystemContext].
+///
+/// Kconfig files are read from the [filesystem][Context::fs] of the first layer, and included files are reported to
+/// every layer.
+#[derive(Default)]
+pub struct LayeredContext<'a> {
+    layers: Vec<Box<dyn Context + 'a>>,
+}
+
+/// A [context][Context] that records every variable looked up in another context, along with its value if it was
+/// found.
+///
+/// This shows exactly which variables influenced a configuration, so they can be pinned for reproducible builds.
+pub struct RecordingContext<'a, C: ?Sized> {
+    context: &'a C,
+    lookups: RefCell<BTreeMap<String, Option<String>>>,
+}
+
+/// The variables a command line tool loads and evaluates a Kconfig tree with: the process environment, then the
+/// variables in a JSON file (`--env-file`), then `NAME=VALUE` assignments (`--env`), with later ones taking precedence.
+///
+/// With the `clap` feature, this can be flattened into a tool's options.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+#[cfg_attr(feature = "clap", derive(clap::Args))]
+pub struct ContextArgs {
+    /// Environment variables to set when evaluating the Kconfig files, as `NAME=VALUE`.
+    #[cfg_attr(feature = "clap", arg(long, value_parser = parse_var_assignment))]
+    pub env: Vec<(String, String)>,
+
+    /// A JSON file containing an object of environment variables to set.
+    #[cfg_attr(feature = "clap", arg(long))]
+    pub env_file: Option<PathBuf>,
+}
+
+impl<'a> LayeredContext<'a> {
+    /// Create a context with no layers.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a layer below the existing layers.
+    pub fn with_layer(mut self, layer: impl Context + 'a) -> Self {
+        self.layers.push(Box::new(layer));
+        self
+    }
+}
+
+impl Debug for LayeredContext<'_> {
+    fn fmt(&self, f: &mut Formatter) -> FmtResult {
+        f.debug_struct("LayeredContext").field("layers", &self.layers.len()).finish()
+    }
+}
+
+impl Context for LayeredContext<'_> {
+    fn var(&self, name: &str) -> Result<String, VarError> {
+        for layer in &self.layers {
+            match layer.var(name) {
+                Err(VarError::NotPresent) => continue,
+                result => return result,
+            }
+        }
+
+        Err(VarError::NotPresent)
+    }
+
+    fn fs(&self) -> &dyn KConfigFs {
+        match self.layers.first() {
+            Some(layer) => layer.fs(),
+            None => &DiskFs,
+        }
+    }
+
+    fn record_include(&self, include: Include) {
+        for layer in &self.layers {
+            layer.record_include(include.clone());
+        }
+    }
+}
+
+impl<'a, C: ?Sized> RecordingContext<'a, C> {
+    /// Create a context that records lookups in `context`.
+    pub fn new(context: &'a C) -> Self {
+        Self {
+            context,
+            lookups: RefCell::new(BTreeMap::new()),
+        }
+    }
+
+    /// Returns the variables looked up so far in name order, with their values, or `None` if they were not set.
+    pub fn lookups(&self) -> BTreeMap<String, Option<String>> {
+        self.lookups.borrow().clone()
+    }
+
+    /// Returns the variables that were found and their values. The result can itself be used as a context to
+    /// reproduce the configuration.
+    pub fn found(&self) -> BTreeMap<String, String> {
+        self.lookups.borrow().iter().filter_map(|(name, value)| Some((name.clone(), value.clone()?))).collect()
+    }
+
+    /// Returns the names of the variables that were looked up but not set.
+    pub fn missing(&self) -> Vec<String> {
+        self.lookups.borrow().iter().filter(|(_, value)| value.is_none()).map(|(name, _)| name.clone()).collect()
+    }
+}
+
+impl<C: Context + ?Sized> Context for RecordingContext<'_, C> {
+    fn var(&self, name: &str) -> Result<String, VarError> {
+        let result = self.context.var(name);
+        debug!("Looked up variable {name}: {result:?}");
+        self.lookups.borrow_mut().insert(name.to_string(), result.as_ref().ok().cloned());
+        result
+    }
+
+    fn fs(&self) -> &dyn KConfigFs {
+        self.context.fs()
+    }
+
+    fn record_include(&self, include: Include) {
+        self.context.record_include(include);
+    }
+}
+
+impl ContextArgs {
+    /// Returns the variables: the process environment, overridden by the `--env-file` file and then by the `--env`
+    /// assignments.
+    pub fn context(&self) -> Result<HashMap<String, String>, KConfigError> {
+        let mut context: HashMap<String, String> = env::vars().collect();
+
+        if let Some(env_file) = &self.env_file {
+            let vars = match serde_json::from_str(&fs::read_to_string(env_file)?) {
+                Ok(JsonValue::Object(vars)) => vars,
+                Ok(_) => {
+                    return Err(KConfigError::new(
+                        KConfigErrorKind::Parse("expected a JSON object".to_string()),
+                        Location::new(env_file, 1, 1),
+                    ))
+                }
+                Err(e) => {
+                    return Err(KConfigError::new(
+                        KConfigErrorKind::Parse(e.to_string()),
+                        Location::new(env_file, e.line(), e.column()),
+                    ))
+                }
+            };
+
+            for (name, value) in vars {
+                let value = match value {
+                    JsonValue::String(s) => s,
+                    value => value.to_string(),
+                };
+                context.insert(name, value);
+            }
+        }
+
+        context.extend(self.env.iter().cloned());
+        Ok(context)
+    }
+}
+
+/// Returns the directory that relative paths in `source` statements are relative to: `$srctree` as in kconfiglib, or
+/// the current directory if it is not set.
+pub fn srctree<C>(context: &C) -> Result<PathBuf, KConfigError>
+where
+    C: Context,
+{
+    match context.var("srctree") {
+        Ok(srctree) => Ok(PathBuf::from(srctree)),
+        Err(_) => Ok(env::current_dir()?),
+    }
+}
+
+/// Parse a `NAME=VALUE` variable assignment, as given to `--env`.
+pub fn parse_var_assignment(s: &str) -> Result<(String, String), String> {
+    match s.split_once('=') {
+        Some((name, value)) => Ok((name.to_string(), value.to_string())),
+        None => Err(format!("expected NAME=VALUE, found {s:?}")),
+    }
+}
+
 /// Create a closure around a context for [`env_with_context`][shellexpand::env_with_context].
 pub(crate) fn context_closure<C>(context: &C) -> impl Fn(&str) -> Result<Option<String>, VarError> + '_
 where
@@ -37,3 +254,126 @@ where
 {
     move |var| context.var(var).map(Some)
 }
+
+/// Expand `$VAR`, `${VAR}`, and `$(VAR)` references in a Kconfig string constant using the given context.
+///
+/// Variables that are not set expand to an empty string, matching the behavior of the Kconfig preprocessor.
+pub(crate) fn expand_vars<C>(s: &str, context: &C) -> String
+where
+    C: Context,
+{
+    let mut result = String::with_capacity(s.len());
+    let mut rest = s;
+
+    while let Some(pos) = rest.find('$') {
+        result.push_str(&rest[..pos]);
+        rest = &rest[pos + 1..];
+
+        let (name, remaining) = match rest.chars().next() {
+            Some(open @ ('{' | '(')) => {
+                let close = if open == '{' {
+                    '}'
+                } else {
+                    ')'
+                };
+
+                match rest.find(close) {
+                    Some(end) => (&rest[1..end], &rest[end + 1..]),
+                    None => {
+                        result.push('$');
+                        continue;
+                    }
+                }
+            }
+            _ => {
+                let end = rest.find(|c: char| !(c.is_ascii_alphanumeric() || c == '_')).unwrap_or(rest.len());
+                (&rest[..end], &rest[end..])
+            }
+        };
+
+        if name.is_empty() {
+            result.push('$');
+            continue;
+        }
+
+        result.push_str(&context.var(name).unwrap_or_default());
+        rest = remaining;
+    }
+
+    result.push_str(rest);
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        crate::{
+            eval::{Evaluation, SymbolTable},
+            parse_var_assignment,
+            parser::KConfig,
+            Context, ContextArgs, FsContext, LayeredContext, MemoryFs, RecordingContext,
+        },
+        std::{
+            collections::HashMap,
+            env::{self, VarError},
+            fs,
+            path::Path,
+        },
+    };
+
+    #[test_log::test]
+    fn context_args() {
+        let env_file = env::temp_dir().join(format!("kconfig-context-args-{}.json", std::process::id()));
+        fs::write(&env_file, r#"{"IDF_TARGET": "esp32", "IDF_ENV_FPGA": true}"#).unwrap();
+
+        let args = ContextArgs {
+            env: vec![parse_var_assignment("IDF_TARGET=esp32c3").unwrap()],
+            env_file: Some(env_file.clone()),
+        };
+        let context = args.context().unwrap();
+        assert_eq!(context["IDF_TARGET"], "esp32c3");
+        assert_eq!(context["IDF_ENV_FPGA"], "true");
+        assert!(parse_var_assignment("IDF_TARGET").is_err());
+
+        fs::write(&env_file, "[1, 2]").unwrap();
+        let e = args.context().unwrap_err();
+        assert_eq!(e.to_string(), format!("{} 1:1: Parse error: expected a JSON object", env_file.display()));
+
+        fs::remove_file(&env_file).unwrap();
+    }
+
+    #[test_log::test]
+    fn layered_context() {
+        let cli: HashMap<String, String> = [("IDF_TARGET".to_string(), "esp32c3".to_string())].into_iter().collect();
+        let project: HashMap<String, String> =
+            [("IDF_TARGET".to_string(), "esp32".to_string()), ("IDF_INIT_VERSION".to_string(), "5.3.0".to_string())]
+                .into_iter()
+                .collect();
+
+        let context = LayeredContext::new().with_layer(&cli).with_layer(&project);
+        assert_eq!(context.var("IDF_TARGET").unwrap(), "esp32c3");
+        assert_eq!(context.var("IDF_INIT_VERSION").unwrap(), "5.3.0");
+        assert_eq!(context.var("IDF_PATH"), Err(VarError::NotPresent));
+        assert_eq!(LayeredContext::new().var("IDF_TARGET"), Err(VarError::NotPresent));
+    }
+
+    #[test_log::test]
+    fn recording_context() {
+        let fs = MemoryFs::new()
+            .with_file("/src/Kconfig", "source \"$IDF_TARGET/Kconfig\"\n")
+            .with_file("/src/esp32/Kconfig", "config A\n    string \"a\"\n    default \"$(IDF_VERSION_SUFFIX)\"\n");
+
+        let vars: HashMap<String, String> = [("IDF_TARGET".to_string(), "esp32".to_string())].into_iter().collect();
+        let context = FsContext::new(&vars, &fs);
+        let recorder = RecordingContext::new(&context);
+        let kconfig = KConfig::from_file(Path::new("/src/Kconfig"), Path::new("/src"), &recorder).unwrap();
+        Evaluation::new(SymbolTable::new(&kconfig)).evaluate(&recorder).unwrap();
+
+        assert_eq!(
+            recorder.lookups().into_iter().collect::<Vec<_>>(),
+            vec![("IDF_TARGET".to_string(), Some("esp32".to_string())), ("IDF_VERSION_SUFFIX".to_string(), None),]
+        );
+        assert_eq!(recorder.found().get("IDF_TARGET").map(String::as_str), Some("esp32"));
+        assert_eq!(recorder.missing(), vec!["IDF_VERSION_SUFFIX".to_string()]);
+    }
+}
@@ -0,0 +1,655 @@
+use {
+    crate::{
+        parser::{
+            inline_fs, ConfigDefault, CstBlock, CstFile, CstLine, CstNode, KConfig, KConfigError, KConfigErrorKind,
+            LocExpr, LocToken, PeekableChars, Source, Token, TokenLine, INLINE_PREFIX,
+        },
+        Context, FsContext,
+    },
+    log::{debug, trace},
+    std::{
+        collections::HashMap,
+        fs,
+        io::ErrorKind as IoErrorKind,
+        path::{Path, PathBuf},
+    },
+};
+
+/// Indentation added for each nesting level when the existing indentation can't be determined.
+const INDENT: &str = "    ";
+
+/// A Kconfig tree loaded losslessly for programmatic editing.
+///
+/// Each file in the tree is kept as a [`CstFile`]. Edits only touch the lines they affect, so writing the tree back
+/// with [`write()`][KConfigEditor::write] produces minimal diffs; comments, blank lines, and formatting elsewhere are
+/// left untouched.
+#[derive(Debug, Default)]
+pub struct KConfigEditor {
+    files: Vec<EditFile>,
+}
+
+/// A single file within a [`KConfigEditor`].
+#[derive(Debug)]
+struct EditFile {
+    /// The path to the file.
+    path: PathBuf,
+
+    /// The contents of the file when it was last read or written.
+    original: String,
+
+    /// The current (possibly edited) syntax tree for the file.
+    cst: CstFile,
+}
+
+impl KConfigEditor {
+    /// Load a Kconfig tree for editing, starting with the given Kconfig file.
+    ///
+    /// This follows `source` statements (and their variants) the same way [`KConfig::from_file`] does, including the
+    /// files named by inline sources. Missing optional sources are skipped. A file that is sourced more than once is
+    /// only loaded once.
+    pub fn from_file<C>(filename: &Path, base_dir: &Path, context: &C) -> Result<Self, KConfigError>
+    where
+        C: Context,
+    {
+        let mut result = Self::default();
+        result.load(filename, base_dir, context)?;
+        Ok(result)
+    }
+
+    /// Load a single file (and any files it sources) into the editor.
+    pub fn load<C>(&mut self, filename: &Path, base_dir: &Path, context: &C) -> Result<(), KConfigError>
+    where
+        C: Context,
+    {
+        self.load_file(filename, base_dir, context, true)
+    }
+
+    /// Load a file and the files it sources. Inline sources are read through [`inline_fs()`] like other files, but are
+    /// not kept for editing since they have nowhere to be written back to.
+    fn load_file(
+        &mut self,
+        filename: &Path,
+        base_dir: &Path,
+        context: &dyn Context,
+        editable: bool,
+    ) -> Result<(), KConfigError> {
+        if editable && self.files.iter().any(|f| f.path == filename) {
+            return Ok(());
+        }
+
+        trace!("Loading {filename:?} for editing");
+        let original = context.fs().read_to_string(filename)?;
+        let cst = CstFile::parse(PeekableChars::new(&original, filename))?;
+
+        let mut sources = Vec::new();
+        visit_lines(&cst.nodes, &mut |line| {
+            if line.keyword().map(Token::is_source).unwrap_or(false) {
+                sources.push(line_tokens(line));
+            }
+        });
+
+        if editable {
+            self.files.push(EditFile {
+                path: filename.to_path_buf(),
+                original,
+                cst,
+            });
+        }
+
+        for tokens in sources {
+            let source = Source::parse(&mut TokenLine::new(&tokens), base_dir)?;
+            let s_filename = source.expand_filename(&context)?;
+
+            if let Some(text) = Source::inline_text(&s_filename) {
+                debug!("Loading inline source in {filename:?}");
+                let fs = inline_fs(text, context.fs());
+                let context = FsContext::new(context, &fs);
+                self.load_file(Path::new(INLINE_PREFIX), base_dir, &context, false)?;
+                continue;
+            }
+
+            let source_dir = source.source_dir(base_dir);
+            for s_filename in source.paths(&s_filename, base_dir, &context)? {
+                match self.load_file(&s_filename, source_dir, context, true) {
+                    Err(e)
+                        if source.optional
+                            && matches!(&e.kind, KConfigErrorKind::Io(io) if io.kind() == IoErrorKind::NotFound) =>
+                    {
+                        debug!("Ignoring NotFound error for optional source file: {s_filename:?}");
+                    }
+                    result => result?,
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Returns the files in the tree along with their current syntax trees, in the order they were loaded.
+    pub fn files(&self) -> impl Iterator<Item = (&Path, &CstFile)> {
+        self.files.iter().map(|f| (f.path.as_path(), &f.cst))
+    }
+
+    /// Returns the files that have been modified since they were loaded or last written.
+    pub fn modified_files(&self) -> impl Iterator<Item = &Path> {
+        self.files.iter().filter(|f| f.cst.to_string() != f.original).map(|f| f.path.as_path())
+    }
+
+    /// Set the unconditional default for a symbol.
+    ///
+    /// The existing unconditional `default` line of the first definition of `symbol` is replaced, keeping any trailing
+    /// comment. If there is none, a new `default` line is added after any conditional defaults (or before the help
+    /// text if there are none). `value` is the text of the default expression, e.g. `y` or `0x1000`.
+    pub fn set_default(&mut self, symbol: &str, value: &str) -> Result<(), KConfigError> {
+        let (path, block) = self.find_config_mut(symbol)?;
+        let mut line = parse_attribute(&format!("{}default {value}\n", attribute_indent(block)), path)?;
+        ConfigDefault::parse(&mut TokenLine::new(&line_tokens(&line)))?;
+
+        let existing = block.children.iter().position(|node| match node {
+            CstNode::Line(l) => {
+                l.keyword() == Some(&Token::Default) && !l.tokens.iter().any(|t| t.token.token == Token::If)
+            }
+            _ => false,
+        });
+
+        if let Some(pos) = existing {
+            let CstNode::Line(old) = &block.children[pos] else {
+                unreachable!();
+            };
+
+            if old.comment.is_some() {
+                line.trailing = old.trailing.clone();
+                line.comment = old.comment.clone();
+            }
+
+            line.eol = old.eol.clone();
+            block.children[pos] = CstNode::Line(line);
+        } else {
+            let pos = last_position(block, &Token::Default).unwrap_or_else(|| help_position(block));
+            insert_line(block, pos, line);
+        }
+
+        Ok(())
+    }
+
+    /// Add a `depends on` line to the first definition of a symbol.
+    ///
+    /// The line is added after any existing `depends on` lines, or after the type and prompt if there are none.
+    /// `expr` is the text of the dependency expression.
+    pub fn add_depends_on(&mut self, symbol: &str, expr: &str) -> Result<(), KConfigError> {
+        let (path, block) = self.find_config_mut(symbol)?;
+        let line = parse_attribute(&format!("{}depends on {expr}\n", attribute_indent(block)), path)?;
+        LocExpr::parse_depends_on(&mut TokenLine::new(&line_tokens(&line)))?;
+
+        let pos = last_position(block, &Token::Depends)
+            .or_else(|| {
+                block
+                    .children
+                    .iter()
+                    .position(|node| matches!(node, CstNode::Line(l) if l.tokens.first().map(|t| t.token.is_type() || t.token.token == Token::Prompt).unwrap_or(false)))
+                    .map(|i| i + 1)
+            })
+            .unwrap_or(0);
+
+        insert_line(block, pos, line);
+        Ok(())
+    }
+
+    /// Rename a symbol everywhere it is referenced in every file of the tree.
+    ///
+    /// This returns the number of references that were changed. It is an error if `new_name` is already defined or
+    /// referenced anywhere in the tree, since the two symbols would be merged.
+    pub fn rename_symbol(&mut self, old_name: &str, new_name: &str) -> Result<usize, KConfigError> {
+        let new_token = parse_attribute(new_name, Path::new("rename"))?;
+        if new_token.tokens.len() != 1 || new_token.tokens[0].token.symbol_value().is_none() {
+            return Err(KConfigError::syntax(
+                format!("Invalid symbol name: {new_name:?}"),
+                new_token.location().unwrap(),
+            ));
+        }
+
+        let new_symbol = Token::Symbol(new_name.to_string());
+        for file in &self.files {
+            let mut existing = None;
+            visit_lines(&file.cst.nodes, &mut |line| {
+                if existing.is_none() {
+                    existing =
+                        line.tokens.iter().find(|token| token.token.token == new_symbol).map(|t| t.token.location);
+                }
+            });
+
+            if let Some(location) = existing {
+                return Err(KConfigError::duplicate_symbol(new_name, location));
+            }
+        }
+
+        let mut count = 0;
+
+        for file in self.files.iter_mut() {
+            visit_lines_mut(&mut file.cst.nodes, &mut |line| {
+                for token in line.tokens.iter_mut() {
+                    if token.token.token == Token::Symbol(old_name.to_string()) {
+                        token.text = new_name.to_string();
+                        token.token.token = Token::Symbol(new_name.to_string());
+                        count += 1;
+                    }
+                }
+            });
+        }
+
+        if count == 0 {
+            return Err(KConfigError::unknown_symbol(old_name));
+        }
+
+        Ok(count)
+    }
+
+    /// Insert new entries (typically a `config` block) at the end of the first menu with the given prompt.
+    ///
+    /// `text` is re-indented to match the other entries in the menu, and a blank line is added before it if needed.
+    pub fn insert_config(&mut self, menu_prompt: &str, text: &str) -> Result<(), KConfigError> {
+        for file in self.files.iter_mut() {
+            let Some(menu) = find_block_mut(&mut file.cst.nodes, &|block| {
+                *block.keyword() == Token::Menu
+                    && block
+                        .header
+                        .tokens
+                        .get(1)
+                        .map(|t| t.token.token == Token::StrLit(menu_prompt.to_string()))
+                        .unwrap_or(false)
+            }) else {
+                continue;
+            };
+
+            let indent = menu
+                .children
+                .iter()
+                .find_map(|node| match node {
+                    CstNode::Line(l) => l.tokens.first(),
+                    CstNode::Block(b) => b.header.tokens.first(),
+                })
+                .map(|t| t.leading.clone())
+                .unwrap_or_else(|| format!("{}{INDENT}", menu.header.tokens[0].leading));
+
+            let text = reindent(text, &indent);
+            KConfig::from_str_raw(
+                PeekableChars::new(&text, &file.path),
+                Path::new(""),
+                &HashMap::<String, String>::new(),
+            )?;
+            let inserted = CstFile::parse(PeekableChars::new(&text, &file.path))?;
+
+            if menu.children.last().map(|node| !node.is_trivia()).unwrap_or(false) {
+                menu.children.push(CstNode::Line(CstLine {
+                    eol: "\n".to_string(),
+                    ..Default::default()
+                }));
+            }
+
+            menu.children.extend(inserted.nodes);
+            return Ok(());
+        }
+
+        Err(KConfigError::unknown_menu(menu_prompt))
+    }
+
+    /// Returns the paths and new contents of the modified files, treating them as saved.
+    ///
+    /// Use this instead of [`write()`][Self::write] for trees loaded through a filesystem other than the local disk,
+    /// such as a [`MemoryFs`][crate::MemoryFs], and persist the returned texts wherever they came from.
+    pub fn take_modified(&mut self) -> Vec<(PathBuf, String)> {
+        let mut modified = Vec::new();
+
+        for file in self.files.iter_mut() {
+            let current = file.cst.to_string();
+            if current != file.original {
+                file.original = current.clone();
+                modified.push((file.path.clone(), current));
+            }
+        }
+
+        modified
+    }
+
+    /// Write any modified files back to the local disk, returning the paths of the files that were written.
+    ///
+    /// The files are written with [`std::fs::write`] regardless of the filesystem they were loaded through, so this is
+    /// only suitable for trees loaded from the disk; see [`take_modified()`][Self::take_modified] for others. Inline
+    /// sources are never written since they aren't kept for editing.
+    pub fn write(&mut self) -> Result<Vec<PathBuf>, KConfigError> {
+        let mut written = Vec::new();
+
+        for file in self.files.iter_mut() {
+            let current = file.cst.to_string();
+            if current != file.original {
+                debug!("Writing {:?}", file.path);
+                fs::write(&file.path, &current)?;
+                file.original = current;
+                written.push(file.path.clone());
+            }
+        }
+
+        Ok(written)
+    }
+
+    /// Find the first `config` or `menuconfig` block defining the given symbol.
+    fn find_config_mut(&mut self, symbol: &str) -> Result<(&Path, &mut CstBlock), KConfigError> {
+        for file in self.files.iter_mut() {
+            let found = find_block_mut(&mut file.cst.nodes, &|block| {
+                matches!(block.keyword(), Token::Config | Token::MenuConfig)
+                    && block
+                        .header
+                        .tokens
+                        .get(1)
+                        .map(|t| t.token.token == Token::Symbol(symbol.to_string()))
+                        .unwrap_or(false)
+            });
+
+            if let Some(block) = found {
+                return Ok((file.path.as_path(), block));
+            }
+        }
+
+        Err(KConfigError::unknown_symbol(symbol))
+    }
+}
+
+/// Call `f` on every line (block headers, attributes, and block ends) in the given nodes.
+fn visit_lines<'a>(nodes: &'a [CstNode], f: &mut impl FnMut(&'a CstLine)) {
+    for node in nodes {
+        match node {
+            CstNode::Line(line) => f(line),
+            CstNode::Block(block) => {
+                f(&block.header);
+                visit_lines(&block.children, f);
+                if let Some(end) = &block.end {
+                    f(end);
+                }
+            }
+        }
+    }
+}
+
+/// Call `f` on every line (block headers, attributes, and block ends) in the given nodes, allowing changes.
+fn visit_lines_mut(nodes: &mut [CstNode], f: &mut impl FnMut(&mut CstLine)) {
+    for node in nodes {
+        match node {
+            CstNode::Line(line) => f(line),
+            CstNode::Block(block) => {
+                f(&mut block.header);
+                visit_lines_mut(&mut block.children, f);
+                if let Some(end) = &mut block.end {
+                    f(end);
+                }
+            }
+        }
+    }
+}
+
+/// Find the first block (searching depth-first) that matches the given predicate.
+fn find_block_mut<'a>(nodes: &'a mut [CstNode], predicate: &impl Fn(&CstBlock) -> bool) -> Option<&'a mut CstBlock> {
+    for node in nodes {
+        if let CstNode::Block(block) = node {
+            if predicate(block) {
+                return Some(block);
+            }
+
+            if let Some(found) = find_block_mut(&mut block.children, predicate) {
+                return Some(found);
+            }
+        }
+    }
+
+    None
+}
+
+/// Returns the tokens of a line without their formatting.
+fn line_tokens(line: &CstLine) -> Vec<LocToken> {
+    line.tokens.iter().map(|t| t.token.clone()).collect()
+}
+
+/// Parse a single attribute line.
+fn parse_attribute(text: &str, path: &Path) -> Result<CstLine, KConfigError> {
+    let cst = CstFile::parse(PeekableChars::new(text, path))?;
+
+    match <[CstNode; 1]>::try_from(cst.nodes) {
+        Ok([CstNode::Line(line)]) if !line.is_trivia() => Ok(line),
+        _ => Err(KConfigError::syntax(
+            format!("Expected a single attribute: {text:?}"),
+            crate::parser::Location::new(path, 1, 1),
+        )),
+    }
+}
+
+/// Returns the indentation used for the attributes of a block.
+fn attribute_indent(block: &CstBlock) -> String {
+    block
+        .children
+        .iter()
+        .find_map(|node| node.as_line().and_then(|l| l.tokens.first()))
+        .map(|t| t.leading.clone())
+        .unwrap_or_else(|| format!("{}{INDENT}", block.header.tokens[0].leading))
+}
+
+/// Returns the position just after the last attribute starting with the given keyword.
+fn last_position(block: &CstBlock, keyword: &Token) -> Option<usize> {
+    block.children.iter().rposition(|node| node.as_line().and_then(|l| l.keyword()) == Some(keyword)).map(|i| i + 1)
+}
+
+/// Returns the position of the help text, or the end of the attributes if there is none.
+fn help_position(block: &CstBlock) -> usize {
+    block
+        .children
+        .iter()
+        .position(|node| node.as_line().map(|l| l.is_help()).unwrap_or(false))
+        .unwrap_or(block.children.len())
+}
+
+/// Insert a line into a block, making sure the preceding line is terminated.
+fn insert_line(block: &mut CstBlock, pos: usize, mut line: CstLine) {
+    let previous = if pos == 0 {
+        Some(&mut block.header)
+    } else {
+        match &mut block.children[pos - 1] {
+            CstNode::Line(l) => Some(l),
+            CstNode::Block(_) => None,
+        }
+    };
+
+    if let Some(previous) = previous {
+        if previous.eol.is_empty() && previous.help.is_empty() {
+            previous.eol = "\n".to_string();
+            line.eol.clear();
+        }
+    }
+
+    block.children.insert(pos, CstNode::Line(line));
+}
+
+/// Replace the common indentation of the non-blank lines in `text` with `indent`.
+fn reindent(text: &str, indent: &str) -> String {
+    let common = text
+        .lines()
+        .filter(|line| !line.trim().is_empty())
+        .map(|line| &line[..line.len() - line.trim_start().len()])
+        .min_by_key(|prefix| prefix.len())
+        .unwrap_or("");
+
+    let mut result = String::with_capacity(text.len());
+    for line in text.lines() {
+        if !line.trim().is_empty() {
+            result.push_str(indent);
+            result.push_str(line.strip_prefix(common).unwrap_or(line.trim_start()));
+        }
+        result.push('\n');
+    }
+
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        crate::{FsContext, KConfigEditor, MemoryFs},
+        std::{
+            collections::HashMap,
+            env, fs,
+            path::{Path, PathBuf},
+        },
+    };
+
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir = env::temp_dir().join(format!("kconfig-edit-{name}-{}", std::process::id()));
+        _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    #[test_log::test]
+    fn edit_minimal_diffs() {
+        let dir = temp_dir("minimal");
+        let top = dir.join("Kconfig");
+        let sub = dir.join("Kconfig.sub");
+
+        fs::write(
+            &top,
+            r##"mainmenu "Test"
+
+# Main options
+menu "Main"
+    config FOO
+        bool "Foo"   # keep me
+        default n # old default
+        help
+            Foo help.
+
+    config BAR
+        int "Bar"
+        default 3 if FOO
+
+endmenu
+
+rsource "Kconfig.sub"
+osource "missing/Kconfig"
+"##,
+        )
+        .unwrap();
+
+        fs::write(
+            &sub,
+            r##"config BAZ
+    bool
+    select FOO if !FOO_DISABLED
+"##,
+        )
+        .unwrap();
+
+        let context = HashMap::<String, String>::new();
+        let mut editor = KConfigEditor::from_file(&top, &dir, &context).unwrap();
+        assert_eq!(editor.files().count(), 2);
+
+        editor.set_default("FOO", "y if BAZ").unwrap();
+        editor.set_default("BAR", "7").unwrap();
+        editor.add_depends_on("BAR", "BAZ").unwrap();
+        assert_eq!(editor.rename_symbol("FOO", "FOO_ENABLE").unwrap(), 3);
+        editor.insert_config("Main", "config QUX\n    string \"Qux\"\n    default \"qux\"\n").unwrap();
+        assert!(editor.set_default("NOPE", "y").is_err());
+        assert!(editor.insert_config("Nope", "config NOPE\n").is_err());
+
+        let written = editor.write().unwrap();
+        assert_eq!(written.len(), 2);
+        assert_eq!(editor.modified_files().count(), 0);
+
+        assert_eq!(
+            fs::read_to_string(&top).unwrap(),
+            r##"mainmenu "Test"
+
+# Main options
+menu "Main"
+    config FOO_ENABLE
+        bool "Foo"   # keep me
+        default y if BAZ # old default
+        help
+            Foo help.
+
+    config BAR
+        int "Bar"
+        depends on BAZ
+        default 3 if FOO_ENABLE
+        default 7
+
+    config QUX
+        string "Qux"
+        default "qux"
+endmenu
+
+rsource "Kconfig.sub"
+osource "missing/Kconfig"
+"##
+        );
+
+        assert_eq!(
+            fs::read_to_string(&sub).unwrap(),
+            r##"config BAZ
+    bool
+    select FOO_ENABLE if !FOO_DISABLED
+"##
+        );
+
+        _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test_log::test]
+    fn edit_inline_sources() {
+        let dir = temp_dir("inline");
+        let top = dir.join("Kconfig");
+        let comp = dir.join("components/comp/Kconfig");
+        fs::create_dir_all(comp.parent().unwrap()).unwrap();
+
+        fs::write(&top, "config FOO\n    bool \"Foo\"\n\nsource \"$COMPONENT_KCONFIGS_SOURCE_FILE\"\n").unwrap();
+        fs::write(&comp, "config COMP\n    bool \"Comp\"\n    depends on FOO\n").unwrap();
+
+        let mut context = HashMap::<String, String>::new();
+        context.insert(
+            "COMPONENT_KCONFIGS_SOURCE_FILE".to_string(),
+            "inline:source \"components/comp/Kconfig\"\n".to_string(),
+        );
+
+        let mut editor = KConfigEditor::from_file(&top, &dir, &context).unwrap();
+        assert_eq!(editor.files().count(), 2);
+
+        let e = editor.rename_symbol("COMP", "FOO").unwrap_err();
+        assert_eq!(e.to_string(), format!("{} 1:8: Symbol already exists: FOO", top.display()));
+
+        assert_eq!(editor.rename_symbol("FOO", "FOO_ENABLE").unwrap(), 2);
+        editor.write().unwrap();
+        assert_eq!(fs::read_to_string(&comp).unwrap(), "config COMP\n    bool \"Comp\"\n    depends on FOO_ENABLE\n");
+
+        _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test_log::test]
+    fn edit_memory_fs() {
+        let fs = MemoryFs::new()
+            .with_file("/src/Kconfig", "config FOO\n    bool \"Foo\"\n\nsource \"$SUB\"\n")
+            .with_file("/src/sub/Kconfig", "config BAR\n    bool \"Bar\"\n    depends on FOO\n");
+        let mut context = HashMap::<String, String>::new();
+        context.insert("SUB".to_string(), "sub/Kconfig".to_string());
+        let context = FsContext::new(&context, &fs);
+
+        let mut editor = KConfigEditor::from_file(Path::new("/src/Kconfig"), Path::new("/src"), &context).unwrap();
+        assert_eq!(editor.rename_symbol("FOO", "FOO_ENABLE").unwrap(), 2);
+        assert_eq!(
+            editor.take_modified(),
+            vec![
+                (PathBuf::from("/src/Kconfig"), "config FOO_ENABLE\n    bool \"Foo\"\n\nsource \"$SUB\"\n".to_string()),
+                (
+                    PathBuf::from("/src/sub/Kconfig"),
+                    "config BAR\n    bool \"Bar\"\n    depends on FOO_ENABLE\n".to_string()
+                ),
+            ]
+        );
+        assert_eq!(editor.modified_files().count(), 0);
+        assert!(editor.take_modified().is_empty());
+    }
+}
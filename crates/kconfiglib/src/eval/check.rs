@@ -0,0 +1,387 @@
+use {
+    crate::{
+        eval::{parse_int, parse_tristate, tristate_str, Evaluation, Value},
+        parser::{Location, Tristate, Type},
+        sdkconfig::{quote, SdkConfig, CONFIG_PREFIX},
+    },
+    serde_json::{Map as JsonMap, Value as JsonValue},
+    std::{
+        collections::HashMap,
+        fmt::{Display, Formatter, Result as FmtResult},
+    },
+};
+
+/// Problems found by checking the assignments in an `sdkconfig` file against a Kconfig tree.
+///
+/// Each problem is a value that will not survive the next configuration run: the build silently drops or resets it.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct ConfigCheck {
+    /// The problems, in the order of the assignments in the file.
+    pub problems: Vec<ConfigProblem>,
+}
+
+/// A problem with an assignment in an `sdkconfig` file.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct ConfigProblem {
+    /// The name of the symbol (without the `CONFIG_` prefix).
+    pub name: String,
+
+    /// The value assigned, as written (without quotes for strings).
+    pub value: String,
+
+    /// The type of the symbol, or [`Type::Unknown`] if the symbol does not exist.
+    pub r#type: Type,
+
+    /// The location of the assignment.
+    pub location: Location,
+
+    /// What is wrong with the assignment.
+    pub kind: ProblemKind,
+}
+
+/// What is wrong with an assignment in an `sdkconfig` file.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum ProblemKind {
+    /// The symbol is not defined in the Kconfig tree.
+    UnknownSymbol,
+
+    /// The value is not valid for the type of the symbol, such as a string for an `int` symbol.
+    Malformed,
+
+    /// The value is outside the active `range` of an `int` or `hex` symbol.
+    OutOfRange {
+        /// The lower bound of the range.
+        low: i128,
+
+        /// The upper bound of the range.
+        high: i128,
+    },
+
+    /// The value cannot hold because the symbol's dependencies are not met (or it is forced by `select` or a choice).
+    UnmetDependencies {
+        /// The value the symbol evaluates to, or `None` if it is not written to `sdkconfig`.
+        actual: Option<String>,
+    },
+}
+
+impl ConfigCheck {
+    /// Check the assignments in `sdkconfig` against `eval`, which must have loaded `sdkconfig` (after applying any
+    /// renames) and been evaluated. When a symbol is assigned more than once, only the last assignment is checked.
+    pub fn new(eval: &Evaluation, sdkconfig: &SdkConfig) -> Self {
+        let last: HashMap<&str, usize> =
+            sdkconfig.entries.iter().enumerate().map(|(index, entry)| (entry.name.as_str(), index)).collect();
+        let mut problems = Vec::new();
+
+        for (index, entry) in sdkconfig.entries.iter().enumerate() {
+            if last.get(entry.name.as_str()) != Some(&index) {
+                continue;
+            }
+
+            let problem = |r#type, kind| ConfigProblem {
+                name: entry.name.clone(),
+                value: entry.value.clone(),
+                r#type,
+                location: entry.location,
+                kind,
+            };
+
+            let Some(id) = eval.table().symbol_id(&entry.name) else {
+                problems.push(problem(Type::Unknown, ProblemKind::UnknownSymbol));
+                continue;
+            };
+
+            let r#type = eval.table().symbols()[id].r#type;
+            let state = eval.symbol_state(id);
+
+            let matches = match r#type {
+                Type::Bool | Type::Tristate => match parse_tristate(&entry.value) {
+                    Some(value) if r#type == Type::Tristate || value != Tristate::Maybe => {
+                        state.value.tristate() == value
+                    }
+                    _ => {
+                        problems.push(problem(r#type, ProblemKind::Malformed));
+                        continue;
+                    }
+                },
+                Type::Int | Type::Hex => {
+                    let Some(value) = parse_int(&entry.value, r#type) else {
+                        problems.push(problem(r#type, ProblemKind::Malformed));
+                        continue;
+                    };
+
+                    if let Some((low, high)) = state.range {
+                        if value < low || value > high {
+                            problems.push(problem(
+                                r#type,
+                                ProblemKind::OutOfRange {
+                                    low,
+                                    high,
+                                },
+                            ));
+                            continue;
+                        }
+                    }
+
+                    state.write && state.value.as_str().and_then(|actual| parse_int(actual, r#type)) == Some(value)
+                }
+                Type::String => {
+                    if !entry.quoted {
+                        problems.push(problem(r#type, ProblemKind::Malformed));
+                        continue;
+                    }
+
+                    state.write && state.value.as_str() == Some(entry.value.as_str())
+                }
+                Type::Unknown => true,
+            };
+
+            if !matches {
+                let actual = match (&state.value, state.write) {
+                    (Value::Tristate(t), _) => Some(tristate_str(*t).to_string()),
+                    (Value::String(s), true) => Some(s.clone()),
+                    (Value::String(_), false) => None,
+                };
+
+                problems.push(problem(
+                    r#type,
+                    ProblemKind::UnmetDependencies {
+                        actual,
+                    },
+                ));
+            }
+        }
+
+        Self {
+            problems,
+        }
+    }
+
+    /// Returns `true` if no problems were found.
+    #[inline(always)]
+    pub fn is_empty(&self) -> bool {
+        self.problems.is_empty()
+    }
+
+    /// Returns the problems as a JSON array of objects with `name`, `value`, `type`, `file`, `line`, `kind`, and
+    /// `message` keys. Out-of-range problems also have `low` and `high` keys, and unmet dependencies have an `actual`
+    /// key holding the evaluated value or `null`.
+    pub fn to_json(&self) -> JsonValue {
+        let problems = self
+            .problems
+            .iter()
+            .map(|problem| {
+                let mut result = JsonMap::new();
+                result.insert("name".to_string(), problem.name.clone().into());
+                result.insert("value".to_string(), problem.value.clone().into());
+                result.insert("type".to_string(), problem.r#type.to_string().into());
+                result.insert("file".to_string(), problem.location.filename.to_string_lossy().into());
+                result.insert("line".to_string(), problem.location.line.into());
+                result.insert("kind".to_string(), problem.kind.name().into());
+                result.insert("message".to_string(), problem.message().into());
+
+                match &problem.kind {
+                    ProblemKind::OutOfRange {
+                        low,
+                        high,
+                    } => {
+                        result.insert("low".to_string(), problem.format_int(*low).into());
+                        result.insert("high".to_string(), problem.format_int(*high).into());
+                    }
+                    ProblemKind::UnmetDependencies {
+                        actual,
+                    } => {
+                        result.insert("actual".to_string(), actual.clone().into());
+                    }
+                    ProblemKind::UnknownSymbol | ProblemKind::Malformed => (),
+                }
+
+                JsonValue::Object(result)
+            })
+            .collect();
+
+        JsonValue::Array(problems)
+    }
+}
+
+/// Formats the problems as text, one per line: the location, the assignment, and what is wrong with it.
+impl Display for ConfigCheck {
+    fn fmt(&self, f: &mut Formatter) -> FmtResult {
+        for problem in &self.problems {
+            writeln!(f, "{problem}")?;
+        }
+
+        Ok(())
+    }
+}
+
+impl ConfigProblem {
+    /// Returns a description of the problem.
+    pub fn message(&self) -> String {
+        match &self.kind {
+            ProblemKind::UnknownSymbol => "unknown symbol".to_string(),
+            ProblemKind::Malformed => format!("not a valid {} value", self.r#type),
+            ProblemKind::OutOfRange {
+                low,
+                high,
+            } => format!("out of range [{}, {}]", self.format_int(*low), self.format_int(*high)),
+            ProblemKind::UnmetDependencies {
+                actual: Some(actual),
+            } => {
+                format!("cannot hold because its dependencies are not met; the value is {}", self.format_value(actual))
+            }
+            ProblemKind::UnmetDependencies {
+                actual: None,
+            } => "cannot hold because its dependencies are not met; the symbol is not written".to_string(),
+        }
+    }
+
+    /// Format a value as written in `sdkconfig`.
+    fn format_value(&self, value: &str) -> String {
+        match self.r#type {
+            Type::String => quote(value),
+            _ => value.to_string(),
+        }
+    }
+
+    /// Format an integer in the base of the symbol's type.
+    fn format_int(&self, value: i128) -> String {
+        match self.r#type {
+            Type::Hex => format!("{value:#x}"),
+            _ => value.to_string(),
+        }
+    }
+}
+
+impl Display for ConfigProblem {
+    fn fmt(&self, f: &mut Formatter) -> FmtResult {
+        write!(
+            f,
+            "{} line {}: {CONFIG_PREFIX}{}={}: {}",
+            self.location.filename.display(),
+            self.location.line,
+            self.name,
+            self.format_value(&self.value),
+            self.message()
+        )
+    }
+}
+
+impl ProblemKind {
+    /// Returns the name of the problem kind used in JSON output.
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::UnknownSymbol => "unknown_symbol",
+            Self::Malformed => "malformed",
+            Self::OutOfRange {
+                ..
+            } => "out_of_range",
+            Self::UnmetDependencies {
+                ..
+            } => "unmet_dependencies",
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        crate::{
+            eval::{ConfigCheck, Evaluation, ProblemKind, SymbolTable},
+            parser::{KConfig, PeekableChars},
+            sdkconfig::SdkConfig,
+        },
+        std::{collections::HashMap, path::Path},
+    };
+
+    const KCONFIG: &str = r##"
+config FOO
+    bool "Foo"
+
+config BAR
+    bool "Bar"
+    depends on FOO
+
+config SIZE
+    int "Size"
+    range 1 16
+    default 4
+
+config ADDR
+    hex "Address"
+    default 0x100
+
+config NAME
+    string "Name"
+    depends on BAR
+"##;
+
+    #[test_log::test]
+    fn check_sdkconfig() {
+        let context = HashMap::<String, String>::new();
+        let kconfig =
+            KConfig::from_str(PeekableChars::new(KCONFIG, Path::new("Kconfig")), Path::new("/tmp"), &context).unwrap();
+
+        let sdkconfig = SdkConfig::parse(
+            "CONFIG_FOO=y\nCONFIG_SIZE=8\nCONFIG_ADDR=0x200\nCONFIG_OLD=y\nCONFIG_SIZE=32\nCONFIG_ADDR=0xfoo\n\
+             # CONFIG_FOO is not set\nCONFIG_BAR=y\nCONFIG_NAME=\"board\"\n",
+            Path::new("sdkconfig"),
+        )
+        .unwrap();
+
+        let mut eval = Evaluation::new(SymbolTable::new(&kconfig));
+        eval.load_sdkconfig(&sdkconfig);
+        eval.evaluate(&context).unwrap();
+
+        let check = ConfigCheck::new(&eval, &sdkconfig);
+        let kinds: Vec<(&str, &ProblemKind)> =
+            check.problems.iter().map(|problem| (problem.name.as_str(), &problem.kind)).collect();
+        assert_eq!(
+            kinds,
+            vec![
+                ("OLD", &ProblemKind::UnknownSymbol),
+                (
+                    "SIZE",
+                    &ProblemKind::OutOfRange {
+                        low: 1,
+                        high: 16
+                    }
+                ),
+                ("ADDR", &ProblemKind::Malformed),
+                (
+                    "BAR",
+                    &ProblemKind::UnmetDependencies {
+                        actual: Some("n".to_string())
+                    }
+                ),
+                (
+                    "NAME",
+                    &ProblemKind::UnmetDependencies {
+                        actual: None
+                    }
+                ),
+            ]
+        );
+
+        assert_eq!(
+            check.to_string(),
+            "sdkconfig line 4: CONFIG_OLD=y: unknown symbol\n\
+             sdkconfig line 5: CONFIG_SIZE=32: out of range [1, 16]\n\
+             sdkconfig line 6: CONFIG_ADDR=0xfoo: not a valid hex value\n\
+             sdkconfig line 8: CONFIG_BAR=y: cannot hold because its dependencies are not met; the value is n\n\
+             sdkconfig line 9: CONFIG_NAME=\"board\": cannot hold because its dependencies are not met; the symbol is \
+             not written\n"
+        );
+
+        let json = check.to_json();
+        assert_eq!(json[1]["kind"], "out_of_range");
+        assert_eq!(json[1]["high"], "16");
+        assert_eq!(json[4]["actual"], serde_json::Value::Null);
+
+        let sdkconfig =
+            SdkConfig::parse("CONFIG_FOO=y\nCONFIG_BAR=y\nCONFIG_SIZE=16\n", Path::new("sdkconfig")).unwrap();
+        let mut eval = Evaluation::new(SymbolTable::new(&kconfig));
+        eval.load_sdkconfig(&sdkconfig);
+        eval.evaluate(&context).unwrap();
+        assert!(ConfigCheck::new(&eval, &sdkconfig).is_empty());
+    }
+}
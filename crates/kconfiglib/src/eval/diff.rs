@@ -0,0 +1,373 @@
+use {
+    crate::{
+        eval::{menu_symbols, parse_int, Evaluation, Value, ValueOrigin},
+        parser::Type,
+        sdkconfig::{quote, CONFIG_PREFIX},
+    },
+    serde_json::{Map as JsonMap, Value as JsonValue},
+    std::{
+        collections::HashSet,
+        fmt::{Display, Formatter, Result as FmtResult},
+    },
+};
+
+/// The differences between two evaluated configurations, which may come from different Kconfig trees.
+///
+/// Only symbols written to `sdkconfig` are compared. Whether a value was set by the user or derived from defaults is
+/// taken from the user values of each evaluation; to classify the values of complete `sdkconfig` files, compare the
+/// results of [`Evaluation::savedefconfig()`].
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct ConfigDiff {
+    /// The symbols that differ, in the menu order of the new configuration followed by removed symbols in the menu
+    /// order of the old configuration.
+    pub symbols: Vec<SymbolDiff>,
+}
+
+/// A symbol that differs between two configurations.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct SymbolDiff {
+    /// The name of the symbol (without the `CONFIG_` prefix).
+    pub name: String,
+
+    /// The value in the old configuration, or `None` if the symbol was not written there.
+    pub old: Option<DiffValue>,
+
+    /// The value in the new configuration, or `None` if the symbol was not written there.
+    pub new: Option<DiffValue>,
+
+    /// The titles of the menus containing the symbol, outermost first.
+    pub menu_path: Vec<String>,
+
+    /// The prompt of the symbol, if it has one.
+    pub prompt: Option<String>,
+}
+
+/// One side of a [`SymbolDiff`].
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct DiffValue {
+    /// The type of the symbol.
+    pub r#type: Type,
+
+    /// The value of the symbol.
+    pub value: Value,
+
+    /// Whether the value was set by the user, as opposed to derived from defaults, `select`, or `imply`.
+    pub user_set: bool,
+}
+
+/// The kind of a [`SymbolDiff`].
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum DiffKind {
+    /// The symbol is only written in the new configuration.
+    Added,
+
+    /// The symbol is only written in the old configuration.
+    Removed,
+
+    /// The symbol has different values.
+    Changed,
+}
+
+impl ConfigDiff {
+    /// Compare two configurations.
+    pub fn new(old: &Evaluation, new: &Evaluation) -> Self {
+        let mut symbols = Vec::new();
+        let mut seen = HashSet::new();
+
+        for id in menu_symbols(new) {
+            let new_value = diff_value(new, id);
+            let Some(new_value) = new_value else {
+                continue;
+            };
+
+            let name = &new.table().symbols()[id].name;
+            seen.insert(name.clone());
+
+            let old_value = old.table().symbol_id(name).and_then(|old_id| diff_value(old, old_id));
+            if old_value.as_ref().is_some_and(|old_value| same_value(old_value, &new_value)) {
+                continue;
+            }
+
+            symbols.push(symbol_diff(new, id, old_value, Some(new_value)));
+        }
+
+        for id in menu_symbols(old) {
+            let name = &old.table().symbols()[id].name;
+            if seen.contains(name) {
+                continue;
+            }
+
+            if let Some(old_value) = diff_value(old, id) {
+                symbols.push(symbol_diff(old, id, Some(old_value), None));
+            }
+        }
+
+        Self {
+            symbols,
+        }
+    }
+
+    /// Returns `true` if the configurations are the same.
+    #[inline(always)]
+    pub fn is_empty(&self) -> bool {
+        self.symbols.is_empty()
+    }
+
+    /// Returns the differences as a JSON array of objects with `name`, `kind`, `old`, `new`, `menu_path`, and `prompt`
+    /// keys. `old` and `new` are `null` or objects with `value` and `user_set` keys.
+    pub fn to_json(&self) -> JsonValue {
+        fn side_json(side: &Option<DiffValue>) -> JsonValue {
+            let Some(side) = side else {
+                return JsonValue::Null;
+            };
+
+            let mut result = JsonMap::new();
+            result.insert("value".to_string(), side.value.to_string().into());
+            result.insert("user_set".to_string(), side.user_set.into());
+            JsonValue::Object(result)
+        }
+
+        let symbols = self
+            .symbols
+            .iter()
+            .map(|symbol| {
+                let mut result = JsonMap::new();
+                result.insert("name".to_string(), symbol.name.clone().into());
+                result.insert("kind".to_string(), symbol.kind().to_string().into());
+                result.insert("old".to_string(), side_json(&symbol.old));
+                result.insert("new".to_string(), side_json(&symbol.new));
+                result.insert("menu_path".to_string(), symbol.menu_path.clone().into());
+                result.insert("prompt".to_string(), symbol.prompt.clone().into());
+                JsonValue::Object(result)
+            })
+            .collect();
+
+        JsonValue::Array(symbols)
+    }
+}
+
+/// Formats the differences as text, one symbol per line followed by an indented line with its menu path and prompt.
+/// Lines start with `+` for added symbols, `-` for removed symbols, and `~` for changed values, and end with where
+/// each value came from.
+impl Display for ConfigDiff {
+    fn fmt(&self, f: &mut Formatter) -> FmtResult {
+        for symbol in &self.symbols {
+            let name = &symbol.name;
+            match (&symbol.old, &symbol.new) {
+                (Some(old), Some(new)) => writeln!(
+                    f,
+                    "~ {CONFIG_PREFIX}{name}: {} -> {} [{} -> {}]",
+                    old.display(),
+                    new.display(),
+                    old.source(),
+                    new.source()
+                )?,
+                (None, Some(new)) => writeln!(f, "+ {CONFIG_PREFIX}{name}={} [{}]", new.display(), new.source())?,
+                (Some(old), None) => writeln!(f, "- {CONFIG_PREFIX}{name}={} [{}]", old.display(), old.source())?,
+                (None, None) => continue,
+            }
+
+            let mut location = symbol.menu_path.clone();
+            if let Some(prompt) = &symbol.prompt {
+                location.push(format!("\"{prompt}\""));
+            }
+
+            if !location.is_empty() {
+                writeln!(f, "    {}", location.join(" > "))?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl SymbolDiff {
+    /// Returns whether the symbol was added, removed, or changed.
+    pub fn kind(&self) -> DiffKind {
+        match (&self.old, &self.new) {
+            (None, _) => DiffKind::Added,
+            (_, None) => DiffKind::Removed,
+            _ => DiffKind::Changed,
+        }
+    }
+}
+
+impl DiffValue {
+    /// Returns the value as written in `sdkconfig`.
+    fn display(&self) -> String {
+        match (self.r#type, &self.value) {
+            (Type::String, Value::String(s)) => quote(s),
+            (_, value) => value.to_string(),
+        }
+    }
+
+    /// Returns a description of where the value came from.
+    fn source(&self) -> &'static str {
+        if self.user_set {
+            "user"
+        } else {
+            "default"
+        }
+    }
+}
+
+impl Display for DiffKind {
+    fn fmt(&self, f: &mut Formatter) -> FmtResult {
+        f.write_str(match self {
+            Self::Added => "added",
+            Self::Removed => "removed",
+            Self::Changed => "changed",
+        })
+    }
+}
+
+/// Returns the value of a symbol if it is written to `sdkconfig`.
+fn diff_value(eval: &Evaluation, id: usize) -> Option<DiffValue> {
+    let symbol = &eval.table().symbols()[id];
+    let state = eval.symbol_state(id);
+    if !state.write || symbol.r#type == Type::Unknown {
+        return None;
+    }
+
+    let user_set =
+        eval.user_value(&symbol.name).is_some() && matches!(state.origin, ValueOrigin::User | ValueOrigin::Choice);
+
+    Some(DiffValue {
+        r#type: symbol.r#type,
+        value: state.value.clone(),
+        user_set,
+    })
+}
+
+/// Returns `true` if two values are the same, comparing `int` and `hex` values numerically.
+fn same_value(old: &DiffValue, new: &DiffValue) -> bool {
+    match (new.r#type, &old.value, &new.value) {
+        (Type::Int | Type::Hex, Value::String(a), Value::String(b)) if old.r#type == new.r#type => {
+            match (parse_int(a, old.r#type), parse_int(b, new.r#type)) {
+                (Some(a), Some(b)) => a == b,
+                _ => a == b,
+            }
+        }
+        _ => old.value == new.value,
+    }
+}
+
+/// Build the difference for a symbol, taking its menu path and prompt from the evaluation given.
+fn symbol_diff(eval: &Evaluation, id: usize, old: Option<DiffValue>, new: Option<DiffValue>) -> SymbolDiff {
+    let symbol = &eval.table().symbols()[id];
+    let definition = symbol.definitions.iter().find(|def| def.config.prompt.is_some()).or(symbol.definitions.first());
+
+    SymbolDiff {
+        name: symbol.name.clone(),
+        old,
+        new,
+        menu_path: definition.map(|def| def.menu_path.clone()).unwrap_or_default(),
+        prompt: definition.and_then(|def| def.config.prompt.as_ref()).map(|prompt| prompt.title.to_string()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        crate::{
+            eval::{ConfigDiff, DiffKind, Evaluation, SymbolTable},
+            parser::{KConfig, PeekableChars},
+        },
+        std::{collections::HashMap, path::Path},
+    };
+
+    const OLD: &str = r##"
+menu "Network"
+config WIFI
+    bool "Enable Wi-Fi"
+
+config BUFFERS
+    int "Buffers"
+    depends on WIFI
+    default 4
+
+config OLD_OPTION
+    bool "Old option"
+    default y
+endmenu
+
+config NAME
+    string "Name"
+    default "name"
+"##;
+
+    const NEW: &str = r##"
+menu "Network"
+config WIFI
+    bool "Enable Wi-Fi"
+
+config BUFFERS
+    int "Buffers"
+    depends on WIFI
+    default 8
+
+config NEW_OPTION
+    bool
+    default y
+endmenu
+
+config NAME
+    string "Name"
+    default "name"
+"##;
+
+    fn evaluation(input: &str, user_values: &[(&str, &str)]) -> Evaluation {
+        let context = HashMap::new();
+        let kconfig =
+            KConfig::from_str(PeekableChars::new(input, Path::new("test")), Path::new("/tmp"), &context).unwrap();
+        let mut eval = Evaluation::new(SymbolTable::new(&kconfig));
+        for (name, value) in user_values {
+            eval.set_user_value(name, *value);
+        }
+        eval.evaluate(&context).unwrap();
+        eval
+    }
+
+    #[test_log::test]
+    fn diff_configs() {
+        let old = evaluation(OLD, &[("NAME", "name")]);
+        let new = evaluation(NEW, &[("WIFI", "y"), ("NAME", "name")]);
+
+        let diff = ConfigDiff::new(&old, &new);
+        let kinds: Vec<(&str, DiffKind)> =
+            diff.symbols.iter().map(|symbol| (symbol.name.as_str(), symbol.kind())).collect();
+        assert_eq!(
+            kinds,
+            vec![
+                ("WIFI", DiffKind::Changed),
+                ("BUFFERS", DiffKind::Added),
+                ("NEW_OPTION", DiffKind::Added),
+                ("OLD_OPTION", DiffKind::Removed),
+            ]
+        );
+
+        assert_eq!(
+            diff.to_string(),
+            r##"~ CONFIG_WIFI: n -> y [default -> user]
+    Network > "Enable Wi-Fi"
++ CONFIG_BUFFERS=8 [default]
+    Network > "Buffers"
++ CONFIG_NEW_OPTION=y [default]
+    Network
+- CONFIG_OLD_OPTION=y [default]
+    Network > "Old option"
+"##
+        );
+
+        let json = diff.to_json();
+        assert_eq!(json[0]["kind"], "changed");
+        assert_eq!(json[0]["old"]["user_set"], false);
+        assert_eq!(json[0]["new"]["value"], "y");
+        assert_eq!(json[1]["old"], serde_json::Value::Null);
+
+        // Integers are compared numerically.
+        let old = evaluation(NEW, &[("WIFI", "y"), ("BUFFERS", "016")]);
+        let new = evaluation(NEW, &[("WIFI", "y"), ("BUFFERS", "16")]);
+        assert!(ConfigDiff::new(&old, &new).is_empty());
+    }
+}
@@ -0,0 +1,1313 @@
+use {
+    crate::{
+        eval::{
+            parse_int, parse_tristate, tri_from_level, tri_level, tristate_str, MenuItem, MenuNode, SymbolTable, Value,
+        },
+        expand_vars,
+        parser::{Expr, ExprCmpOp, KConfigError, LitValue, LocExpr, LocLitValue, Located, Tristate, Type},
+        sdkconfig::SdkConfig,
+        Context,
+    },
+    log::{debug, warn},
+    std::{cmp::Ordering, collections::HashMap, env::VarError},
+};
+
+/// Symbol values computed from a [`SymbolTable`] and a set of user-supplied values.
+///
+/// User values are typically loaded from an `sdkconfig` file. After changing user values, call
+/// [`evaluate()`][Evaluation::evaluate] to recompute the value of every symbol.
+#[derive(Clone, Debug)]
+pub struct Evaluation {
+    table: SymbolTable,
+
+    /// User values by symbol name, as written (without quotes for strings).
+    user_values: HashMap<String, String>,
+
+    /// The member chosen by the user for each choice, by choice index.
+    user_selections: HashMap<usize, usize>,
+
+    /// The evaluated state of each symbol, by symbol index.
+    symbols: Vec<SymbolState>,
+
+    /// The evaluated state of each choice, by choice index.
+    choices: Vec<ChoiceState>,
+
+    /// The evaluated state of each menu, by menu id.
+    menus: Vec<MenuState>,
+}
+
+/// The evaluated state of a symbol.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct SymbolState {
+    /// The value of the symbol.
+    pub value: Value,
+
+    /// The visibility of the symbol's prompt. A symbol that is not visible cannot be changed by the user.
+    pub visibility: Tristate,
+
+    /// The value of the symbol's `depends on` conditions (including inherited ones).
+    pub direct_dep: Tristate,
+
+    /// Whether the symbol is written to `sdkconfig` and the other generated outputs.
+    pub write: bool,
+
+    /// Where the value came from.
+    pub origin: ValueOrigin,
+
+    /// The bounds of the first `range` statement whose condition is met, for `int` and `hex` symbols.
+    pub range: Option<(i128, i128)>,
+}
+
+/// Where the value of a symbol came from.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub enum ValueOrigin {
+    /// The symbol has no value (no user value and no active default).
+    #[default]
+    Unset,
+
+    /// The value was set by the user.
+    User,
+
+    /// The value came from a `default` statement.
+    Default {
+        /// The index of the definition containing the statement.
+        definition: usize,
+
+        /// The index of the statement within the definition.
+        index: usize,
+    },
+
+    /// The value was raised by a `select` statement.
+    Selected,
+
+    /// The value was raised by an `imply` statement.
+    Implied,
+
+    /// The value comes from the selection of the enclosing choice.
+    Choice,
+
+    /// The value comes from the environment variable named by an `option env` statement.
+    Environment,
+}
+
+/// The evaluated state of a choice.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct ChoiceState {
+    /// The visibility of the choice's prompt.
+    pub visibility: Tristate,
+
+    /// The index of the selected symbol, if any.
+    pub selection: Option<usize>,
+
+    /// Whether the selection was made by the user (as opposed to a default).
+    pub user_selected: bool,
+}
+
+/// The evaluated state of a menu.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct MenuState {
+    /// The value of the menu's dependencies (including inherited ones).
+    pub dependency: Tristate,
+
+    /// The value of the menu's `visible if` condition.
+    pub visibility: Tristate,
+}
+
+impl Evaluation {
+    /// Create a new evaluation for the given symbol table with no user values.
+    ///
+    /// Symbols have their unset values until [`evaluate()`][Evaluation::evaluate] is called.
+    pub fn new(table: SymbolTable) -> Self {
+        let symbols = table
+            .symbols()
+            .iter()
+            .map(|symbol| SymbolState {
+                value: Value::default_for(symbol.r#type),
+                visibility: Tristate::False,
+                direct_dep: Tristate::False,
+                write: false,
+                origin: ValueOrigin::Unset,
+                range: None,
+            })
+            .collect();
+        let choices = vec![
+            ChoiceState {
+                visibility: Tristate::False,
+                selection: None,
+                user_selected: false,
+            };
+            table.choices().len()
+        ];
+        let menus = vec![
+            MenuState {
+                dependency: Tristate::False,
+                visibility: Tristate::False,
+            };
+            table.menu_count()
+        ];
+
+        Self {
+            table,
+            user_values: HashMap::new(),
+            user_selections: HashMap::new(),
+            symbols,
+            choices,
+            menus,
+        }
+    }
+
+    /// Returns the symbol table being evaluated.
+    #[inline(always)]
+    pub fn table(&self) -> &SymbolTable {
+        &self.table
+    }
+
+    /// Set the user value of a symbol. `value` is the value as it would appear in `sdkconfig`, without quotes.
+    ///
+    /// Setting a choice member to `y` selects it. Values for unknown symbols are kept but otherwise ignored.
+    pub fn set_user_value(&mut self, name: &str, value: impl Into<String>) {
+        let value = value.into();
+
+        if let Some(id) = self.table.symbol_id(name) {
+            if let Some(choice) = self.table.symbols()[id].choice {
+                if value == "y" {
+                    self.user_selections.insert(choice, id);
+                } else if self.user_selections.get(&choice) == Some(&id) {
+                    self.user_selections.remove(&choice);
+                }
+            }
+        }
+
+        self.user_values.insert(name.to_string(), value);
+    }
+
+    /// Remove the user value of a symbol, returning the old value if there was one.
+    pub fn unset_user_value(&mut self, name: &str) -> Option<String> {
+        if let Some(id) = self.table.symbol_id(name) {
+            if let Some(choice) = self.table.symbols()[id].choice {
+                if self.user_selections.get(&choice) == Some(&id) {
+                    self.user_selections.remove(&choice);
+                }
+            }
+        }
+
+        self.user_values.remove(name)
+    }
+
+    /// Remove all user values.
+    pub fn clear_user_values(&mut self) {
+        self.user_values.clear();
+        self.user_selections.clear();
+    }
+
+    /// Returns the user value of a symbol, if any.
+    pub fn user_value(&self, name: &str) -> Option<&str> {
+        self.user_values.get(name).map(String::as_str)
+    }
+
+    /// Returns all user values by symbol name.
+    #[inline(always)]
+    pub fn user_values(&self) -> &HashMap<String, String> {
+        &self.user_values
+    }
+
+    /// Set user values from the entries of an `sdkconfig` file. Later entries override earlier ones.
+    pub fn load_sdkconfig(&mut self, sdkconfig: &SdkConfig) {
+        for entry in &sdkconfig.entries {
+            self.set_user_value(&entry.name, entry.value.clone());
+        }
+    }
+
+    /// Returns the state of the symbol with the given name, if it exists.
+    pub fn state(&self, name: &str) -> Option<&SymbolState> {
+        self.table.symbol_id(name).map(|id| &self.symbols[id])
+    }
+
+    /// Returns the value of the symbol with the given name, if it exists.
+    pub fn value(&self, name: &str) -> Option<&Value> {
+        self.state(name).map(|state| &state.value)
+    }
+
+    /// Returns the state of a symbol by index.
+    #[inline(always)]
+    pub fn symbol_state(&self, id: usize) -> &SymbolState {
+        &self.symbols[id]
+    }
+
+    /// Returns the state of a choice by index.
+    #[inline(always)]
+    pub fn choice_state(&self, id: usize) -> &ChoiceState {
+        &self.choices[id]
+    }
+
+    /// Returns the state of a menu by id.
+    #[inline(always)]
+    pub fn menu_state(&self, id: usize) -> &MenuState {
+        &self.menus[id]
+    }
+
+    /// Recompute the value of every symbol from the user values and the Kconfig definitions.
+    ///
+    /// `context` supplies the values of environment variables referenced in string constants, e.g. `"$IDF_TARGET"`.
+    pub fn evaluate<C>(&mut self, context: &C) -> Result<(), KConfigError>
+    where
+        C: Context,
+    {
+        let symbol_count = self.table.symbols().len();
+        let choice_count = self.table.choices().len();
+
+        let mut evaluator = Evaluator {
+            table: &self.table,
+            user_values: &self.user_values,
+            user_selections: &self.user_selections,
+            context,
+            symbols: vec![None; symbol_count],
+            visibility: vec![None; symbol_count],
+            in_progress: vec![false; symbol_count],
+            choice_visibility: vec![None; choice_count],
+            choice_selection: vec![None; choice_count],
+        };
+
+        for id in 0..symbol_count {
+            evaluator.symbol(id)?;
+        }
+
+        for id in 0..choice_count {
+            evaluator.choice_selection(id)?;
+        }
+
+        let mut menus = vec![
+            MenuState {
+                dependency: Tristate::False,
+                visibility: Tristate::False,
+            };
+            self.table.menu_count()
+        ];
+        evaluator.menus(self.table.menu(), &mut menus)?;
+
+        let Evaluator {
+            symbols,
+            choice_visibility,
+            choice_selection,
+            ..
+        } = evaluator;
+
+        self.symbols = symbols.into_iter().map(Option::unwrap).collect();
+        self.choices = choice_visibility
+            .into_iter()
+            .zip(choice_selection)
+            .enumerate()
+            .map(|(id, (visibility, selection))| {
+                let selection = selection.unwrap();
+                ChoiceState {
+                    visibility: tri_from_level(visibility.unwrap()),
+                    selection,
+                    user_selected: selection.is_some() && self.user_selections.get(&id).copied() == selection,
+                }
+            })
+            .collect();
+        self.menus = menus;
+
+        Ok(())
+    }
+
+    /// Evaluate an expression against the current symbol values.
+    pub fn eval_expr<C>(&self, expr: &LocExpr, context: &C) -> Result<Tristate, KConfigError>
+    where
+        C: Context,
+    {
+        Ok(tri_from_level(self.evaluator(context).expr(expr)?))
+    }
+
+    /// Returns the value a symbol would have without a user value, given the current values of the other symbols. This
+    /// is the value that kconfiglib compares user values against when writing a minimal configuration.
+    ///
+    /// For choice members, this is the value that follows from [`default_selection()`][Self::default_selection].
+    pub fn default_value<C>(&self, id: usize, context: &C) -> Result<Value, KConfigError>
+    where
+        C: Context,
+    {
+        let symbol = &self.table.symbols()[id];
+        let state = &self.symbols[id];
+        let vis = tri_level(state.visibility);
+        let mut evaluator = self.evaluator(context);
+
+        if let Some(state) = evaluator.env_symbol(id, vis, state.range)? {
+            return Ok(state.value);
+        }
+
+        if let Some(choice) = symbol.choice {
+            let selected = vis == 2 && self.default_selection(choice, context)? == Some(id);
+            return Ok(Value::Tristate(Tristate::from(selected)));
+        }
+
+        let state = match symbol.r#type {
+            Type::Bool | Type::Tristate => evaluator.bool_symbol(id, vis, tri_level(state.direct_dep), None)?,
+            Type::Int | Type::Hex | Type::String => evaluator.string_symbol(id, vis, None, state.range)?,
+            Type::Unknown => return Ok(state.value.clone()),
+        };
+
+        Ok(state.value)
+    }
+
+    /// Returns the member a choice would select without a user selection, given the current values of the other
+    /// symbols.
+    pub fn default_selection<C>(&self, id: usize, context: &C) -> Result<Option<usize>, KConfigError>
+    where
+        C: Context,
+    {
+        if self.choices[id].visibility == Tristate::False {
+            return Ok(None);
+        }
+
+        self.evaluator(context).default_selection(id)
+    }
+
+    /// Returns an evaluator primed with the current symbol and choice states.
+    fn evaluator<'a, C>(&'a self, context: &'a C) -> Evaluator<'a, C> {
+        Evaluator {
+            table: &self.table,
+            user_values: &self.user_values,
+            user_selections: &self.user_selections,
+            context,
+            symbols: self.symbols.iter().cloned().map(Some).collect(),
+            visibility: self.symbols.iter().map(|s| Some(tri_level(s.visibility))).collect(),
+            in_progress: vec![false; self.table.symbols().len()],
+            choice_visibility: self.choices.iter().map(|c| Some(tri_level(c.visibility))).collect(),
+            choice_selection: self.choices.iter().map(|c| Some(c.selection)).collect(),
+        }
+    }
+}
+
+/// Returns the state of a `string`, `int`, or `hex` symbol with the given value. The visibility, dependencies, and
+/// range are filled in by the caller.
+fn string_state(value: String, write: bool, origin: ValueOrigin) -> SymbolState {
+    SymbolState {
+        value: Value::String(value),
+        visibility: Tristate::False,
+        direct_dep: Tristate::False,
+        write,
+        origin,
+        range: None,
+    }
+}
+
+/// Format an integer as kconfiglib writes it for a symbol of the given type: decimal for `int`, `0x`-prefixed hex
+/// for `hex`.
+fn format_int(value: i128, r#type: Type) -> String {
+    match r#type {
+        Type::Hex => format!("{value:#x}"),
+        _ => value.to_string(),
+    }
+}
+
+/// An operand of a comparison: the string value of a symbol or constant along with its tristate value and type.
+struct Operand {
+    text: String,
+    tri: u8,
+    r#type: Type,
+}
+
+/// Working state for [`Evaluation::evaluate()`]. Symbols are evaluated lazily (with memoization) so that each
+/// symbol is computed after the symbols it depends on.
+struct Evaluator<'a, C> {
+    table: &'a SymbolTable,
+    user_values: &'a HashMap<String, String>,
+    user_selections: &'a HashMap<usize, usize>,
+    context: &'a C,
+    symbols: Vec<Option<SymbolState>>,
+    visibility: Vec<Option<u8>>,
+    in_progress: Vec<bool>,
+    choice_visibility: Vec<Option<u8>>,
+    choice_selection: Vec<Option<Option<usize>>>,
+}
+
+impl<C> Evaluator<'_, C>
+where
+    C: Context,
+{
+    /// Evaluate an expression as a tristate level.
+    fn expr(&mut self, expr: &LocExpr) -> Result<u8, KConfigError> {
+        match &expr.expr {
+            Expr::Not(e) => Ok(2 - self.expr(e)?),
+            Expr::And(lhs, rhs) => Ok(self.expr(lhs)?.min(self.expr(rhs)?)),
+            Expr::Or(lhs, rhs) => Ok(self.expr(lhs)?.max(self.expr(rhs)?)),
+            Expr::Cmp(op, lhs, rhs) => {
+                let lhs = self.operand(lhs)?;
+                let rhs = self.operand(rhs)?;
+                let ordering = compare(&lhs, &rhs);
+                let result = match op {
+                    ExprCmpOp::Eq => ordering == Ordering::Equal,
+                    ExprCmpOp::Ne => ordering != Ordering::Equal,
+                    ExprCmpOp::Lt => ordering == Ordering::Less,
+                    ExprCmpOp::Le => ordering != Ordering::Greater,
+                    ExprCmpOp::Gt => ordering == Ordering::Greater,
+                    ExprCmpOp::Ge => ordering != Ordering::Less,
+                };
+
+                Ok(if result {
+                    2
+                } else {
+                    0
+                })
+            }
+            _ => Ok(self.operand(expr)?.tri),
+        }
+    }
+
+    /// Evaluate an optional condition; a missing condition is `y`.
+    fn cond(&mut self, expr: Option<&LocExpr>) -> Result<u8, KConfigError> {
+        match expr {
+            Some(expr) => self.expr(expr),
+            None => Ok(2),
+        }
+    }
+
+    /// Evaluate an expression as an operand: a symbol or constant's string value.
+    fn operand(&mut self, expr: &LocExpr) -> Result<Operand, KConfigError> {
+        match &expr.expr {
+            Expr::Symbol(sym) => {
+                if let Some(t) = parse_tristate(&sym.name) {
+                    return Ok(constant(tristate_str(t).to_string()));
+                }
+
+                let Some(id) = self.table.symbol_id(&sym.name) else {
+                    // Undefined symbols evaluate to their name.
+                    return Ok(Operand {
+                        text: sym.name.clone(),
+                        tri: 0,
+                        r#type: Type::Unknown,
+                    });
+                };
+
+                let value = self.symbol(id)?.value;
+                Ok(Operand {
+                    text: value.to_string(),
+                    tri: tri_level(value.tristate()),
+                    r#type: self.table.symbols()[id].r#type,
+                })
+            }
+            Expr::String(s) => Ok(constant(expand_vars(s, self.context))),
+            Expr::Int(i) => Ok(constant(i.to_string())),
+            Expr::Hex(h) => Ok(constant(format!("0x{h:x}"))),
+            _ => {
+                let tri = self.expr(expr)?;
+                Ok(constant(tristate_str(tri_from_level(tri)).to_string()))
+            }
+        }
+    }
+
+    /// Evaluate the visibility of a symbol's prompt.
+    fn visibility(&mut self, id: usize) -> Result<u8, KConfigError> {
+        if let Some(vis) = self.visibility[id] {
+            return Ok(vis);
+        }
+
+        let table = self.table;
+        let symbol = &table.symbols()[id];
+        let mut vis = 0;
+
+        for def in &symbol.definitions {
+            let Some(prompt) = &def.config.prompt else {
+                continue;
+            };
+
+            let v = self
+                .cond(prompt.condition.as_ref())?
+                .min(self.cond(def.dependency.as_ref())?)
+                .min(self.cond(def.visibility.as_ref())?);
+            vis = vis.max(v);
+        }
+
+        if let Some(choice) = symbol.choice {
+            vis = vis.min(self.choice_visibility(choice)?);
+        }
+
+        if vis == 1 && symbol.r#type != Type::Tristate {
+            vis = 2;
+        }
+
+        self.visibility[id] = Some(vis);
+        Ok(vis)
+    }
+
+    /// Evaluate the visibility (and hence mode) of a choice.
+    fn choice_visibility(&mut self, id: usize) -> Result<u8, KConfigError> {
+        if let Some(vis) = self.choice_visibility[id] {
+            return Ok(vis);
+        }
+
+        let table = self.table;
+        let choice = &table.choices()[id];
+        let vis = match &choice.choice.prompt {
+            Some(prompt) => self
+                .cond(prompt.condition.as_ref())?
+                .min(self.cond(choice.dependency.as_ref())?)
+                .min(self.cond(choice.visibility.as_ref())?),
+            None => 0,
+        };
+
+        // Choices are always bool here, so m is promoted to y.
+        let vis = if vis > 0 {
+            2
+        } else {
+            0
+        };
+
+        self.choice_visibility[id] = Some(vis);
+        Ok(vis)
+    }
+
+    /// Determine the selected member of a choice.
+    fn choice_selection(&mut self, id: usize) -> Result<Option<usize>, KConfigError> {
+        if let Some(selection) = self.choice_selection[id] {
+            return Ok(selection);
+        }
+
+        let mut selection = None;
+
+        if self.choice_visibility(id)? > 0 {
+            if let Some(&user) = self.user_selections.get(&id) {
+                if self.visibility(user)? > 0 {
+                    selection = Some(user);
+                }
+            }
+
+            if selection.is_none() {
+                selection = self.default_selection(id)?;
+            }
+        }
+
+        self.choice_selection[id] = Some(selection);
+        Ok(selection)
+    }
+
+    /// Determine the member a visible choice selects without a user selection: the target of the first `default`
+    /// whose condition is met, or else the first visible member.
+    fn default_selection(&mut self, id: usize) -> Result<Option<usize>, KConfigError> {
+        let table = self.table;
+        let choice = &table.choices()[id];
+
+        for default in &choice.choice.defaults {
+            if self.cond(default.condition.as_ref())?.min(self.cond(choice.dependency.as_ref())?) == 0 {
+                continue;
+            }
+
+            let Some(target) = table.symbol_id(&default.target) else {
+                continue;
+            };
+
+            if choice.members.contains(&target) && self.visibility(target)? > 0 {
+                return Ok(Some(target));
+            }
+        }
+
+        for &member in &choice.members {
+            if self.visibility(member)? > 0 {
+                return Ok(Some(member));
+            }
+        }
+
+        Ok(None)
+    }
+
+    /// Evaluate a symbol.
+    fn symbol(&mut self, id: usize) -> Result<SymbolState, KConfigError> {
+        if let Some(state) = &self.symbols[id] {
+            return Ok(state.clone());
+        }
+
+        let table = self.table;
+        let symbol = &table.symbols()[id];
+
+        if self.in_progress[id] {
+            warn!("Dependency loop detected involving symbol {}", symbol.name);
+            return Ok(SymbolState {
+                value: Value::default_for(symbol.r#type),
+                visibility: Tristate::False,
+                direct_dep: Tristate::False,
+                write: false,
+                origin: ValueOrigin::Unset,
+                range: None,
+            });
+        }
+
+        self.in_progress[id] = true;
+
+        let vis = self.visibility(id)?;
+        let mut direct_dep = 0;
+        for def in &symbol.definitions {
+            direct_dep = direct_dep.max(self.cond(def.dependency.as_ref())?);
+        }
+
+        // The active range is needed to check user values and clamp defaults.
+        let range = match symbol.r#type {
+            Type::Int | Type::Hex => self.range(id)?,
+            _ => None,
+        };
+
+        let user_value = self.user_values.get(&symbol.name).map(String::as_str);
+        let state = match self.env_symbol(id, vis, range)? {
+            Some(state) => state,
+            None => match symbol.r#type {
+                Type::Bool | Type::Tristate => self.bool_symbol(id, vis, direct_dep, user_value)?,
+                Type::Int | Type::Hex | Type::String => self.string_symbol(id, vis, user_value, range)?,
+                Type::Unknown => SymbolState {
+                    value: Value::default_for(Type::Unknown),
+                    visibility: Tristate::False,
+                    direct_dep: Tristate::False,
+                    write: false,
+                    origin: ValueOrigin::Unset,
+                    range: None,
+                },
+            },
+        };
+
+        let state = SymbolState {
+            direct_dep: tri_from_level(direct_dep),
+            visibility: tri_from_level(vis),
+            range,
+            ..state
+        };
+
+        self.in_progress[id] = false;
+        self.symbols[id] = Some(state.clone());
+        Ok(state)
+    }
+
+    /// Evaluate a symbol bound to an environment variable with `option env`. Returns `None` if the symbol has no binding
+    /// or the variable is not set (or empty), in which case the symbol is evaluated normally.
+    ///
+    /// `int` and `hex` values are formatted like defaults and clamped to the active `range` in the same way.
+    fn env_symbol(&self, id: usize, vis: u8, range: Option<(i128, i128)>) -> Result<Option<SymbolState>, KConfigError> {
+        let table = self.table;
+        let symbol = &table.symbols()[id];
+        let Some(env) = symbol.definitions.iter().find_map(|def| def.config.env.as_ref()) else {
+            return Ok(None);
+        };
+
+        let value = match self.context.var(env.as_str()) {
+            Ok(value) if !value.is_empty() => value,
+            Ok(_) | Err(VarError::NotPresent) => return Ok(None),
+            Err(VarError::NotUnicode(_)) => return Err(KConfigError::invalid_env(env.as_str(), env.location())),
+        };
+
+        let invalid = || KConfigError::invalid_env_value(env.as_str(), &value, symbol.r#type, env.location());
+        let (value, write) = match symbol.r#type {
+            Type::Bool | Type::Tristate => {
+                let tri = parse_tristate(value.trim()).ok_or_else(invalid)?;
+                let level = match (symbol.r#type, tri_level(tri)) {
+                    (Type::Bool, 1) => return Err(invalid()),
+                    (_, level) => level,
+                };
+                (Value::Tristate(tri), vis != 0 || level > 0)
+            }
+            Type::Int | Type::Hex => {
+                let mut number = parse_int(&value, symbol.r#type).ok_or_else(invalid)?;
+                if let Some((low, high)) = range {
+                    let clamp = if number < low {
+                        Some(low)
+                    } else if number > high {
+                        Some(high)
+                    } else {
+                        None
+                    };
+
+                    if let Some(clamp) = clamp {
+                        warn!(
+                            "Value {} of environment variable {} on {} clamped to {} due to being outside the active \
+                             range ([{}, {}])",
+                            value.trim(),
+                            env.as_str(),
+                            symbol.name,
+                            format_int(clamp, symbol.r#type),
+                            format_int(low, symbol.r#type),
+                            format_int(high, symbol.r#type)
+                        );
+                        number = clamp;
+                    }
+                }
+
+                (Value::String(format_int(number, symbol.r#type)), true)
+            }
+            Type::String => (Value::String(value), true),
+            Type::Unknown => return Ok(None),
+        };
+
+        Ok(Some(SymbolState {
+            value,
+            visibility: Tristate::False,
+            direct_dep: Tristate::False,
+            write,
+            origin: ValueOrigin::Environment,
+            range: None,
+        }))
+    }
+
+    /// Evaluate a `bool` or `tristate` symbol.
+    fn bool_symbol(
+        &mut self,
+        id: usize,
+        vis: u8,
+        direct_dep: u8,
+        user_value: Option<&str>,
+    ) -> Result<SymbolState, KConfigError> {
+        let table = self.table;
+        let symbol = &table.symbols()[id];
+        let mut write = vis != 0;
+        let mut origin = ValueOrigin::Unset;
+        let mut val = 0;
+
+        let user_value = user_value.and_then(|v| {
+            let parsed = parse_tristate(v);
+            if parsed.is_none() {
+                warn!("Ignoring invalid value {v:?} for {} symbol {}", symbol.r#type, symbol.name);
+            }
+            parsed.map(tri_level)
+        });
+
+        if let Some(choice) = symbol.choice {
+            if vis == 2 {
+                val = if self.choice_selection(choice)? == Some(id) {
+                    2
+                } else {
+                    0
+                };
+                origin = ValueOrigin::Choice;
+            } else if vis > 0 && user_value.unwrap_or(0) > 0 {
+                val = 1;
+                origin = ValueOrigin::User;
+            }
+        } else {
+            let mut weak = 0;
+
+            if let (true, Some(user)) = (vis > 0, user_value) {
+                val = user.min(vis);
+                origin = ValueOrigin::User;
+            } else {
+                'defaults: for (d, def) in symbol.definitions.iter().enumerate() {
+                    for (i, default) in def.config.defaults.iter().enumerate() {
+                        let dep_val = self.cond(default.condition.as_ref())?.min(self.cond(def.dependency.as_ref())?);
+                        if dep_val > 0 {
+                            val = self.expr(&default.value)?.min(dep_val);
+                            if val > 0 {
+                                write = true;
+                            }
+
+                            // A default of `n` still supplies the value, even though it is not written.
+                            origin = ValueOrigin::Default {
+                                definition: d,
+                                index: i,
+                            };
+                            break 'defaults;
+                        }
+                    }
+                }
+
+                // Weak reverse dependencies (imply) only apply if the direct dependencies are met.
+                for rdep in &symbol.implied_by {
+                    weak = weak.max(self.reverse_dep(rdep)?);
+                }
+
+                if weak > 0 && direct_dep > 0 {
+                    if weak > val {
+                        origin = ValueOrigin::Implied;
+                    }
+                    val = val.max(weak);
+                    write = true;
+                }
+            }
+
+            let mut rev = 0;
+            for rdep in &symbol.selected_by {
+                rev = rev.max(self.reverse_dep(rdep)?);
+            }
+
+            if rev > 0 {
+                if direct_dep < rev {
+                    debug!("{} is selected but its dependencies are not met", symbol.name);
+                }
+
+                if rev > val {
+                    origin = ValueOrigin::Selected;
+                }
+                val = val.max(rev);
+                write = true;
+            }
+
+            if val == 1 && (symbol.r#type == Type::Bool || weak == 2) {
+                val = 2;
+            }
+        }
+
+        Ok(SymbolState {
+            value: Value::Tristate(tri_from_level(val)),
+            visibility: Tristate::False,
+            direct_dep: Tristate::False,
+            write,
+            origin,
+            range: None,
+        })
+    }
+
+    /// Evaluate a `string`, `int`, or `hex` symbol.
+    ///
+    /// As in kconfiglib, a user value outside the active `range` is ignored with a warning in favor of the defaults, and
+    /// a default value outside the range (or no default at all) is clamped to the nearest bound.
+    fn string_symbol(
+        &mut self,
+        id: usize,
+        vis: u8,
+        user_value: Option<&str>,
+        range: Option<(i128, i128)>,
+    ) -> Result<SymbolState, KConfigError> {
+        let table = self.table;
+        let symbol = &table.symbols()[id];
+        let mut write = vis != 0;
+
+        if let (true, Some(user)) = (vis > 0, user_value) {
+            let number = parse_int(user, symbol.r#type);
+            match (symbol.r#type, number, range) {
+                (Type::String, _, _) => return Ok(string_state(user.to_string(), write, ValueOrigin::User)),
+                (_, Some(number), Some((low, high))) if number < low || number > high => warn!(
+                    "User value {} on the {} symbol {} ignored due to being outside the active range ([{}, {}]) -- \
+                     falling back on defaults",
+                    format_int(number, symbol.r#type),
+                    symbol.r#type,
+                    symbol.name,
+                    format_int(low, symbol.r#type),
+                    format_int(high, symbol.r#type)
+                ),
+                (_, Some(_), _) => return Ok(string_state(user.to_string(), write, ValueOrigin::User)),
+                (_, None, _) => warn!("Ignoring invalid value {user:?} for {} symbol {}", symbol.r#type, symbol.name),
+            }
+        }
+
+        let mut value = String::new();
+        let mut origin = ValueOrigin::Unset;
+
+        'defaults: for (d, def) in symbol.definitions.iter().enumerate() {
+            for (i, default) in def.config.defaults.iter().enumerate() {
+                if self.cond(default.condition.as_ref())?.min(self.cond(def.dependency.as_ref())?) > 0 {
+                    write = true;
+                    value = self.operand(&default.value)?.text;
+                    origin = ValueOrigin::Default {
+                        definition: d,
+                        index: i,
+                    };
+                    break 'defaults;
+                }
+            }
+        }
+
+        // Clamping applies even without a default, where the value counts as 0.
+        if let Some((low, high)) = range {
+            let number = parse_int(&value, symbol.r#type).unwrap_or(0);
+            let clamp = if number < low {
+                Some(low)
+            } else if number > high {
+                Some(high)
+            } else {
+                None
+            };
+
+            if let Some(clamp) = clamp {
+                let clamped = format_int(clamp, symbol.r#type);
+                if origin != ValueOrigin::Unset {
+                    warn!(
+                        "Default value {value} on {} clamped to {clamped} due to being outside the active range ([{}, \
+                         {}])",
+                        symbol.name,
+                        format_int(low, symbol.r#type),
+                        format_int(high, symbol.r#type)
+                    );
+                }
+                value = clamped;
+            }
+        }
+
+        Ok(string_state(value, write, origin))
+    }
+
+    /// Determine the active range of an `int` or `hex` symbol: the bounds of the first `range` statement whose
+    /// condition is met.
+    fn range(&mut self, id: usize) -> Result<Option<(i128, i128)>, KConfigError> {
+        let table = self.table;
+        let symbol = &table.symbols()[id];
+
+        for def in &symbol.definitions {
+            for range in &def.config.ranges {
+                if self.cond(range.condition.as_ref())?.min(self.cond(def.dependency.as_ref())?) == 0 {
+                    continue;
+                }
+
+                let low = self.range_bound(&range.start, symbol.r#type)?;
+                let high = self.range_bound(&range.end, symbol.r#type)?;
+                let (Some(low), Some(high)) = (low, high) else {
+                    warn!("{}: Ignoring range with a non-numeric bound for {}", range.start.location, symbol.name);
+                    return Ok(None);
+                };
+
+                return Ok(Some((low, high)));
+            }
+        }
+
+        Ok(None)
+    }
+
+    /// Evaluate one bound of a `range` statement. Symbol values are parsed according to the type of the symbol being
+    /// ranged.
+    fn range_bound(&mut self, bound: &LocLitValue, r#type: Type) -> Result<Option<i128>, KConfigError> {
+        Ok(match &bound.value {
+            LitValue::Hex(h) => Some(*h as i128),
+            LitValue::Int(i) => Some(*i as i128),
+            LitValue::String(s) => parse_int(s, r#type),
+            LitValue::Symbol(name) => match self.table.symbol_id(name) {
+                Some(id) => parse_int(&self.symbol(id)?.value.to_string(), r#type),
+                None => parse_int(name, r#type),
+            },
+            LitValue::Tristate(_) => None,
+        })
+    }
+
+    /// Evaluate a `select` or `imply` statement.
+    fn reverse_dep(&mut self, rdep: &crate::eval::ReverseDep) -> Result<u8, KConfigError> {
+        let table = self.table;
+        let def = &table.symbols()[rdep.symbol].definitions[rdep.definition];
+        let value = tri_level(self.symbol(rdep.symbol)?.value.tristate());
+        Ok(value.min(self.cond(rdep.condition.as_ref())?).min(self.cond(def.dependency.as_ref())?))
+    }
+
+    /// Evaluate the dependencies and visibility of every menu in the tree.
+    fn menus(&mut self, nodes: &[MenuNode], states: &mut [MenuState]) -> Result<(), KConfigError> {
+        for node in nodes {
+            if let MenuItem::Menu {
+                id,
+                visibility,
+                ..
+            } = &node.item
+            {
+                states[*id] = MenuState {
+                    dependency: tri_from_level(self.cond(node.dependency.as_ref())?),
+                    visibility: tri_from_level(self.cond(visibility.as_ref())?),
+                };
+            }
+
+            self.menus(&node.children, states)?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Create an operand for a constant value.
+fn constant(text: String) -> Operand {
+    let tri = parse_tristate(&text).map(tri_level).unwrap_or(0);
+    Operand {
+        text,
+        tri,
+        r#type: Type::Unknown,
+    }
+}
+
+/// Compare two operands. Strings are compared lexicographically; anything else is compared numerically if both sides
+/// are numbers.
+fn compare(lhs: &Operand, rhs: &Operand) -> Ordering {
+    if lhs.r#type != Type::String || rhs.r#type != Type::String {
+        if let (Some(l), Some(r)) = (operand_num(lhs), operand_num(rhs)) {
+            return l.cmp(&r);
+        }
+    }
+
+    lhs.text.cmp(&rhs.text)
+}
+
+/// Returns the numeric value of an operand, if it has one.
+fn operand_num(operand: &Operand) -> Option<i128> {
+    match operand.r#type {
+        Type::Bool | Type::Tristate => Some(operand.tri as i128),
+        r#type => parse_int(&operand.text, r#type),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        crate::{
+            eval::{Evaluation, SymbolTable, UserValueStatus, Value, ValueOrigin},
+            parser::{KConfig, PeekableChars, Tristate},
+        },
+        std::{
+            collections::HashMap,
+            env,
+            path::{Path, PathBuf},
+        },
+    };
+
+    fn evaluate(input: &str, user_values: &[(&str, &str)]) -> Evaluation {
+        let mut context = HashMap::new();
+        context.insert("IDF_TARGET".to_string(), "esp32".to_string());
+
+        let kconfig =
+            KConfig::from_str(PeekableChars::new(input, Path::new("test")), Path::new("/tmp"), &context).unwrap();
+        let mut eval = Evaluation::new(SymbolTable::new(&kconfig));
+
+        for (name, value) in user_values {
+            eval.set_user_value(name, *value);
+        }
+
+        eval.evaluate(&context).unwrap();
+        eval
+    }
+
+    fn value(eval: &Evaluation, name: &str) -> String {
+        eval.value(name).unwrap().to_string()
+    }
+
+    const KCONFIG: &str = r##"
+config TARGET
+    string
+    default "$IDF_TARGET"
+
+config IS_ESP32
+    bool
+    default "y" if TARGET="esp32"
+
+config FOO
+    bool "Foo"
+    default y if IS_ESP32 && !BAR
+
+config BAR
+    bool "Bar"
+    select BAZ
+
+config BAZ
+    bool
+
+if FOO
+    config SIZE
+        int "Size"
+        default 16
+endif
+
+menu "Options"
+    depends on !BAR
+
+    config LEVEL
+        hex "Level"
+        default 0x10 if SIZE > 8
+        default 0x1
+
+    choice MODE
+        prompt "Mode"
+        default MODE_B
+
+        config MODE_A
+            bool "A"
+        config MODE_B
+            bool "B"
+        config MODE_C
+            bool "C"
+            depends on BAR
+    endchoice
+endmenu
+"##;
+
+    #[test_log::test]
+    fn eval_defaults() {
+        let eval = evaluate(KCONFIG, &[]);
+        assert_eq!(value(&eval, "TARGET"), "esp32");
+        assert_eq!(value(&eval, "IS_ESP32"), "y");
+        assert_eq!(value(&eval, "FOO"), "y");
+        assert_eq!(value(&eval, "BAR"), "n");
+        assert_eq!(value(&eval, "BAZ"), "n");
+        assert_eq!(value(&eval, "SIZE"), "16");
+        assert_eq!(value(&eval, "LEVEL"), "0x10");
+        assert_eq!(value(&eval, "MODE_A"), "n");
+        assert_eq!(value(&eval, "MODE_B"), "y");
+        assert_eq!(value(&eval, "MODE_C"), "n");
+        assert!(!eval.state("BAZ").unwrap().write);
+        assert!(eval.state("MODE_C").unwrap().visibility == Tristate::False);
+        assert_eq!(
+            eval.state("FOO").unwrap().origin,
+            ValueOrigin::Default {
+                definition: 0,
+                index: 0
+            }
+        );
+
+        // A default of `n` whose condition holds is still where the value came from.
+        let eval = evaluate("config OFF\n    bool\n    default y if n\n    default n if y\n", &[]);
+        assert_eq!(value(&eval, "OFF"), "n");
+        assert!(!eval.state("OFF").unwrap().write);
+        assert_eq!(
+            eval.state("OFF").unwrap().origin,
+            ValueOrigin::Default {
+                definition: 0,
+                index: 1
+            }
+        );
+    }
+
+    #[test_log::test]
+    fn eval_user_values() {
+        let eval = evaluate(KCONFIG, &[("SIZE", "4"), ("MODE_A", "y"), ("BAR", "y")]);
+        assert_eq!(value(&eval, "FOO"), "n");
+        assert_eq!(value(&eval, "BAZ"), "y");
+        assert_eq!(eval.state("BAZ").unwrap().origin, ValueOrigin::Selected);
+
+        // SIZE and the Options menu are hidden now, so the user values no longer apply.
+        assert_eq!(value(&eval, "SIZE"), "");
+        assert!(!eval.state("SIZE").unwrap().write);
+        assert_eq!(value(&eval, "MODE_A"), "n");
+        assert_eq!(value(&eval, "MODE_B"), "n");
+
+        let eval = evaluate(KCONFIG, &[("SIZE", "4"), ("MODE_A", "y"), ("LEVEL", "bogus")]);
+        assert_eq!(value(&eval, "SIZE"), "4");
+        assert_eq!(value(&eval, "LEVEL"), "0x1");
+        assert_eq!(eval.value("MODE_A"), Some(&Value::Tristate(Tristate::True)));
+        assert_eq!(value(&eval, "MODE_B"), "n");
+        assert!(eval.choice_state(0).user_selected);
+    }
+
+    #[test_log::test]
+    fn eval_option_env() {
+        const ENV_KCONFIG: &str = r##"
+config FPGA
+    bool
+    option env="IDF_ENV_FPGA"
+
+config FPGA_BUILD
+    bool
+    default y if FPGA
+
+config RAM_SIZE
+    hex "RAM size"
+    range 0x100 0x8000
+    default 0x1000
+    option env="RAM_SIZE"
+"##;
+
+        let env_eval = |vars: &[(&str, &str)]| {
+            let context: HashMap<String, String> =
+                vars.iter().map(|(name, value)| (name.to_string(), value.to_string())).collect();
+            let kconfig =
+                KConfig::from_str(PeekableChars::new(ENV_KCONFIG, Path::new("test")), Path::new("/tmp"), &context)
+                    .unwrap();
+            let mut eval = Evaluation::new(SymbolTable::new(&kconfig));
+            eval.set_user_value("RAM_SIZE", "0x2000");
+            eval.evaluate(&context).map(|()| eval)
+        };
+
+        // Unset variables leave the symbols to their defaults and user values.
+        let eval = env_eval(&[]).unwrap();
+        assert_eq!(value(&eval, "FPGA"), "n");
+        assert_eq!(value(&eval, "RAM_SIZE"), "0x2000");
+        assert_eq!(eval.state("RAM_SIZE").unwrap().origin, ValueOrigin::User);
+
+        let eval = env_eval(&[("IDF_ENV_FPGA", "y"), ("RAM_SIZE", "0x4000")]).unwrap();
+        assert_eq!(value(&eval, "FPGA"), "y");
+        assert_eq!(eval.state("FPGA").unwrap().origin, ValueOrigin::Environment);
+        assert!(eval.state("FPGA").unwrap().write);
+        assert_eq!(value(&eval, "FPGA_BUILD"), "y");
+        assert_eq!(value(&eval, "RAM_SIZE"), "0x4000");
+        assert_eq!(eval.state("RAM_SIZE").unwrap().origin, ValueOrigin::Environment);
+
+        // Numbers are normalized and clamped to the active range like defaults.
+        let eval = env_eval(&[("RAM_SIZE", "4000")]).unwrap();
+        assert_eq!(value(&eval, "RAM_SIZE"), "0x4000");
+        let eval = env_eval(&[("RAM_SIZE", "0x10000")]).unwrap();
+        assert_eq!(value(&eval, "RAM_SIZE"), "0x8000");
+        assert_eq!(eval.state("RAM_SIZE").unwrap().origin, ValueOrigin::Environment);
+
+        let e = env_eval(&[("IDF_ENV_FPGA", "1")]).unwrap_err();
+        assert_eq!(e.to_string(), "test 4:16: Invalid bool value for environment variable IDF_ENV_FPGA: \"1\"");
+        assert!(env_eval(&[("RAM_SIZE", "lots")]).is_err());
+    }
+
+    #[test_log::test]
+    fn eval_ranges() {
+        const RANGE_KCONFIG: &str = r##"
+config SMALL
+    bool "Small"
+
+config MAX_STACK
+    int
+    default 2048 if SMALL
+    default 8192
+
+config STACK
+    int "Stack size"
+    range 512 MAX_STACK if SMALL
+    range 1024 MAX_STACK
+    default 4096
+
+config ALIGN
+    hex "Alignment"
+    range 0x10 0x100
+
+config PRIORITY
+    int "Priority"
+    range 1 10
+    default 5
+"##;
+
+        let eval = evaluate(RANGE_KCONFIG, &[]);
+        assert_eq!(value(&eval, "STACK"), "4096");
+        assert_eq!(eval.state("STACK").unwrap().range, Some((1024, 8192)));
+        // Without a default, the value is clamped to the lower bound.
+        assert_eq!(value(&eval, "ALIGN"), "0x10");
+        assert_eq!(eval.state("ALIGN").unwrap().origin, ValueOrigin::Unset);
+
+        // The first range whose condition holds wins, and its symbol bound follows the symbol's value.
+        let eval = evaluate(RANGE_KCONFIG, &[("SMALL", "y"), ("STACK", "1024"), ("PRIORITY", "10")]);
+        assert_eq!(eval.state("STACK").unwrap().range, Some((512, 2048)));
+        assert_eq!(value(&eval, "STACK"), "1024");
+        assert_eq!(eval.state("STACK").unwrap().origin, ValueOrigin::User);
+        assert_eq!(value(&eval, "PRIORITY"), "10");
+
+        // Defaults outside the active range are clamped.
+        let eval = evaluate(RANGE_KCONFIG, &[("SMALL", "y")]);
+        assert_eq!(value(&eval, "STACK"), "2048");
+
+        // User values outside the active range are ignored in favor of the (clamped) defaults.
+        let eval = evaluate(RANGE_KCONFIG, &[("SMALL", "y"), ("STACK", "4096"), ("ALIGN", "0x200"), ("PRIORITY", "0")]);
+        assert_eq!(value(&eval, "STACK"), "2048");
+        assert_ne!(eval.state("STACK").unwrap().origin, ValueOrigin::User);
+        assert_eq!(value(&eval, "ALIGN"), "0x10");
+        assert_eq!(value(&eval, "PRIORITY"), "5");
+        let why = eval.explain("PRIORITY", &HashMap::<String, String>::new()).unwrap();
+        assert_eq!(why.user_value.unwrap().status, UserValueStatus::OutOfRange);
+    }
+
+    #[test_log::test]
+    fn eval_esp_idf() {
+        let mut context = HashMap::default();
+        let base_dir = PathBuf::from(env::var("CARGO_MANIFEST_DIR").unwrap());
+        let esp_idf = base_dir.join("tests/esp-idf");
+
+        context.insert("IDF_PATH".to_string(), esp_idf.to_str().unwrap().to_string());
+        context.insert("IDF_TARGET".to_string(), "esp32".to_string());
+        context.insert(
+            "COMPONENT_KCONFIGS_SOURCE_FILE".to_string(),
+            esp_idf.join("Kconfigs.in").to_str().unwrap().to_string(),
+        );
+        context.insert(
+            "COMPONENT_KCONFIGS_PROJBUILD_SOURCE_FILE".to_string(),
+            esp_idf.join("Kconfigs.projbuild.in").to_str().unwrap().to_string(),
+        );
+
+        let kconfig = KConfig::from_file(&esp_idf.join("Kconfig"), &esp_idf, &context).unwrap();
+        let mut eval = Evaluation::new(SymbolTable::new(&kconfig));
+        eval.evaluate(&context).unwrap();
+
+        assert_eq!(value(&eval, "IDF_TARGET"), "esp32");
+        assert_eq!(value(&eval, "IDF_TARGET_ESP32"), "y");
+        assert_eq!(value(&eval, "IDF_TARGET_ESP32S2"), "n");
+        assert_eq!(value(&eval, "IDF_TARGET_ARCH_XTENSA"), "y");
+        assert_eq!(value(&eval, "IDF_TARGET_ARCH"), "xtensa");
+        assert_eq!(value(&eval, "IDF_FIRMWARE_CHIP_ID"), "0x0");
+        assert_eq!(value(&eval, "FREERTOS_UNICORE"), "n");
+        assert_eq!(value(&eval, "APP_BUILD_TYPE_APP_2NDBOOT"), "y");
+    }
+}
@@ -0,0 +1,592 @@
+use {
+    crate::{
+        eval::{parse_int, parse_tristate, tristate_str, Evaluation, ReverseDep, Value, ValueOrigin},
+        parser::{Expr, KConfigError, LocExpr, Located, Location, Tristate, Type},
+        Context,
+    },
+    std::fmt::{Display, Formatter, Result as FmtResult},
+};
+
+/// A structured explanation of how the value of a symbol was determined, from [`Evaluation::explain()`].
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Explanation {
+    /// The name of the symbol.
+    pub name: String,
+
+    /// The type of the symbol.
+    pub r#type: Type,
+
+    /// The value of the symbol.
+    pub value: Value,
+
+    /// Where the value came from.
+    pub origin: ValueOrigin,
+
+    /// The visibility of the symbol's prompt.
+    pub visibility: Tristate,
+
+    /// The value of the symbol's `depends on` conditions.
+    pub direct_dep: Tristate,
+
+    /// The locations of the symbol's definitions.
+    pub definitions: Vec<Location>,
+
+    /// The user value of the symbol and whether it was used.
+    pub user_value: Option<UserValue>,
+
+    /// The `default` statement that supplied the value, if the value came from a default.
+    pub default: Option<DefaultReason>,
+
+    /// The active `select` statements targeting the symbol.
+    pub selects: Vec<ReverseDepReason>,
+
+    /// The active `imply` statements targeting the symbol.
+    pub implies: Vec<ReverseDepReason>,
+
+    /// The terms of the symbol's dependencies that are not `y`, which limit its value or hide its prompt.
+    pub limits: Vec<Term>,
+
+    /// The state of the enclosing choice, for choice members.
+    pub choice: Option<ChoiceReason>,
+}
+
+/// A user value and what happened to it.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct UserValue {
+    /// The user value, as written in `sdkconfig`.
+    pub value: String,
+
+    /// Whether the user value was used, and if not, why.
+    pub status: UserValueStatus,
+}
+
+/// What happened to a user value.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum UserValueStatus {
+    /// The user value is the value of the symbol.
+    Applied,
+
+    /// The user value was ignored because the symbol's prompt is not visible.
+    NotVisible,
+
+    /// The user value was ignored because it is not valid for the symbol's type.
+    Invalid,
+
+    /// The user value was ignored because it is outside the active `range` of an `int` or `hex` symbol.
+    OutOfRange,
+
+    /// The user value was lowered to the visibility of the symbol's prompt.
+    Limited,
+
+    /// The user value was raised by a `select` statement.
+    Selected,
+
+    /// The user value was overridden by the selection of the enclosing choice.
+    Choice,
+
+    /// The user value was overridden by the environment variable bound to the symbol with `option env`.
+    Environment,
+}
+
+/// The `default` statement that supplied a value.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct DefaultReason {
+    /// The default value expression. Its location is the location of the statement.
+    pub value: LocExpr,
+
+    /// The `if` condition of the statement and the terms that made it true, if it has one.
+    pub condition: Option<Condition>,
+}
+
+/// A `select` or `imply` statement that raises the value of a symbol.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct ReverseDepReason {
+    /// The name of the symbol containing the statement.
+    pub symbol: String,
+
+    /// The location of the statement's target.
+    pub location: Location,
+
+    /// The value the statement raises the symbol to: the smallest of the selecting symbol's value, the condition, and
+    /// the selecting symbol's dependencies.
+    pub value: Tristate,
+
+    /// The `if` condition of the statement and the terms that made it true, if it has one.
+    pub condition: Option<Condition>,
+}
+
+/// The state of the choice containing a symbol.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct ChoiceReason {
+    /// The location of the choice.
+    pub location: Location,
+
+    /// The name of the selected member, if any.
+    pub selection: Option<String>,
+
+    /// Whether the selection was made by the user.
+    pub user_selected: bool,
+}
+
+/// A condition and the values of the terms it refers to.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Condition {
+    /// The condition.
+    pub expr: LocExpr,
+
+    /// The value of the condition.
+    pub value: Tristate,
+
+    /// The symbols and comparisons in the condition with their values.
+    pub terms: Vec<Term>,
+}
+
+/// A part of an expression and its value.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Term {
+    /// The expression.
+    pub expr: LocExpr,
+
+    /// The value of the expression.
+    pub value: Tristate,
+}
+
+impl Evaluation {
+    /// Explain the value of a symbol: which `default` supplied it and why its condition was true, which `select` and
+    /// `imply` statements raised it, which dependencies limited it, and whether a user value was used.
+    ///
+    /// `context` must be the context passed to [`evaluate()`][Evaluation::evaluate].
+    pub fn explain<C>(&self, name: &str, context: &C) -> Result<Explanation, KConfigError>
+    where
+        C: Context,
+    {
+        let Some(id) = self.table().symbol_id(name) else {
+            return Err(KConfigError::unknown_symbol(name));
+        };
+
+        let symbol = &self.table().symbols()[id];
+        let state = self.symbol_state(id);
+
+        let user_value = self.user_value(name).map(|value| UserValue {
+            value: value.to_string(),
+            status: self.user_value_status(id, value),
+        });
+
+        let default = match state.origin {
+            ValueOrigin::User | ValueOrigin::Choice => None,
+            _ => self.active_default(id, context)?,
+        };
+
+        let mut selects = Vec::new();
+        for rdep in &symbol.selected_by {
+            if let Some(reason) = self.reverse_dep_reason(rdep, name, false, context)? {
+                selects.push(reason);
+            }
+        }
+
+        let mut implies = Vec::new();
+        for rdep in &symbol.implied_by {
+            if let Some(reason) = self.reverse_dep_reason(rdep, name, true, context)? {
+                implies.push(reason);
+            }
+        }
+
+        let mut limits: Vec<Term> = Vec::new();
+        for def in &symbol.definitions {
+            let Some(dependency) = &def.dependency else {
+                continue;
+            };
+
+            for conjunct in conjuncts(dependency) {
+                let value = self.eval_expr(conjunct, context)?;
+                if value != Tristate::True && !limits.iter().any(|term| term.expr == *conjunct) {
+                    limits.push(Term {
+                        expr: conjunct.clone(),
+                        value,
+                    });
+                }
+            }
+        }
+
+        let choice = symbol.choice.map(|choice| {
+            let info = &self.table().choices()[choice];
+            let choice_state = self.choice_state(choice);
+            ChoiceReason {
+                location: info.choice.name.location(),
+                selection: choice_state.selection.map(|member| self.table().symbols()[member].name.clone()),
+                user_selected: choice_state.user_selected,
+            }
+        });
+
+        Ok(Explanation {
+            name: symbol.name.clone(),
+            r#type: symbol.r#type,
+            value: state.value.clone(),
+            origin: state.origin,
+            visibility: state.visibility,
+            direct_dep: state.direct_dep,
+            definitions: symbol.definitions.iter().map(|def| def.config.name.location()).collect(),
+            user_value,
+            default,
+            selects,
+            implies,
+            limits,
+            choice,
+        })
+    }
+
+    /// Determine what happened to the user value of a symbol.
+    fn user_value_status(&self, id: usize, user_value: &str) -> UserValueStatus {
+        let symbol = &self.table().symbols()[id];
+        let state = self.symbol_state(id);
+
+        let valid = match symbol.r#type {
+            Type::Bool | Type::Tristate => parse_tristate(user_value).is_some(),
+            Type::Int | Type::Hex => parse_int(user_value, symbol.r#type).is_some(),
+            _ => true,
+        };
+
+        if state.origin == ValueOrigin::Environment {
+            return UserValueStatus::Environment;
+        }
+
+        if state.visibility == Tristate::False {
+            return UserValueStatus::NotVisible;
+        }
+
+        if !valid {
+            return UserValueStatus::Invalid;
+        }
+
+        if let (Some(value), Some((low, high))) = (parse_int(user_value, symbol.r#type), state.range) {
+            if value < low || value > high {
+                return UserValueStatus::OutOfRange;
+            }
+        }
+
+        let matches = match &state.value {
+            Value::Tristate(t) => parse_tristate(user_value) == Some(*t),
+            Value::String(_) => true,
+        };
+
+        match state.origin {
+            ValueOrigin::Selected => UserValueStatus::Selected,
+            ValueOrigin::Choice if !matches => UserValueStatus::Choice,
+            _ if !matches => UserValueStatus::Limited,
+            _ => UserValueStatus::Applied,
+        }
+    }
+
+    /// Find the first `default` statement whose condition and definition dependency are met, as the evaluator does.
+    fn active_default<C>(&self, id: usize, context: &C) -> Result<Option<DefaultReason>, KConfigError>
+    where
+        C: Context,
+    {
+        for def in &self.table().symbols()[id].definitions {
+            if let Some(dependency) = &def.dependency {
+                if self.eval_expr(dependency, context)? == Tristate::False {
+                    continue;
+                }
+            }
+
+            for default in &def.config.defaults {
+                let condition = default.condition.as_ref().map(|expr| self.condition(expr, context)).transpose()?;
+                if condition.as_ref().is_some_and(|condition| condition.value == Tristate::False) {
+                    continue;
+                }
+
+                return Ok(Some(DefaultReason {
+                    value: default.value.clone(),
+                    condition,
+                }));
+            }
+        }
+
+        Ok(None)
+    }
+
+    /// Explain a `select` or `imply` statement, or return `None` if it is not active.
+    fn reverse_dep_reason<C>(
+        &self,
+        rdep: &ReverseDep,
+        target: &str,
+        imply: bool,
+        context: &C,
+    ) -> Result<Option<ReverseDepReason>, KConfigError>
+    where
+        C: Context,
+    {
+        let symbol = &self.table().symbols()[rdep.symbol];
+        let def = &symbol.definitions[rdep.definition];
+
+        let mut value = self.symbol_state(rdep.symbol).value.tristate();
+        if let Some(dependency) = &def.dependency {
+            value = min(value, self.eval_expr(dependency, context)?);
+        }
+
+        let condition = rdep.condition.as_ref().map(|expr| self.condition(expr, context)).transpose()?;
+        if let Some(condition) = &condition {
+            value = min(value, condition.value);
+        }
+
+        if value == Tristate::False {
+            return Ok(None);
+        }
+
+        let statements = if imply {
+            &def.config.implies
+        } else {
+            &def.config.selects
+        };
+        let location = statements
+            .iter()
+            .find(|statement| statement.target_name.as_str() == target && statement.condition == rdep.condition)
+            .map(|statement| statement.target_name.location())
+            .unwrap_or(def.config.name.location());
+
+        Ok(Some(ReverseDepReason {
+            symbol: symbol.name.clone(),
+            location,
+            value,
+            condition,
+        }))
+    }
+
+    /// Evaluate a condition along with the symbols and comparisons in it.
+    fn condition<C>(&self, expr: &LocExpr, context: &C) -> Result<Condition, KConfigError>
+    where
+        C: Context,
+    {
+        let mut terms: Vec<Term> = Vec::new();
+        for atom in atoms(expr) {
+            if !terms.iter().any(|term| term.expr == *atom) {
+                terms.push(Term {
+                    expr: atom.clone(),
+                    value: self.eval_expr(atom, context)?,
+                });
+            }
+        }
+
+        Ok(Condition {
+            expr: expr.clone(),
+            value: self.eval_expr(expr, context)?,
+            terms,
+        })
+    }
+}
+
+/// Formats the explanation as indented text for display to users.
+impl Display for Explanation {
+    fn fmt(&self, f: &mut Formatter) -> FmtResult {
+        writeln!(f, "{} ({}) = {}", self.name, self.r#type, self.value)?;
+        for location in &self.definitions {
+            writeln!(f, "  defined at {location}")?;
+        }
+
+        let origin = match self.origin {
+            ValueOrigin::Unset => "no value was set; the symbol has its unset value",
+            ValueOrigin::User => "the value was set by the user",
+            ValueOrigin::Default {
+                ..
+            } => "the value comes from a default",
+            ValueOrigin::Selected => "the value was raised by select",
+            ValueOrigin::Implied => "the value was raised by imply",
+            ValueOrigin::Choice => "the value comes from the choice selection",
+            ValueOrigin::Environment => "the value comes from the environment",
+        };
+        writeln!(f, "  {origin}")?;
+        writeln!(
+            f,
+            "  prompt visibility: {}, dependencies: {}",
+            tristate_str(self.visibility),
+            tristate_str(self.direct_dep)
+        )?;
+
+        if let Some(user_value) = &self.user_value {
+            let status = match user_value.status {
+                UserValueStatus::Applied => "used",
+                UserValueStatus::NotVisible => "ignored because the prompt is not visible",
+                UserValueStatus::Invalid => "ignored because it is not valid",
+                UserValueStatus::OutOfRange => "ignored because it is outside the range",
+                UserValueStatus::Limited => "limited by the prompt's visibility",
+                UserValueStatus::Selected => "overridden by select",
+                UserValueStatus::Choice => "overridden by the choice selection",
+                UserValueStatus::Environment => "overridden by the environment",
+            };
+            writeln!(f, "  user value {:?}: {status}", user_value.value)?;
+        }
+
+        if let Some(default) = &self.default {
+            writeln!(f, "  default {} at {}", default.value.expr, default.value.location)?;
+            if let Some(condition) = &default.condition {
+                write_condition(f, condition)?;
+            }
+        }
+
+        for (kind, reasons) in [("selected", &self.selects), ("implied", &self.implies)] {
+            for reason in reasons {
+                writeln!(f, "  {kind} by {} (= {}) at {}", reason.symbol, tristate_str(reason.value), reason.location)?;
+                if let Some(condition) = &reason.condition {
+                    write_condition(f, condition)?;
+                }
+            }
+        }
+
+        for term in &self.limits {
+            writeln!(f, "  limited by {} (= {}) at {}", term.expr.expr, tristate_str(term.value), term.expr.location)?;
+        }
+
+        if let Some(choice) = &self.choice {
+            let selection = choice.selection.as_deref().unwrap_or("nothing");
+            let by = if choice.user_selected {
+                "the user"
+            } else {
+                "default"
+            };
+            writeln!(f, "  in choice at {}: {selection} selected by {by}", choice.location)?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Write a condition and its terms.
+fn write_condition(f: &mut Formatter, condition: &Condition) -> FmtResult {
+    writeln!(f, "    if {} (= {})", condition.expr.expr, tristate_str(condition.value))?;
+    for term in &condition.terms {
+        writeln!(f, "      {} = {} at {}", term.expr.expr, tristate_str(term.value), term.expr.location)?;
+    }
+    Ok(())
+}
+
+/// Returns the smaller of two tristate values.
+fn min(a: Tristate, b: Tristate) -> Tristate {
+    match (a, b) {
+        (Tristate::False, _) | (_, Tristate::False) => Tristate::False,
+        (Tristate::Maybe, _) | (_, Tristate::Maybe) => Tristate::Maybe,
+        _ => Tristate::True,
+    }
+}
+
+/// Split an expression into the operands of its top-level `&&` operators.
+fn conjuncts(expr: &LocExpr) -> Vec<&LocExpr> {
+    match &expr.expr {
+        Expr::And(lhs, rhs) => {
+            let mut result = conjuncts(lhs);
+            result.extend(conjuncts(rhs));
+            result
+        }
+        _ => vec![expr],
+    }
+}
+
+/// Returns the symbols and comparisons in an expression, excluding constants.
+fn atoms(expr: &LocExpr) -> Vec<&LocExpr> {
+    match &expr.expr {
+        Expr::And(lhs, rhs) | Expr::Or(lhs, rhs) => {
+            let mut result = atoms(lhs);
+            result.extend(atoms(rhs));
+            result
+        }
+        Expr::Not(e) => atoms(e),
+        Expr::Symbol(symbol) if parse_tristate(&symbol.name).is_none() => vec![expr],
+        Expr::Cmp(..) => vec![expr],
+        _ => Vec::new(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        crate::{
+            eval::{Evaluation, SymbolTable, UserValueStatus, ValueOrigin},
+            parser::{KConfig, PeekableChars, Tristate},
+        },
+        std::{collections::HashMap, path::Path},
+    };
+
+    const KCONFIG: &str = r##"
+config TARGET
+    string
+    default "esp32"
+
+config PSRAM_SUPPORTED
+    bool
+    default y if TARGET = "esp32"
+
+config FLASH_ONLY
+    bool "Flash only"
+
+config PSRAM
+    bool "Support for external PSRAM"
+    depends on PSRAM_SUPPORTED && !FLASH_ONLY
+    default y
+
+config BT
+    bool "Bluetooth"
+    select MEMORY if BT_LARGE || TARGET = "esp32"
+
+config BT_LARGE
+    bool "Large Bluetooth buffers"
+
+config MEMORY
+    bool "Extra memory"
+"##;
+
+    fn evaluation(user_values: &[(&str, &str)]) -> (Evaluation, HashMap<String, String>) {
+        let context = HashMap::new();
+        let kconfig =
+            KConfig::from_str(PeekableChars::new(KCONFIG, Path::new("test")), Path::new("/tmp"), &context).unwrap();
+        let mut eval = Evaluation::new(SymbolTable::new(&kconfig));
+        for (name, value) in user_values {
+            eval.set_user_value(name, *value);
+        }
+        eval.evaluate(&context).unwrap();
+        (eval, context)
+    }
+
+    #[test_log::test]
+    fn explain_default_and_limits() {
+        let (eval, context) = evaluation(&[]);
+        let why = eval.explain("PSRAM_SUPPORTED", &context).unwrap();
+        let default = why.default.unwrap();
+        assert_eq!(default.value.location.line, 8);
+        let condition = default.condition.unwrap();
+        assert_eq!(condition.value, Tristate::True);
+        assert_eq!(condition.terms.len(), 1);
+        assert_eq!(condition.terms[0].expr.expr.to_string(), "TARGET == \"esp32\"");
+
+        let (eval, context) = evaluation(&[("FLASH_ONLY", "y"), ("PSRAM", "y")]);
+        let why = eval.explain("PSRAM", &context).unwrap();
+        assert_eq!(why.value.to_string(), "n");
+        assert_eq!(why.user_value.unwrap().status, UserValueStatus::NotVisible);
+        assert_eq!(why.limits.len(), 1);
+        assert_eq!(why.limits[0].expr.expr.to_string(), "!FLASH_ONLY");
+        assert_eq!(why.limits[0].expr.location.line, 15);
+        assert_eq!(why.definitions[0].line, 13);
+
+        assert!(eval.explain("MISSING", &context).is_err());
+    }
+
+    #[test_log::test]
+    fn explain_select() {
+        let (eval, context) = evaluation(&[("BT", "y"), ("MEMORY", "n")]);
+        let why = eval.explain("MEMORY", &context).unwrap();
+        assert_eq!(why.value.to_string(), "y");
+        assert_eq!(why.origin, ValueOrigin::Selected);
+        assert_eq!(why.user_value.as_ref().unwrap().status, UserValueStatus::Selected);
+        assert_eq!(why.selects.len(), 1);
+        assert_eq!(why.selects[0].symbol, "BT");
+        assert_eq!(why.selects[0].location.line, 20);
+
+        let condition = why.selects[0].condition.as_ref().unwrap();
+        let terms: Vec<String> =
+            condition.terms.iter().map(|term| format!("{}={:?}", term.expr.expr, term.value)).collect();
+        assert_eq!(terms, vec!["BT_LARGE=False", "TARGET == \"esp32\"=True"]);
+
+        let text = why.to_string();
+        assert!(text.starts_with("MEMORY (bool) = y\n"), "{text}");
+        assert!(text.contains("  selected by BT (= y) at test 20:"), "{text}");
+        assert!(text.contains("  user value \"n\": overridden by select\n"), "{text}");
+    }
+}
@@ -0,0 +1,12 @@
+//! Kconfig symbol evaluation.
+
+mod check;
+mod diff;
+mod evaluation;
+mod explain;
+mod solver;
+mod table;
+mod targets;
+mod value;
+
+pub use {check::*, diff::*, evaluation::*, explain::*, solver::*, table::*, targets::*, value::*};
@@ -0,0 +1,771 @@
+use {
+    crate::{
+        eval::{parse_int, parse_tristate, Evaluation, SymbolTable, Value},
+        parser::{Expr, ExprCmpOp, KConfigError, LocExpr, Located, Location, Tristate, Type},
+        sdkconfig::CONFIG_PREFIX,
+        Context,
+    },
+    log::debug,
+    std::{
+        collections::HashMap,
+        fmt::{Display, Formatter, Result as FmtResult},
+        str::FromStr,
+    },
+};
+
+/// The deepest chain of requirements the solver follows before giving up.
+const MAX_DEPTH: usize = 32;
+
+/// The number of times the solver retries when satisfying one goal undoes another.
+const MAX_PASSES: usize = 3;
+
+/// A symbol value, used for solver goals and the assignments it produces.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Assignment {
+    /// The name of the symbol (without the `CONFIG_` prefix).
+    pub name: String,
+
+    /// The value, as written in `sdkconfig` without quotes.
+    pub value: String,
+}
+
+/// The result of [`Evaluation::solve()`].
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct Solution {
+    /// The user values to add to the configuration, in the order they were found.
+    pub assignments: Vec<Assignment>,
+
+    /// The goals that could not be met and why. If this is empty, the assignments meet every goal.
+    pub conflicts: Vec<Conflict>,
+}
+
+/// A goal that could not be met.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Conflict {
+    /// The requirements leading from the goal to the constraint that could not be met, starting with the goal.
+    pub path: Vec<String>,
+
+    /// Why the last requirement could not be met.
+    pub reason: String,
+
+    /// The location of the constraint, if known.
+    pub location: Option<Location>,
+}
+
+impl Assignment {
+    /// Create a new assignment.
+    pub fn new(name: impl Into<String>, value: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            value: value.into(),
+        }
+    }
+}
+
+/// Parses `NAME=VALUE`, with or without the `CONFIG_` prefix. Quotes around the value are removed.
+impl FromStr for Assignment {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let Some((name, value)) = s.split_once('=') else {
+            return Err(format!("expected NAME=VALUE, found {s:?}"));
+        };
+
+        let name = name.trim();
+        let name = name.strip_prefix(CONFIG_PREFIX).unwrap_or(name);
+        let value = value.trim();
+        let value = value.strip_prefix('"').and_then(|v| v.strip_suffix('"')).unwrap_or(value);
+        Ok(Self::new(name, value))
+    }
+}
+
+impl Display for Assignment {
+    fn fmt(&self, f: &mut Formatter) -> FmtResult {
+        write!(f, "{}={}", self.name, self.value)
+    }
+}
+
+impl Solution {
+    /// Returns `true` if every goal was met.
+    #[inline(always)]
+    pub fn is_satisfied(&self) -> bool {
+        self.conflicts.is_empty()
+    }
+}
+
+impl Display for Conflict {
+    fn fmt(&self, f: &mut Formatter) -> FmtResult {
+        write!(f, "{}: {}", self.path.join(" -> "), self.reason)?;
+        if let Some(location) = &self.location {
+            write!(f, " (at {location})")?;
+        }
+        Ok(())
+    }
+}
+
+impl Evaluation {
+    /// Find the user values to add to this configuration so each goal symbol has its goal value.
+    ///
+    /// The solver works backwards from each goal through prompt conditions, `depends on` conditions, choices, `select`
+    /// statements, and defaults, trying alternatives for `||` conditions, and checks each step by evaluating the
+    /// configuration. Assignments that turn out not to be needed are then removed. Goals that cannot be met are
+    /// reported as conflicts with the chain of requirements that failed.
+    ///
+    /// The evaluation itself is not changed; apply the assignments with
+    /// [`set_user_value()`][Evaluation::set_user_value] to use the solution.
+    pub fn solve<C>(&self, goals: &[Assignment], context: &C) -> Result<Solution, KConfigError>
+    where
+        C: Context,
+    {
+        let mut eval = self.clone();
+        eval.evaluate(context)?;
+
+        let mut solver = Solver {
+            table: self.table(),
+            base: self.user_values().clone(),
+            eval,
+            context,
+            assignments: Vec::new(),
+            required: HashMap::new(),
+            trail: Vec::new(),
+            conflict: None,
+        };
+
+        let mut conflicts = Vec::new();
+        let mut unmet: Vec<&Assignment> = goals.iter().collect();
+
+        for pass in 0..MAX_PASSES {
+            conflicts.clear();
+            solver.required.clear();
+
+            for goal in &unmet {
+                let snapshot = solver.snapshot();
+                if !solver.require(&goal.name, &goal.value, None)? {
+                    conflicts.push(solver.conflict.take().unwrap());
+                    solver.restore(snapshot)?;
+                }
+            }
+
+            let failed: Vec<&str> = conflicts.iter().map(|conflict| conflict.path[0].as_str()).collect();
+            unmet = goals
+                .iter()
+                .filter(|goal| !solver.satisfied(goal) && !failed.contains(&goal.to_string().as_str()))
+                .collect();
+
+            if unmet.is_empty() {
+                break;
+            }
+
+            debug!("Solver pass {pass} left goals unmet: {unmet:?}");
+        }
+
+        for goal in unmet {
+            conflicts.push(Conflict {
+                path: vec![goal.to_string()],
+                reason: "conflicts with the other goals".to_string(),
+                location: None,
+            });
+        }
+
+        // Drop assignments that the goals that were met do not need.
+        let met: Vec<&Assignment> = goals.iter().filter(|goal| solver.satisfied(goal)).collect();
+        let mut i = 0;
+        while i < solver.assignments.len() {
+            let removed = solver.assignments.remove(i);
+            solver.apply()?;
+
+            if met.iter().all(|goal| solver.satisfied(goal)) {
+                debug!("Assignment {removed} is not needed");
+            } else {
+                solver.assignments.insert(i, removed);
+                i += 1;
+            }
+        }
+
+        Ok(Solution {
+            assignments: solver.assignments,
+            conflicts,
+        })
+    }
+}
+
+/// Working state for [`Evaluation::solve()`].
+struct Solver<'a, C> {
+    /// The symbol table of the configuration being solved.
+    table: &'a SymbolTable,
+
+    /// The configuration with the assignments found so far applied.
+    eval: Evaluation,
+
+    /// The user values of the original configuration.
+    base: HashMap<String, String>,
+
+    context: &'a C,
+
+    /// The assignments found so far.
+    assignments: Vec<Assignment>,
+
+    /// The values required of symbols by the current goal, to detect contradictory requirements.
+    required: HashMap<String, String>,
+
+    /// The requirements being worked on, starting from the goal.
+    trail: Vec<String>,
+
+    /// The most recent failure.
+    conflict: Option<Conflict>,
+}
+
+/// A point to return to when an alternative fails.
+struct Snapshot {
+    assignments: usize,
+    required: HashMap<String, String>,
+}
+
+impl<'a, C> Solver<'a, C>
+where
+    C: Context,
+{
+    /// Require a symbol to have a value. This returns `false` and records a conflict if it cannot.
+    fn require(&mut self, name: &str, value: &str, location: Option<Location>) -> Result<bool, KConfigError> {
+        let label = format!("{name}={value}");
+
+        if let Some(existing) = self.required.get(name) {
+            if same_value(existing, value) {
+                return Ok(true);
+            }
+
+            let reason = format!("{name} is also required to be {existing}");
+            return Ok(self.fail(label, reason, location));
+        }
+
+        let Some(id) = self.table.symbol_id(name) else {
+            return Ok(self.fail(label, format!("{name} is not defined"), location));
+        };
+
+        if self.trail.len() >= MAX_DEPTH {
+            return Ok(self.fail(label, "the chain of dependencies is too deep".to_string(), location));
+        }
+
+        self.required.insert(name.to_string(), value.to_string());
+        if self.symbol_satisfied(id, value) {
+            return Ok(true);
+        }
+
+        self.trail.push(label);
+        let result = self.require_symbol(id, value, location);
+        self.trail.pop();
+        result
+    }
+
+    /// Find a way to give a symbol a value it does not have yet.
+    fn require_symbol(&mut self, id: usize, value: &str, location: Option<Location>) -> Result<bool, KConfigError> {
+        let table = self.table;
+        let symbol = &table.symbols()[id];
+
+        match symbol.r#type {
+            Type::Bool | Type::Tristate => {
+                let Some(target) = parse_tristate(value) else {
+                    let reason = format!("{value:?} is not a valid {} value", symbol.r#type);
+                    return Ok(self.fail_here(reason, location));
+                };
+
+                if target == Tristate::False {
+                    return self.require_disabled(id, location);
+                }
+
+                if let Some(choice) = symbol.choice {
+                    let info = &table.choices()[choice];
+                    let snapshot = self.snapshot();
+                    let mut ok = self.require_opt(info.dependency.as_ref(), true)?;
+                    if ok {
+                        if let Some(prompt) = &info.choice.prompt {
+                            ok = self.require_opt(prompt.condition.as_ref(), true)?;
+                        }
+                    }
+                    if ok && self.assign_and_check(id, value)? {
+                        return Ok(true);
+                    }
+                    self.restore(snapshot)?;
+                }
+
+                let snapshot = self.snapshot();
+
+                // Make the prompt visible and set the value directly.
+                for def in &symbol.definitions {
+                    let Some(prompt) = &def.config.prompt else {
+                        continue;
+                    };
+
+                    if self.require_opt(def.dependency.as_ref(), true)?
+                        && self.require_opt(def.visibility.as_ref(), true)?
+                        && self.require_opt(prompt.condition.as_ref(), true)?
+                        && self.assign_and_check(id, value)?
+                    {
+                        return Ok(true);
+                    }
+                    self.restore_keep_conflict(&snapshot)?;
+                }
+
+                // Enable a symbol that selects this one.
+                for rdep in &symbol.selected_by {
+                    let selector = &table.symbols()[rdep.symbol];
+                    let def = &selector.definitions[rdep.definition];
+                    if self.require(&selector.name, "y", Some(def.config.name.location()))?
+                        && self.require_opt(rdep.condition.as_ref(), true)?
+                        && self.require_opt(def.dependency.as_ref(), true)?
+                        && self.symbol_satisfied(id, value)
+                    {
+                        return Ok(true);
+                    }
+                    self.restore_keep_conflict(&snapshot)?;
+                }
+
+                // Meet the condition of a default.
+                for def in &symbol.definitions {
+                    for default in &def.config.defaults {
+                        if self.require_opt(def.dependency.as_ref(), true)?
+                            && self.require_opt(default.condition.as_ref(), true)?
+                            && self.require_expr(&default.value, true)?
+                            && self.symbol_satisfied(id, value)
+                        {
+                            return Ok(true);
+                        }
+                        self.restore_keep_conflict(&snapshot)?;
+                    }
+                }
+
+                if self.conflict.is_none() {
+                    self.fail_here(format!("{} cannot be enabled", symbol.name), location);
+                }
+                Ok(false)
+            }
+
+            Type::Int | Type::Hex | Type::String => {
+                if symbol.r#type != Type::String && parse_int(value, symbol.r#type).is_none() {
+                    let reason = format!("{value:?} is not a valid {} value", symbol.r#type);
+                    return Ok(self.fail_here(reason, location));
+                }
+
+                let snapshot = self.snapshot();
+                for def in &symbol.definitions {
+                    let Some(prompt) = &def.config.prompt else {
+                        continue;
+                    };
+
+                    if self.require_opt(def.dependency.as_ref(), true)?
+                        && self.require_opt(def.visibility.as_ref(), true)?
+                        && self.require_opt(prompt.condition.as_ref(), true)?
+                    {
+                        if self.assign_and_check(id, value)? {
+                            return Ok(true);
+                        }
+
+                        if let Some((low, high)) = self.eval.symbol_state(id).range {
+                            let reason = format!("{value} is outside the range {low} to {high}");
+                            return Ok(self.fail_here(reason, location));
+                        }
+                    }
+                    self.restore_keep_conflict(&snapshot)?;
+                }
+
+                if self.conflict.is_none() {
+                    self.fail_here(format!("{} has no prompt and cannot be set", symbol.name), location);
+                }
+                Ok(false)
+            }
+
+            Type::Unknown => Ok(self.fail_here(format!("{} has no type", symbol.name), location)),
+        }
+    }
+
+    /// Find a way to disable a `bool` or `tristate` symbol.
+    fn require_disabled(&mut self, id: usize, location: Option<Location>) -> Result<bool, KConfigError> {
+        let table = self.table;
+        let symbol = &table.symbols()[id];
+
+        // Every active select must be turned off, either by disabling the selecting symbol or its condition.
+        for rdep in &symbol.selected_by {
+            let selector = &table.symbols()[rdep.symbol];
+            if self.eval.symbol_state(rdep.symbol).value.tristate() == Tristate::False {
+                continue;
+            }
+
+            let snapshot = self.snapshot();
+            let def = &selector.definitions[rdep.definition];
+            if self.require(&selector.name, "n", Some(def.config.name.location()))? {
+                continue;
+            }
+            self.restore_keep_conflict(&snapshot)?;
+
+            match &rdep.condition {
+                Some(condition) if self.require_expr(condition, false)? => (),
+                _ => return Ok(false),
+            }
+        }
+
+        if self.symbol_satisfied(id, "n") {
+            return Ok(true);
+        }
+
+        if self.eval.symbol_state(id).visibility != Tristate::False && self.assign_and_check(id, "n")? {
+            return Ok(true);
+        }
+
+        // Without a visible prompt, the symbol is disabled when its dependencies are not met.
+        let snapshot = self.snapshot();
+        for def in &symbol.definitions {
+            if let Some(dependency) = &def.dependency {
+                if self.require_expr(dependency, false)? && self.symbol_satisfied(id, "n") {
+                    return Ok(true);
+                }
+                self.restore_keep_conflict(&snapshot)?;
+            }
+        }
+
+        if self.conflict.is_none() {
+            self.fail_here(format!("{} cannot be disabled", symbol.name), location);
+        }
+        Ok(false)
+    }
+
+    /// Require an optional condition to be true or false. A missing condition is always true.
+    fn require_opt(&mut self, expr: Option<&LocExpr>, want: bool) -> Result<bool, KConfigError> {
+        match expr {
+            Some(expr) => self.require_expr(expr, want),
+            None if want => Ok(true),
+            None => Ok(self.fail_here("the condition is always true".to_string(), None)),
+        }
+    }
+
+    /// Require an expression to be true (`y` or `m`) or false (`n`).
+    fn require_expr(&mut self, expr: &LocExpr, want: bool) -> Result<bool, KConfigError> {
+        if (self.eval.eval_expr(expr, self.context)? != Tristate::False) == want {
+            return Ok(true);
+        }
+
+        let location = Some(expr.location);
+        match &expr.expr {
+            Expr::Not(e) => self.require_expr(e, !want),
+
+            Expr::And(lhs, rhs) | Expr::Or(lhs, rhs) => {
+                // `a && b` must have both sides true, and `a || b` both sides false; otherwise either side will do.
+                let both = matches!(expr.expr, Expr::And(..)) == want;
+                if both {
+                    return Ok(self.require_expr(lhs, want)? && self.require_expr(rhs, want)?);
+                }
+
+                let snapshot = self.snapshot();
+                if self.require_expr(lhs, want)? {
+                    return Ok(true);
+                }
+                self.restore_keep_conflict(&snapshot)?;
+                self.require_expr(rhs, want)
+            }
+
+            Expr::Symbol(symbol) => {
+                if parse_tristate(&symbol.name).is_some() {
+                    let reason = format!("the condition {} is constant", expr.expr);
+                    return Ok(self.fail(expr.expr.to_string(), reason, location));
+                }
+
+                let value = if want {
+                    "y"
+                } else {
+                    "n"
+                };
+                self.require(&symbol.name, value, location)
+            }
+
+            Expr::Cmp(op, lhs, rhs) => {
+                let assign = matches!((op, want), (ExprCmpOp::Eq, true) | (ExprCmpOp::Ne, false));
+                let target = match (&lhs.expr, &rhs.expr) {
+                    (Expr::Symbol(symbol), other) | (other, Expr::Symbol(symbol))
+                        if assign && self.table.symbol_id(&symbol.name).is_some() =>
+                    {
+                        constant_text(other, self.table).map(|value| (symbol.name.clone(), value))
+                    }
+                    _ => None,
+                };
+
+                match target {
+                    Some((name, value)) => self.require(&name, &value, location),
+                    None => {
+                        let reason = format!("the comparison {} cannot be changed", expr.expr);
+                        Ok(self.fail(expr.expr.to_string(), reason, location))
+                    }
+                }
+            }
+
+            _ => {
+                let reason = format!("the condition {} is constant", expr.expr);
+                Ok(self.fail(expr.expr.to_string(), reason, location))
+            }
+        }
+    }
+
+    /// Set a user value and check that the symbol has the value afterwards.
+    fn assign_and_check(&mut self, id: usize, value: &str) -> Result<bool, KConfigError> {
+        let name = self.table.symbols()[id].name.clone();
+        self.assignments.retain(|assignment| assignment.name != name);
+        if self.base.get(&name).map(String::as_str) != Some(value) {
+            self.assignments.push(Assignment::new(name, value));
+        }
+        self.apply()?;
+
+        if self.symbol_satisfied(id, value) {
+            Ok(true)
+        } else {
+            let state = self.eval.symbol_state(id);
+            let reason = format!("setting it gives {} instead", state.value);
+            Ok(self.fail_here(reason, None))
+        }
+    }
+
+    /// Rebuild the user values from the original configuration and the assignments, and evaluate them.
+    fn apply(&mut self) -> Result<(), KConfigError> {
+        self.eval.clear_user_values();
+        for (name, value) in &self.base {
+            self.eval.set_user_value(name, value.clone());
+        }
+        for assignment in &self.assignments {
+            self.eval.set_user_value(&assignment.name, assignment.value.clone());
+        }
+        self.eval.evaluate(self.context)
+    }
+
+    fn snapshot(&self) -> Snapshot {
+        Snapshot {
+            assignments: self.assignments.len(),
+            required: self.required.clone(),
+        }
+    }
+
+    /// Return to a snapshot, forgetting the most recent failure.
+    fn restore(&mut self, snapshot: Snapshot) -> Result<(), KConfigError> {
+        self.conflict = None;
+        self.restore_keep_conflict(&snapshot)
+    }
+
+    /// Return to a snapshot, keeping the most recent failure to report if no alternative succeeds.
+    fn restore_keep_conflict(&mut self, snapshot: &Snapshot) -> Result<(), KConfigError> {
+        self.required = snapshot.required.clone();
+        if self.assignments.len() != snapshot.assignments {
+            self.assignments.truncate(snapshot.assignments);
+            self.apply()?;
+        }
+        Ok(())
+    }
+
+    /// Returns `true` if a goal is met.
+    fn satisfied(&self, goal: &Assignment) -> bool {
+        match self.table.symbol_id(&goal.name) {
+            Some(id) => self.symbol_satisfied(id, &goal.value),
+            None => false,
+        }
+    }
+
+    /// Returns `true` if a symbol has a value.
+    fn symbol_satisfied(&self, id: usize, value: &str) -> bool {
+        let r#type = self.table.symbols()[id].r#type;
+        match (&self.eval.symbol_state(id).value, r#type) {
+            (Value::Tristate(t), _) => parse_tristate(value) == Some(*t),
+            (Value::String(s), Type::Int | Type::Hex) => match (parse_int(s, r#type), parse_int(value, r#type)) {
+                (Some(a), Some(b)) => a == b,
+                _ => false,
+            },
+            (Value::String(s), _) => s == value,
+        }
+    }
+
+    /// Record a failure of the requirement being worked on.
+    fn fail_here(&mut self, reason: String, location: Option<Location>) -> bool {
+        self.conflict = Some(Conflict {
+            path: self.trail.clone(),
+            reason,
+            location,
+        });
+        false
+    }
+
+    /// Record a failure of a requirement that is not on the trail.
+    fn fail(&mut self, label: String, reason: String, location: Option<Location>) -> bool {
+        let mut path = self.trail.clone();
+        path.push(label);
+        self.conflict = Some(Conflict {
+            path,
+            reason,
+            location,
+        });
+        false
+    }
+}
+
+/// Returns `true` if two values are the same, treating `y`/`n`/`m` and numbers leniently.
+fn same_value(a: &str, b: &str) -> bool {
+    match (parse_tristate(a), parse_tristate(b)) {
+        (Some(a), Some(b)) => a == b,
+        _ => a == b || parse_int(a, Type::Unknown).is_some_and(|a| parse_int(b, Type::Unknown) == Some(a)),
+    }
+}
+
+/// Returns the text of a constant operand of a comparison. Undefined symbols are constants equal to their names.
+fn constant_text(expr: &Expr, table: &SymbolTable) -> Option<String> {
+    match expr {
+        Expr::String(s) => Some(s.clone()),
+        Expr::Int(i) => Some(i.to_string()),
+        Expr::Hex(h) => Some(format!("0x{h:x}")),
+        Expr::Symbol(symbol) if table.symbol_id(&symbol.name).is_none() => Some(symbol.name.clone()),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        crate::{
+            eval::{Assignment, Evaluation, SymbolTable},
+            parser::{KConfig, PeekableChars},
+        },
+        std::{collections::HashMap, path::Path},
+    };
+
+    const KCONFIG: &str = r##"
+config TARGET
+    string
+    default "esp32"
+
+config SOC_PSRAM
+    bool
+    default y if TARGET = "esp32"
+
+config RAM_APP
+    bool "RAM-only app"
+
+menu "PSRAM"
+    depends on SOC_PSRAM && !RAM_APP
+
+config SPIRAM
+    bool "Support for external PSRAM"
+
+choice SPIRAM_MODE
+    prompt "Mode"
+    depends on SPIRAM
+
+    config SPIRAM_MODE_QUAD
+        bool "Quad"
+    config SPIRAM_MODE_OCT
+        bool "Octal"
+endchoice
+
+config SPIRAM_SIZE
+    int "Size"
+    depends on SPIRAM
+    range 1 16
+    default 4
+endmenu
+
+config BT_CONTROLLER
+    bool "Bluetooth controller"
+    depends on BT_ENABLED
+
+config BT_ENABLED
+    bool "Bluetooth"
+    depends on RADIO || DUMMY_RADIO
+
+config RADIO
+    bool "Radio"
+
+config DUMMY_RADIO
+    bool
+
+config MESH_SUPPORT
+    bool
+
+config BLE_MESH
+    bool "BLE mesh"
+    depends on BT_ENABLED
+    select MESH_SUPPORT
+
+config WIFI
+    bool "Wi-Fi"
+    depends on TARGET = "esp32c2"
+"##;
+
+    fn evaluation(user_values: &[(&str, &str)]) -> (Evaluation, HashMap<String, String>) {
+        let context = HashMap::new();
+        let kconfig =
+            KConfig::from_str(PeekableChars::new(KCONFIG, Path::new("test")), Path::new("/tmp"), &context).unwrap();
+        let mut eval = Evaluation::new(SymbolTable::new(&kconfig));
+        for (name, value) in user_values {
+            eval.set_user_value(name, *value);
+        }
+        eval.evaluate(&context).unwrap();
+        (eval, context)
+    }
+
+    fn goals(goals: &[&str]) -> Vec<Assignment> {
+        goals.iter().map(|goal| goal.parse().unwrap()).collect()
+    }
+
+    /// Solve the goals, check that applying the solution meets them, and return the assignments.
+    fn solve(eval: &Evaluation, goals: &[Assignment], context: &HashMap<String, String>) -> Vec<String> {
+        let solution = eval.solve(goals, context).unwrap();
+        assert!(solution.is_satisfied(), "{:?}", solution.conflicts);
+
+        let mut eval = eval.clone();
+        for assignment in &solution.assignments {
+            eval.set_user_value(&assignment.name, assignment.value.clone());
+        }
+        eval.evaluate(context).unwrap();
+        for goal in goals {
+            assert_eq!(eval.value(&goal.name).unwrap().to_string(), goal.value);
+        }
+
+        solution.assignments.iter().map(ToString::to_string).collect()
+    }
+
+    #[test_log::test]
+    fn solve_goals() {
+        let (eval, context) = evaluation(&[("RAM_APP", "y")]);
+        assert_eq!(
+            solve(&eval, &goals(&["CONFIG_SPIRAM_MODE_OCT=y", "SPIRAM_SIZE=8"]), &context),
+            vec!["RAM_APP=n", "SPIRAM=y", "SPIRAM_MODE_OCT=y", "SPIRAM_SIZE=8"]
+        );
+
+        // The first alternative of `||` that works is used.
+        assert_eq!(
+            solve(&eval, &goals(&["BT_CONTROLLER=y"]), &context),
+            vec!["RADIO=y", "BT_ENABLED=y", "BT_CONTROLLER=y"]
+        );
+
+        // Symbols without prompts are enabled through selects and disabled by turning the selects off.
+        assert_eq!(solve(&eval, &goals(&["MESH_SUPPORT=y"]), &context), vec!["RADIO=y", "BT_ENABLED=y", "BLE_MESH=y"]);
+
+        let (eval, context) = evaluation(&[("RADIO", "y"), ("BT_ENABLED", "y"), ("BLE_MESH", "y")]);
+        assert_eq!(solve(&eval, &goals(&["MESH_SUPPORT=n"]), &context), vec!["BLE_MESH=n"]);
+
+        // Goals that are already met need nothing.
+        assert!(solve(&eval, &goals(&["SOC_PSRAM=y", "RADIO=y"]), &context).is_empty());
+    }
+
+    #[test_log::test]
+    fn solve_conflicts() {
+        let (eval, context) = evaluation(&[]);
+
+        let solution = eval.solve(&goals(&["WIFI=y", "RADIO=y"]), &context).unwrap();
+        assert_eq!(solution.assignments, vec![Assignment::new("RADIO", "y")]);
+        assert_eq!(solution.conflicts.len(), 1);
+        let conflict = &solution.conflicts[0];
+        assert_eq!(conflict.path, vec!["WIFI=y", "TARGET=esp32c2"]);
+        assert_eq!(conflict.reason, "TARGET has no prompt and cannot be set");
+        assert_eq!(conflict.location.unwrap().line, 60);
+
+        let solution = eval.solve(&goals(&["DUMMY_RADIO=y"]), &context).unwrap();
+        assert_eq!(solution.conflicts[0].to_string(), "DUMMY_RADIO=y: DUMMY_RADIO cannot be enabled");
+
+        let solution = eval.solve(&goals(&["RAM_APP=y", "SPIRAM=y"]), &context).unwrap();
+        assert_eq!(solution.assignments, vec![Assignment::new("RAM_APP", "y")]);
+        assert_eq!(solution.conflicts[0].path, vec!["SPIRAM=y", "RAM_APP=n"]);
+        assert_eq!(solution.conflicts[0].reason, "RAM_APP is also required to be y");
+    }
+}
@@ -0,0 +1,423 @@
+use {
+    crate::parser::{Block, Choice, Config, Expr, KConfig, LocExpr, LocString, Located, Type},
+    log::debug,
+    std::{cell::RefCell, collections::HashMap, rc::Rc},
+};
+
+/// The symbols, choices, and menu structure of a resolved Kconfig tree.
+///
+/// Dependencies from enclosing menus, choices, and `if` blocks are propagated onto each definition so that symbols can
+/// be evaluated without walking the tree.
+#[derive(Clone, Debug, Default)]
+pub struct SymbolTable {
+    /// The symbols in the order they first appear.
+    symbols: Vec<Symbol>,
+
+    /// Index into `symbols` by name.
+    by_name: HashMap<String, usize>,
+
+    /// The choices in the order they appear.
+    choices: Vec<ChoiceInfo>,
+
+    /// The top-level entries of the menu tree.
+    menu: Vec<MenuNode>,
+
+    /// The number of menus in the menu tree.
+    menu_count: usize,
+
+    /// The title from the `mainmenu` statement, if any.
+    mainmenu: Option<LocString>,
+}
+
+/// A symbol and all of its definitions.
+#[derive(Clone, Debug)]
+pub struct Symbol {
+    /// The name of the symbol (without the `CONFIG_` prefix).
+    pub name: String,
+
+    /// The type of the symbol, taken from the first definition that specifies one.
+    pub r#type: Type,
+
+    /// The `config` and `menuconfig` entries defining the symbol.
+    pub definitions: Vec<Definition>,
+
+    /// The index of the choice this symbol belongs to, if any.
+    pub choice: Option<usize>,
+
+    /// `select` statements targeting this symbol.
+    pub selected_by: Vec<ReverseDep>,
+
+    /// `imply` statements targeting this symbol.
+    pub implied_by: Vec<ReverseDep>,
+}
+
+/// A single `config` or `menuconfig` entry for a symbol.
+#[derive(Clone, Debug)]
+pub struct Definition {
+    /// The entry as parsed.
+    pub config: Config,
+
+    /// Whether this is a `menuconfig` entry.
+    pub menuconfig: bool,
+
+    /// The entry's `depends on` statements combined with the dependencies of the enclosing menus, choices, and `if`
+    /// blocks. If `None`, the entry has no dependencies.
+    pub dependency: Option<LocExpr>,
+
+    /// `visible if` conditions of the enclosing menus. These limit the visibility of the prompt but not the value.
+    pub visibility: Option<LocExpr>,
+
+    /// The prompts of the enclosing menus, outermost first.
+    pub menu_path: Vec<String>,
+}
+
+/// A `select` or `imply` statement targeting a symbol.
+#[derive(Clone, Debug)]
+pub struct ReverseDep {
+    /// The index of the symbol containing the statement.
+    pub symbol: usize,
+
+    /// The index of the definition containing the statement.
+    pub definition: usize,
+
+    /// The condition on the statement, if any.
+    pub condition: Option<LocExpr>,
+}
+
+/// A `choice` entry.
+#[derive(Clone, Debug)]
+pub struct ChoiceInfo {
+    /// The entry as parsed.
+    pub choice: Choice,
+
+    /// The choice's `depends on` statements combined with the dependencies of the enclosing menus and `if` blocks.
+    pub dependency: Option<LocExpr>,
+
+    /// `visible if` conditions of the enclosing menus.
+    pub visibility: Option<LocExpr>,
+
+    /// The indices of the symbols that can be chosen, in order.
+    pub members: Vec<usize>,
+
+    /// The prompts of the enclosing menus, outermost first.
+    pub menu_path: Vec<String>,
+}
+
+/// A node in the menu tree.
+#[derive(Clone, Debug)]
+pub struct MenuNode {
+    /// The entry at this node.
+    pub item: MenuItem,
+
+    /// The dependencies of the entry, including those inherited from enclosing entries.
+    pub dependency: Option<LocExpr>,
+
+    /// The entries within this one (for menus and choices).
+    pub children: Vec<MenuNode>,
+}
+
+/// An entry in the menu tree.
+#[derive(Clone, Debug)]
+pub enum MenuItem {
+    /// A `menu` block.
+    Menu {
+        /// The index of the menu, used to look up its state in an [`Evaluation`][crate::eval::Evaluation].
+        id: usize,
+
+        /// The title of the menu.
+        prompt: LocString,
+
+        /// The `visible if` condition of the menu, if any.
+        visibility: Option<LocExpr>,
+    },
+
+    /// A `config` or `menuconfig` entry.
+    Symbol {
+        /// The index of the symbol.
+        symbol: usize,
+
+        /// The index of the definition within the symbol.
+        definition: usize,
+    },
+
+    /// A `choice` block. The members of the choice are the children of the node.
+    Choice(usize),
+}
+
+impl SymbolTable {
+    /// Build a symbol table from a resolved Kconfig tree.
+    pub fn new(kconfig: &KConfig) -> Self {
+        let mut result = Self::default();
+        let mut menu_path = Vec::new();
+        result.menu = result.add_blocks(&kconfig.blocks, None, None, &mut menu_path);
+        result.add_reverse_deps();
+        result
+    }
+
+    /// Returns all symbols in the order they first appear.
+    #[inline(always)]
+    pub fn symbols(&self) -> &[Symbol] {
+        &self.symbols
+    }
+
+    /// Returns the symbol with the given name, if it exists.
+    pub fn symbol(&self, name: &str) -> Option<&Symbol> {
+        self.by_name.get(name).map(|&id| &self.symbols[id])
+    }
+
+    /// Returns the index of the symbol with the given name, if it exists.
+    #[inline(always)]
+    pub fn symbol_id(&self, name: &str) -> Option<usize> {
+        self.by_name.get(name).copied()
+    }
+
+    /// Returns all choices in the order they appear.
+    #[inline(always)]
+    pub fn choices(&self) -> &[ChoiceInfo] {
+        &self.choices
+    }
+
+    /// Returns the top-level entries of the menu tree.
+    #[inline(always)]
+    pub fn menu(&self) -> &[MenuNode] {
+        &self.menu
+    }
+
+    /// Returns the number of `menu` blocks in the menu tree.
+    #[inline(always)]
+    pub fn menu_count(&self) -> usize {
+        self.menu_count
+    }
+
+    /// Returns the title from the `mainmenu` statement, if any.
+    #[inline(always)]
+    pub fn mainmenu(&self) -> Option<&LocString> {
+        self.mainmenu.as_ref()
+    }
+
+    fn add_blocks(
+        &mut self,
+        blocks: &[Rc<RefCell<Block>>],
+        dependency: Option<&LocExpr>,
+        visibility: Option<&LocExpr>,
+        menu_path: &mut Vec<String>,
+    ) -> Vec<MenuNode> {
+        let mut nodes = Vec::with_capacity(blocks.len());
+
+        for block in blocks {
+            match &*block.borrow() {
+                Block::Config(config) | Block::MenuConfig(config) => {
+                    let menuconfig = matches!(&*block.borrow(), Block::MenuConfig(_));
+                    let dependency = and_all(dependency, &config.depends_on);
+                    let (symbol, definition) =
+                        self.add_definition(config, menuconfig, dependency.clone(), visibility, menu_path, None);
+
+                    nodes.push(MenuNode {
+                        item: MenuItem::Symbol {
+                            symbol,
+                            definition,
+                        },
+                        dependency,
+                        children: Vec::new(),
+                    });
+                }
+
+                Block::Choice(choice) => nodes.push(self.add_choice(choice, dependency, visibility, menu_path)),
+
+                Block::If(if_block) => {
+                    let dependency = and(dependency, Some(&if_block.condition));
+                    nodes.extend(self.add_blocks(&if_block.items, dependency.as_ref(), visibility, menu_path));
+                }
+
+                Block::Mainmenu(title) => self.mainmenu = Some(title.clone()),
+
+                Block::Menu(menu) => {
+                    let id = self.menu_count;
+                    self.menu_count += 1;
+
+                    let dependency = and_all(dependency, &menu.depends_on);
+                    let child_visibility = and(visibility, menu.visibility.as_ref());
+
+                    menu_path.push(menu.prompt.to_string());
+                    let children =
+                        self.add_blocks(&menu.blocks, dependency.as_ref(), child_visibility.as_ref(), menu_path);
+                    menu_path.pop();
+
+                    nodes.push(MenuNode {
+                        item: MenuItem::Menu {
+                            id,
+                            prompt: menu.prompt.clone(),
+                            visibility: menu.visibility.clone(),
+                        },
+                        dependency,
+                        children,
+                    });
+                }
+
+                Block::Source(source) => {
+                    debug!("Ignoring unresolved source statement: {:?}", source.filename);
+                }
+            }
+        }
+
+        nodes
+    }
+
+    fn add_choice(
+        &mut self,
+        choice: &Choice,
+        dependency: Option<&LocExpr>,
+        visibility: Option<&LocExpr>,
+        menu_path: &[String],
+    ) -> MenuNode {
+        let id = self.choices.len();
+        let dependency = and_all(dependency, &choice.depends_on);
+
+        self.choices.push(ChoiceInfo {
+            choice: choice.clone(),
+            dependency: dependency.clone(),
+            visibility: visibility.cloned(),
+            members: Vec::with_capacity(choice.configs.len()),
+            menu_path: menu_path.to_vec(),
+        });
+
+        let mut children = Vec::with_capacity(choice.configs.len());
+
+        for config in &choice.configs {
+            let member_dependency = and_all(dependency.as_ref(), &config.depends_on);
+            let (symbol, definition) =
+                self.add_definition(config, false, member_dependency.clone(), visibility, menu_path, Some(id));
+
+            if !self.choices[id].members.contains(&symbol) {
+                self.choices[id].members.push(symbol);
+            }
+
+            children.push(MenuNode {
+                item: MenuItem::Symbol {
+                    symbol,
+                    definition,
+                },
+                dependency: member_dependency,
+                children: Vec::new(),
+            });
+        }
+
+        MenuNode {
+            item: MenuItem::Choice(id),
+            dependency,
+            children,
+        }
+    }
+
+    fn add_definition(
+        &mut self,
+        config: &Config,
+        menuconfig: bool,
+        dependency: Option<LocExpr>,
+        visibility: Option<&LocExpr>,
+        menu_path: &[String],
+        choice: Option<usize>,
+    ) -> (usize, usize) {
+        let name = config.name.as_str();
+        let id = match self.by_name.get(name) {
+            Some(&id) => id,
+            None => {
+                let id = self.symbols.len();
+                self.symbols.push(Symbol {
+                    name: name.to_string(),
+                    r#type: Type::Unknown,
+                    definitions: Vec::new(),
+                    choice: None,
+                    selected_by: Vec::new(),
+                    implied_by: Vec::new(),
+                });
+                self.by_name.insert(name.to_string(), id);
+                id
+            }
+        };
+
+        let symbol = &mut self.symbols[id];
+
+        if symbol.r#type == Type::Unknown {
+            symbol.r#type = config.r#type;
+        }
+
+        if choice.is_some() {
+            symbol.choice = choice;
+
+            // Choice members take on the type of the choice, which is always bool here.
+            if symbol.r#type == Type::Unknown {
+                symbol.r#type = Type::Bool;
+            }
+        }
+
+        symbol.definitions.push(Definition {
+            config: config.clone(),
+            menuconfig,
+            dependency,
+            visibility: visibility.cloned(),
+            menu_path: menu_path.to_vec(),
+        });
+
+        (id, symbol.definitions.len() - 1)
+    }
+
+    fn add_reverse_deps(&mut self) {
+        let mut selects = Vec::new();
+        let mut implies = Vec::new();
+
+        for (id, symbol) in self.symbols.iter().enumerate() {
+            for (definition, def) in symbol.definitions.iter().enumerate() {
+                for target in &def.config.selects {
+                    selects.push((target.target_name.to_string(), id, definition, target.condition.clone()));
+                }
+
+                for target in &def.config.implies {
+                    implies.push((target.target_name.to_string(), id, definition, target.condition.clone()));
+                }
+            }
+        }
+
+        for (target, symbol, definition, condition) in selects {
+            let Some(&target_id) = self.by_name.get(&target) else {
+                debug!("Ignoring select of undefined symbol {target}");
+                continue;
+            };
+
+            self.symbols[target_id].selected_by.push(ReverseDep {
+                symbol,
+                definition,
+                condition,
+            });
+        }
+
+        for (target, symbol, definition, condition) in implies {
+            let Some(&target_id) = self.by_name.get(&target) else {
+                debug!("Ignoring imply of undefined symbol {target}");
+                continue;
+            };
+
+            self.symbols[target_id].implied_by.push(ReverseDep {
+                symbol,
+                definition,
+                condition,
+            });
+        }
+    }
+}
+
+/// Combine two optional conditions with `&&`.
+pub(crate) fn and(lhs: Option<&LocExpr>, rhs: Option<&LocExpr>) -> Option<LocExpr> {
+    match (lhs, rhs) {
+        (None, None) => None,
+        (Some(e), None) | (None, Some(e)) => Some(e.clone()),
+        (Some(lhs), Some(rhs)) => {
+            Some(LocExpr::new(Expr::And(Box::new(lhs.clone()), Box::new(rhs.clone())), rhs.location()))
+        }
+    }
+}
+
+/// Combine an optional condition with a list of `depends on` expressions using `&&`.
+fn and_all(lhs: Option<&LocExpr>, exprs: &[LocExpr]) -> Option<LocExpr> {
+    exprs.iter().fold(lhs.cloned(), |acc, expr| and(acc.as_ref(), Some(expr)))
+}
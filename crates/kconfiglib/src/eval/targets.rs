@@ -0,0 +1,460 @@
+use {
+    crate::{
+        eval::{Evaluation, MenuItem, MenuNode, Value},
+        parser::{KConfigError, Tristate, Type},
+        Context,
+    },
+    log::{debug, warn},
+};
+
+/// The standard ways of generating a configuration non-interactively, as in the Linux kernel's `make *config` targets.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum ConfigGenerator {
+    /// Keep the current user values and use defaults for everything else.
+    OldDefConfig,
+
+    /// Enable every `bool` and `tristate` option that can be enabled.
+    AllYesConfig,
+
+    /// Disable every `bool` and `tristate` option that can be disabled.
+    AllNoConfig,
+
+    /// Use the default value for every option.
+    AllDefConfig,
+
+    /// Choose random values with the given seed.
+    RandConfig(u64),
+}
+
+impl Evaluation {
+    /// Generate a configuration and evaluate it.
+    pub fn generate<C>(&mut self, generator: ConfigGenerator, context: &C) -> Result<(), KConfigError>
+    where
+        C: Context,
+    {
+        match generator {
+            ConfigGenerator::OldDefConfig => self.olddefconfig(context),
+            ConfigGenerator::AllYesConfig => self.allyesconfig(context),
+            ConfigGenerator::AllNoConfig => self.allnoconfig(context),
+            ConfigGenerator::AllDefConfig => self.alldefconfig(context),
+            ConfigGenerator::RandConfig(seed) => self.randconfig(seed, context),
+        }
+    }
+
+    /// Keep the current user values and use defaults for all other options. User values for symbols that are not
+    /// defined are dropped.
+    pub fn olddefconfig<C>(&mut self, context: &C) -> Result<(), KConfigError>
+    where
+        C: Context,
+    {
+        let mut undefined: Vec<String> =
+            self.user_values().keys().filter(|name| self.table().symbol_id(name).is_none()).cloned().collect();
+        undefined.sort();
+
+        for name in undefined {
+            debug!("Dropping value for undefined symbol {name}");
+            self.unset_user_value(&name);
+        }
+
+        self.evaluate(context)
+    }
+
+    /// Set every `bool` and `tristate` option to `y` where its dependencies allow. Choices keep their default
+    /// selection.
+    pub fn allyesconfig<C>(&mut self, context: &C) -> Result<(), KConfigError>
+    where
+        C: Context,
+    {
+        self.set_all_bools("y");
+        self.evaluate(context)
+    }
+
+    /// Set every `bool` and `tristate` option to `n` unless it is selected by another option. Choices keep their
+    /// default selection.
+    pub fn allnoconfig<C>(&mut self, context: &C) -> Result<(), KConfigError>
+    where
+        C: Context,
+    {
+        self.set_all_bools("n");
+        self.evaluate(context)
+    }
+
+    /// Use the default value for every option, discarding all user values.
+    pub fn alldefconfig<C>(&mut self, context: &C) -> Result<(), KConfigError>
+    where
+        C: Context,
+    {
+        self.clear_user_values();
+        self.evaluate(context)
+    }
+
+    /// Choose random values for every option, using `seed` to make the result reproducible.
+    ///
+    /// `bool` and `tristate` options are set randomly and each choice selects a random member. `int` and `hex` options
+    /// with an active `range` are set to a random value within it; other `int`, `hex`, and `string` options keep their
+    /// defaults. Dependencies and `select` statements are honored as usual, so the result is always a valid
+    /// configuration.
+    pub fn randconfig<C>(&mut self, seed: u64, context: &C) -> Result<(), KConfigError>
+    where
+        C: Context,
+    {
+        let mut rng = SplitMix64(seed);
+        self.clear_user_values();
+
+        let symbols = self.table().symbols().to_vec();
+        for symbol in &symbols {
+            let value = match symbol.r#type {
+                _ if symbol.choice.is_some() => continue,
+                Type::Bool => ["n", "y"][rng.below(2) as usize],
+                Type::Tristate => ["n", "m", "y"][rng.below(3) as usize],
+                _ => continue,
+            };
+
+            self.set_user_value(&symbol.name, value);
+        }
+
+        let choices: Vec<Vec<usize>> = self.table().choices().iter().map(|choice| choice.members.clone()).collect();
+        for members in choices {
+            if members.is_empty() {
+                continue;
+            }
+
+            let member = members[rng.below(members.len() as u128) as usize];
+            self.set_user_value(&symbols[member].name, "y");
+        }
+
+        // Ranges may depend on the values chosen above, so pick numbers once those are known.
+        self.evaluate(context)?;
+
+        for (id, symbol) in symbols.iter().enumerate() {
+            let Some((low, high)) = self.symbol_state(id).range else {
+                continue;
+            };
+
+            if high < low {
+                continue;
+            }
+
+            let value = low + rng.below((high - low) as u128 + 1) as i128;
+            let value = match symbol.r#type {
+                Type::Hex => format!("{value:#x}"),
+                _ => value.to_string(),
+            };
+
+            self.set_user_value(&symbol.name, value);
+        }
+
+        self.evaluate(context)
+    }
+
+    /// Returns an evaluation whose user values are the smallest set that reproduces this configuration, as written by
+    /// the Linux kernel's `make savedefconfig`.
+    ///
+    /// As in kconfiglib, each option the user can change is kept only if its value differs from the value it would
+    /// have without a user value, which drops values equal to their computed defaults and values forced by `select`. A
+    /// choice member is kept only if it is selected and is not the choice's default selection. Write the result with
+    /// [`SdkConfigWriter::write_defaults()`][crate::sdkconfig::SdkConfigWriter::write_defaults].
+    pub fn savedefconfig<C>(&self, context: &C) -> Result<Evaluation, KConfigError>
+    where
+        C: Context,
+    {
+        let mut result = self.clone();
+        result.clear_user_values();
+
+        for id in menu_symbols(self) {
+            let symbol = &self.table().symbols()[id];
+            let state = self.symbol_state(id);
+            if state.visibility == Tristate::False || !state.write {
+                continue;
+            }
+
+            match symbol.choice {
+                Some(choice) => {
+                    let selection = self.choice_state(choice).selection;
+                    if selection == Some(id) && self.default_selection(choice, context)? != selection {
+                        result.set_user_value(&symbol.name, "y");
+                    }
+                }
+                None => {
+                    if state.value != self.default_value(id, context)? {
+                        result.set_user_value(&symbol.name, state.value.to_string());
+                    }
+                }
+            }
+        }
+
+        result.evaluate(context)?;
+        if result.configuration() != self.configuration() {
+            warn!("Configuration cannot be reproduced from the values of visible options");
+        }
+
+        Ok(result)
+    }
+
+    /// Returns the value and written flag of every symbol, for comparing configurations.
+    fn configuration(&self) -> Vec<(Value, bool)> {
+        (0..self.table().symbols().len())
+            .map(|id| {
+                let state = self.symbol_state(id);
+                (state.value.clone(), state.write)
+            })
+            .collect()
+    }
+
+    /// Replace the user values with the given value for every `bool` and `tristate` symbol outside a choice.
+    fn set_all_bools(&mut self, value: &str) {
+        self.clear_user_values();
+
+        let names: Vec<String> = self
+            .table()
+            .symbols()
+            .iter()
+            .filter(|symbol| symbol.choice.is_none() && matches!(symbol.r#type, Type::Bool | Type::Tristate))
+            .map(|symbol| symbol.name.clone())
+            .collect();
+
+        for name in names {
+            self.set_user_value(&name, value);
+        }
+    }
+}
+
+/// Returns the ids of the symbols in the menu tree in menu order, without duplicates.
+pub(crate) fn menu_symbols(eval: &Evaluation) -> Vec<usize> {
+    fn visit(nodes: &[MenuNode], seen: &mut [bool], result: &mut Vec<usize>) {
+        for node in nodes {
+            if let MenuItem::Symbol {
+                symbol,
+                ..
+            } = &node.item
+            {
+                if !seen[*symbol] {
+                    seen[*symbol] = true;
+                    result.push(*symbol);
+                }
+            }
+
+            visit(&node.children, seen, result);
+        }
+    }
+
+    let mut seen = vec![false; eval.table().symbols().len()];
+    let mut result = Vec::new();
+    visit(eval.table().menu(), &mut seen, &mut result);
+    result
+}
+
+/// The SplitMix64 pseudo-random number generator.
+///
+/// This is used instead of an external crate so that the configuration produced for a given seed never changes.
+struct SplitMix64(u64);
+
+impl SplitMix64 {
+    fn next_u64(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+    }
+
+    /// Returns a number in `0..n`.
+    fn below(&mut self, n: u128) -> u128 {
+        let value = ((self.next_u64() as u128) << 64) | self.next_u64() as u128;
+        value % n
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        crate::{
+            eval::{ConfigGenerator, Evaluation, SymbolTable, Value},
+            parser::{KConfig, PeekableChars, Tristate},
+            sdkconfig::{SdkConfig, SdkConfigWriter},
+        },
+        std::{collections::HashMap, path::Path},
+    };
+
+    const KCONFIG: &str = r##"
+config A
+    bool "A"
+    default y
+
+config B
+    bool "B"
+    depends on A
+
+config C
+    bool "C"
+    select D
+
+config D
+    bool "D"
+
+config LEVEL
+    int "Level"
+    depends on A
+    range 1 5 if B
+    range 10 20
+    default 15
+
+config ADDR
+    hex "Address"
+    range 0x100 0x1ff
+    default 0x180
+
+config NAME
+    string "Name"
+    default "name"
+
+choice MODE
+    prompt "Mode"
+    default MODE_2
+
+    config MODE_1
+        bool "1"
+    config MODE_2
+        bool "2"
+    config MODE_3
+        bool "3"
+endchoice
+"##;
+
+    fn evaluation() -> (Evaluation, HashMap<String, String>) {
+        let context = HashMap::new();
+        let kconfig =
+            KConfig::from_str(PeekableChars::new(KCONFIG, Path::new("test")), Path::new("/tmp"), &context).unwrap();
+        (Evaluation::new(SymbolTable::new(&kconfig)), context)
+    }
+
+    fn value(eval: &Evaluation, name: &str) -> String {
+        eval.value(name).unwrap().to_string()
+    }
+
+    /// Returns the names of symbols whose value differs from `value`.
+    fn symbols_not<'a>(eval: &'a Evaluation, value: &Value) -> Vec<&'a str> {
+        eval.table()
+            .symbols()
+            .iter()
+            .enumerate()
+            .filter(|(id, _)| eval.symbol_state(*id).value != *value)
+            .map(|(_, symbol)| symbol.name.as_str())
+            .collect()
+    }
+
+    fn snapshot(eval: &Evaluation) -> Vec<String> {
+        eval.table().symbols().iter().map(|symbol| format!("{}={}", symbol.name, value(eval, &symbol.name))).collect()
+    }
+
+    #[test_log::test]
+    fn generate_all() {
+        let (mut eval, context) = evaluation();
+        let y = Value::Tristate(Tristate::True);
+        let n = Value::Tristate(Tristate::False);
+
+        eval.set_user_value("UNKNOWN", "y");
+        eval.set_user_value("C", "y");
+        eval.generate(ConfigGenerator::OldDefConfig, &context).unwrap();
+        assert_eq!(eval.user_value("UNKNOWN"), None);
+        assert_eq!(value(&eval, "D"), "y");
+
+        eval.generate(ConfigGenerator::AllYesConfig, &context).unwrap();
+        let not_yes: Vec<&str> = symbols_not(&eval, &y).into_iter().filter(|name| !name.starts_with("MODE_")).collect();
+        assert_eq!(not_yes, vec!["LEVEL", "ADDR", "NAME"]);
+        assert_eq!(value(&eval, "MODE_2"), "y");
+        // B enables `range 1 5`, so the default is clamped.
+        assert_eq!(value(&eval, "LEVEL"), "5");
+
+        eval.generate(ConfigGenerator::AllNoConfig, &context).unwrap();
+        let not_no: Vec<&str> = symbols_not(&eval, &n).into_iter().filter(|name| !name.starts_with("MODE_")).collect();
+        assert_eq!(not_no, vec!["LEVEL", "ADDR", "NAME"]);
+        assert_eq!(value(&eval, "MODE_2"), "y");
+
+        eval.set_user_value("NAME", "other");
+        eval.generate(ConfigGenerator::AllDefConfig, &context).unwrap();
+        assert_eq!(value(&eval, "A"), "y");
+        assert_eq!(value(&eval, "NAME"), "name");
+    }
+
+    #[test_log::test]
+    fn generate_randconfig() {
+        let (mut eval, context) = evaluation();
+        let mut configs = Vec::new();
+
+        for seed in 0..32 {
+            eval.generate(ConfigGenerator::RandConfig(seed), &context).unwrap();
+            let config = snapshot(&eval);
+
+            // The same seed always gives the same configuration.
+            eval.randconfig(seed, &context).unwrap();
+            assert_eq!(snapshot(&eval), config);
+
+            if value(&eval, "C") == "y" {
+                assert_eq!(value(&eval, "D"), "y");
+            }
+
+            if value(&eval, "A") == "y" {
+                let level: i64 = value(&eval, "LEVEL").parse().unwrap();
+                let (low, high) = if value(&eval, "B") == "y" {
+                    (1, 5)
+                } else {
+                    (10, 20)
+                };
+                assert!((low..=high).contains(&level), "LEVEL={level} with seed {seed}");
+            } else {
+                assert_eq!(value(&eval, "B"), "n");
+            }
+
+            let addr = i64::from_str_radix(value(&eval, "ADDR").trim_start_matches("0x"), 16).unwrap();
+            assert!((0x100..=0x1ff).contains(&addr));
+
+            let modes = ["MODE_1", "MODE_2", "MODE_3"].iter().filter(|mode| value(&eval, mode) == "y").count();
+            assert_eq!(modes, 1);
+
+            configs.push(config);
+        }
+
+        configs.sort();
+        configs.dedup();
+        assert!(configs.len() > 16, "only {} distinct configurations", configs.len());
+    }
+
+    #[test_log::test]
+    fn savedefconfig() {
+        let (mut eval, context) = evaluation();
+        for (name, value) in
+            [("A", "y"), ("B", "y"), ("C", "y"), ("D", "y"), ("LEVEL", "3"), ("ADDR", "0x180"), ("NAME", "other")]
+        {
+            eval.set_user_value(name, value);
+        }
+        eval.set_user_value("MODE_3", "y");
+        eval.evaluate(&context).unwrap();
+
+        let minimal = eval.savedefconfig(&context).unwrap();
+        let mut defaults = Vec::new();
+        SdkConfigWriter::new().write_defaults(&minimal, &mut defaults).unwrap();
+        let defaults = String::from_utf8(defaults).unwrap();
+        assert_eq!(defaults, "CONFIG_B=y\nCONFIG_C=y\nCONFIG_LEVEL=3\nCONFIG_NAME=\"other\"\nCONFIG_MODE_3=y\n");
+
+        // Loading the defaults gives back the same configuration.
+        let mut reloaded = evaluation().0;
+        reloaded.load_sdkconfig(&SdkConfig::parse(&defaults, Path::new("sdkconfig.defaults")).unwrap());
+        reloaded.evaluate(&context).unwrap();
+        assert_eq!(snapshot(&reloaded), snapshot(&eval));
+
+        eval.allnoconfig(&context).unwrap();
+        let minimal = eval.savedefconfig(&context).unwrap();
+        let mut defaults = Vec::new();
+        SdkConfigWriter::new().write_defaults(&minimal, &mut defaults).unwrap();
+        assert_eq!(String::from_utf8(defaults).unwrap(), "# CONFIG_A is not set\n");
+
+        // Selecting the default member of a choice is not written.
+        eval.clear_user_values();
+        eval.set_user_value("MODE_2", "y");
+        eval.set_user_value("ADDR", "0x180");
+        eval.evaluate(&context).unwrap();
+        let minimal = eval.savedefconfig(&context).unwrap();
+        assert!(minimal.user_values().is_empty());
+    }
+}
@@ -0,0 +1,124 @@
+use {
+    crate::parser::{Tristate, Type},
+    std::fmt::{Display, Formatter, Result as FmtResult},
+};
+
+/// The value of a symbol.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum Value {
+    /// The value of a `bool` or `tristate` symbol.
+    Tristate(Tristate),
+
+    /// The value of a `string`, `int`, or `hex` symbol. Integers are kept in the form they were written, e.g. with or
+    /// without a `0x` prefix.
+    String(String),
+}
+
+impl Value {
+    /// Returns the default (unset) value for a symbol of the given type: `n` for `bool` and `tristate` symbols, or an
+    /// empty string otherwise.
+    pub fn default_for(r#type: Type) -> Self {
+        match r#type {
+            Type::Bool | Type::Tristate | Type::Unknown => Self::Tristate(Tristate::False),
+            _ => Self::String(String::new()),
+        }
+    }
+
+    /// Returns the tristate value of this value when used in a boolean context.
+    ///
+    /// As in kconfig, non-`bool`/`tristate` values are always `n` in a boolean context.
+    pub fn tristate(&self) -> Tristate {
+        match self {
+            Self::Tristate(t) => *t,
+            Self::String(_) => Tristate::False,
+        }
+    }
+
+    /// Returns the string value if this is a `string`, `int`, or `hex` value.
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            Self::String(s) => Some(s),
+            Self::Tristate(_) => None,
+        }
+    }
+}
+
+impl Display for Value {
+    fn fmt(&self, f: &mut Formatter) -> FmtResult {
+        match self {
+            Self::Tristate(t) => f.write_str(tristate_str(*t)),
+            Self::String(s) => f.write_str(s),
+        }
+    }
+}
+
+/// Returns the numeric level of a tristate value (`n` = 0, `m` = 1, `y` = 2) for comparisons.
+#[inline(always)]
+pub(crate) fn tri_level(value: Tristate) -> u8 {
+    match value {
+        Tristate::False => 0,
+        Tristate::Maybe => 1,
+        Tristate::True => 2,
+    }
+}
+
+/// Returns the tristate value for a numeric level.
+#[inline(always)]
+pub(crate) fn tri_from_level(level: u8) -> Tristate {
+    match level {
+        0 => Tristate::False,
+        1 => Tristate::Maybe,
+        _ => Tristate::True,
+    }
+}
+
+/// Returns the Kconfig spelling of a tristate value.
+#[inline(always)]
+pub(crate) fn tristate_str(value: Tristate) -> &'static str {
+    match value {
+        Tristate::False => "n",
+        Tristate::Maybe => "m",
+        Tristate::True => "y",
+    }
+}
+
+/// Parse a Kconfig tristate constant (`y`, `m`, or `n`).
+#[inline(always)]
+pub(crate) fn parse_tristate(s: &str) -> Option<Tristate> {
+    match s {
+        "n" => Some(Tristate::False),
+        "m" => Some(Tristate::Maybe),
+        "y" => Some(Tristate::True),
+        _ => None,
+    }
+}
+
+/// Parse an integer in the base used by the given type. `hex` values may omit the `0x` prefix; other types infer the
+/// base from the prefix.
+pub fn parse_int(s: &str, r#type: Type) -> Option<i128> {
+    let s = s.trim();
+    let (negative, digits) = match s.strip_prefix('-') {
+        Some(rest) => (true, rest),
+        None => (false, s),
+    };
+
+    let hex_digits = digits.strip_prefix("0x").or_else(|| digits.strip_prefix("0X"));
+    let value = match (r#type, hex_digits) {
+        (Type::Int, Some(_)) => return None,
+        (Type::Int, None) => digits.parse::<i128>().ok()?,
+        (Type::Hex, Some(h)) => i128::from_str_radix(h, 16).ok()?,
+        (Type::Hex, None) => i128::from_str_radix(digits, 16).ok()?,
+        (_, Some(h)) => i128::from_str_radix(h, 16).ok()?,
+        (_, None) => digits.parse::<i128>().ok()?,
+    };
+
+    if digits.is_empty() || digits.starts_with(['+', '-']) {
+        return None;
+    }
+
+    Some(if negative {
+        -value
+    } else {
+        value
+    })
+}
@@ -0,0 +1,236 @@
+use crate::parser::{CstFile, CstLine, CstNode, CstToken, Token};
+
+/// The indentation used for each nesting level in ESP-IDF style.
+const INDENT: &str = "    ";
+
+/// Styles for re-emitting a Kconfig file with [`format_kconfig`].
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub enum FormatStyle {
+    /// Reproduce the input exactly.
+    Lossless,
+
+    /// Normalize to the style used by ESP-IDF's own Kconfig files:
+    /// * Four spaces of indentation for each nesting level. Entries within `menu`, `choice`, and `if` blocks and the
+    ///   attributes of an entry are indented one level; everything after `mainmenu` is indented one level.
+    /// * Help text is indented one level deeper than its `help` keyword.
+    /// * Obsolete keyword spellings (`boolean`, `---help---`) are replaced with their canonical forms.
+    /// * Whitespace between tokens is collapsed to a single space and trailing whitespace is removed.
+    /// * Runs of blank lines are collapsed to a single blank line, and blank lines at the start and end of the file
+    ///   are removed.
+    #[default]
+    EspIdf,
+}
+
+/// Re-emit a Kconfig file from its concrete syntax tree in the given style.
+pub fn format_kconfig(cst: &CstFile, style: FormatStyle) -> String {
+    match style {
+        FormatStyle::Lossless => cst.to_string(),
+        FormatStyle::EspIdf => {
+            let mut formatter = EspIdfFormatter::default();
+            formatter.write_nodes(&cst.nodes, 0, true);
+            formatter.output
+        }
+    }
+}
+
+/// Formatter state for [`FormatStyle::EspIdf`].
+#[derive(Default)]
+struct EspIdfFormatter {
+    output: String,
+
+    /// Whether a blank line should be written before the next non-blank line.
+    blank_pending: bool,
+}
+
+impl EspIdfFormatter {
+    fn write_nodes(&mut self, nodes: &[CstNode], mut level: usize, top_level: bool) {
+        for node in nodes {
+            match node {
+                CstNode::Line(line) => {
+                    self.write_line(line, level);
+
+                    if top_level && line.keyword() == Some(&Token::Mainmenu) {
+                        level += 1;
+                    }
+                }
+
+                CstNode::Block(block) => {
+                    self.write_line(&block.header, level);
+                    self.write_nodes(&block.children, level + 1, false);
+
+                    if let Some(end) = &block.end {
+                        self.write_line(end, level);
+                    }
+                }
+            }
+        }
+    }
+
+    fn write_line(&mut self, line: &CstLine, level: usize) {
+        if line.is_blank() {
+            self.blank_pending = !self.output.is_empty();
+            return;
+        }
+
+        if self.blank_pending {
+            self.output.push('\n');
+            self.blank_pending = false;
+        }
+
+        let indent = INDENT.repeat(level);
+        self.output.push_str(&indent);
+
+        for (i, token) in line.tokens.iter().enumerate() {
+            if i > 0 {
+                if token.leading.contains("\\\n") {
+                    // Keep line continuations (and the indentation of the continued line) as written.
+                    self.output.push_str(&token.leading);
+                } else if !token.leading.is_empty() {
+                    self.output.push(' ');
+                }
+            }
+
+            self.output.push_str(canonical_text(token));
+        }
+
+        if let Some(comment) = &line.comment {
+            if !line.tokens.is_empty() {
+                self.output.push(' ');
+            }
+
+            self.output.push_str(comment.trim_end());
+        }
+
+        self.output.push('\n');
+        self.write_help(&line.help, level + 1);
+    }
+
+    fn write_help(&mut self, help: &[String], level: usize) {
+        let Some(first) = help.iter().find(|line| !line.trim().is_empty()) else {
+            return;
+        };
+
+        let help_indent = &first[..first.len() - first.trim_start().len()];
+        let indent = INDENT.repeat(level);
+
+        for line in help {
+            let line = line.trim_end();
+
+            if !line.is_empty() {
+                self.output.push_str(&indent);
+                self.output.push_str(line.strip_prefix(help_indent).unwrap_or(line.trim_start()));
+            }
+
+            self.output.push('\n');
+        }
+    }
+}
+
+/// Returns the canonical spelling of a token.
+fn canonical_text(token: &CstToken) -> &str {
+    match token.token.token {
+        Token::Bool => "bool",
+        Token::Help => "help",
+        _ => &token.text,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        crate::{
+            format_kconfig,
+            parser::{CstFile, KConfig, PeekableChars},
+            FormatStyle,
+        },
+        std::{
+            collections::HashMap,
+            env, fs,
+            path::{Path, PathBuf},
+        },
+    };
+
+    fn format_str(input: &str, style: FormatStyle) -> String {
+        let cst = CstFile::parse(PeekableChars::new(input, Path::new("test"))).unwrap();
+        format_kconfig(&cst, style)
+    }
+
+    #[test_log::test]
+    fn format_esp_idf() {
+        let input = r##"
+
+menu   "Foo"
+config FOO
+  boolean   "Foo"     # Foo is great
+  default y if BAR&&!BAZ
+  ---help---
+   Say foo.
+
+     Indented foo.
+
+
+
+# Between entries
+if BAR
+  source "$IDF_PATH/Kconfig.bar"
+endif
+endmenu
+
+"##;
+
+        let expected = r##"menu "Foo"
+    config FOO
+        bool "Foo" # Foo is great
+        default y if BAR&&!BAZ
+        help
+            Say foo.
+
+              Indented foo.
+
+    # Between entries
+    if BAR
+        source "$IDF_PATH/Kconfig.bar"
+    endif
+endmenu
+"##;
+
+        let formatted = format_str(input, FormatStyle::EspIdf);
+        assert_eq!(formatted, expected);
+        assert_eq!(format_str(&formatted, FormatStyle::EspIdf), expected);
+        assert_eq!(format_str(input, FormatStyle::Lossless), input);
+    }
+
+    #[test_log::test]
+    fn format_mainmenu() {
+        let input = "mainmenu \"Top\"\n\nconfig FOO\n    bool\n";
+        let expected = "mainmenu \"Top\"\n\n    config FOO\n        bool\n";
+        assert_eq!(format_str(input, FormatStyle::EspIdf), expected);
+    }
+
+    #[test_log::test]
+    fn format_esp_idf_idempotent() {
+        let base_dir = PathBuf::from(env::var("CARGO_MANIFEST_DIR").unwrap());
+        let mut dirs = vec![base_dir.join("tests/esp-idf")];
+        let context = HashMap::<String, String>::default();
+
+        while let Some(dir) = dirs.pop() {
+            for entry in fs::read_dir(dir).unwrap() {
+                let path = entry.unwrap().path();
+                if path.is_dir() {
+                    dirs.push(path);
+                    continue;
+                }
+
+                if !path.file_name().unwrap().to_string_lossy().starts_with("Kconfig") {
+                    continue;
+                }
+
+                let formatted = format_kconfig(&CstFile::from_file(&path).unwrap(), FormatStyle::EspIdf);
+                assert_eq!(format_str(&formatted, FormatStyle::EspIdf), formatted, "Not idempotent: {path:?}");
+
+                let parsed = KConfig::from_str_raw(PeekableChars::new(&formatted, &path), &base_dir, &context);
+                assert!(parsed.is_ok(), "Formatted output of {path:?} does not parse: {:?}", parsed.err());
+            }
+        }
+    }
+}
@@ -0,0 +1,268 @@
+use {
+    crate::{parser::Location, Context, KConfigFs},
+    std::{
+        cell::RefCell,
+        collections::BTreeSet,
+        env::VarError,
+        io::{Result as IoResult, Write},
+        path::{Path, PathBuf},
+    },
+};
+
+/// A file read or probed while loading a Kconfig tree.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Include {
+    /// The path of the file or directory.
+    pub path: PathBuf,
+
+    /// What happened to the path.
+    pub kind: IncludeKind,
+
+    /// The `source` statement that included the path, or `None` for the top-level Kconfig file.
+    pub location: Option<Location>,
+
+    /// The closest existing directory containing the path. Changes to it show when a missing file appears.
+    pub watch: PathBuf,
+}
+
+/// What happened to an [`Include`].
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum IncludeKind {
+    /// The file was read.
+    Read,
+
+    /// The file was named by an optional source statement but does not exist.
+    Missing,
+
+    /// The directory was searched for files matching a glob pattern.
+    Glob,
+}
+
+/// The files read and probed while loading a Kconfig tree, in the order they were encountered.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct IncludeGraph {
+    /// The files and directories.
+    pub includes: Vec<Include>,
+}
+
+/// A [context][Context] that records an [`IncludeGraph`] while loading a Kconfig tree, looking up variables and reading
+/// files through another context.
+pub struct IncludeRecorder<'a, C: ?Sized> {
+    context: &'a C,
+    graph: RefCell<IncludeGraph>,
+}
+
+impl Include {
+    /// Create an include, finding the closest existing directory containing the path in the filesystem.
+    pub fn new(path: PathBuf, kind: IncludeKind, location: Option<Location>, fs: &dyn KConfigFs) -> Self {
+        let watch = match kind {
+            IncludeKind::Read => path.clone(),
+            IncludeKind::Missing | IncludeKind::Glob => {
+                let mut dir = path.as_path();
+                while !fs.is_dir(dir) {
+                    match dir.parent() {
+                        Some(parent) if !parent.as_os_str().is_empty() => dir = parent,
+                        _ => {
+                            dir = Path::new(".");
+                            break;
+                        }
+                    }
+                }
+                dir.to_path_buf()
+            }
+        };
+
+        Self {
+            path,
+            kind,
+            location,
+            watch,
+        }
+    }
+}
+
+impl IncludeGraph {
+    /// Returns the files that were read.
+    pub fn files(&self) -> impl Iterator<Item = &Path> {
+        self.includes.iter().filter(|include| include.kind == IncludeKind::Read).map(|include| include.path.as_path())
+    }
+
+    /// Returns the optional files that were probed but missing.
+    pub fn missing(&self) -> impl Iterator<Item = &Path> {
+        self.includes
+            .iter()
+            .filter(|include| include.kind == IncludeKind::Missing)
+            .map(|include| include.path.as_path())
+    }
+
+    /// Returns the chain of includes leading to a path, starting with the top-level Kconfig file and ending with the
+    /// path itself. Returns an empty chain if the path was not included.
+    pub fn chain(&self, path: &Path) -> Vec<&Include> {
+        let mut result = Vec::new();
+        let mut current = self.includes.iter().find(|include| include.path == path);
+
+        while let Some(include) = current {
+            if result.contains(&include) {
+                break;
+            }
+
+            result.push(include);
+            current = include.location.and_then(|location| {
+                self.includes.iter().find(|parent| parent.kind == IncludeKind::Read && parent.path == location.filename)
+            });
+        }
+
+        result.reverse();
+        result
+    }
+
+    /// Returns the paths to watch for changes, sorted and deduplicated: the files that were read, along with the closest
+    /// existing directories of missing files and the directories searched by glob patterns.
+    pub fn dependencies(&self) -> Vec<&Path> {
+        let dependencies: BTreeSet<&Path> = self.includes.iter().map(|include| include.watch.as_path()).collect();
+        dependencies.into_iter().collect()
+    }
+
+    /// Write a Makefile-style dependency file listing [`dependencies()`][Self::dependencies] as prerequisites of
+    /// `target`. Each dependency also gets an empty rule so deleting a file does not break the build.
+    pub fn write_depfile<W: Write>(&self, writer: &mut W, target: &Path) -> IoResult<()> {
+        let dependencies = self.dependencies();
+        write!(writer, "{}:", depfile_escape(target))?;
+        for dependency in &dependencies {
+            write!(writer, " \\\n  {}", depfile_escape(dependency))?;
+        }
+        writeln!(writer)?;
+
+        for dependency in &dependencies {
+            writeln!(writer, "\n{}:", depfile_escape(dependency))?;
+        }
+
+        Ok(())
+    }
+
+    /// Write `cargo:rerun-if-changed` lines for [`dependencies()`][Self::dependencies], for use in build scripts.
+    pub fn write_rerun_if_changed<W: Write>(&self, writer: &mut W) -> IoResult<()> {
+        for dependency in self.dependencies() {
+            writeln!(writer, "cargo:rerun-if-changed={}", dependency.display())?;
+        }
+
+        Ok(())
+    }
+}
+
+impl<'a, C: ?Sized> IncludeRecorder<'a, C> {
+    /// Create a recorder that looks up variables and reads files through `context`.
+    pub fn new(context: &'a C) -> Self {
+        Self {
+            context,
+            graph: RefCell::new(IncludeGraph::default()),
+        }
+    }
+
+    /// Returns the include graph recorded so far.
+    pub fn graph(&self) -> IncludeGraph {
+        self.graph.borrow().clone()
+    }
+
+    /// Returns the recorded include graph.
+    pub fn into_graph(self) -> IncludeGraph {
+        self.graph.into_inner()
+    }
+}
+
+impl<C: Context + ?Sized> Context for IncludeRecorder<'_, C> {
+    fn var(&self, name: &str) -> Result<String, VarError> {
+        self.context.var(name)
+    }
+
+    fn fs(&self) -> &dyn KConfigFs {
+        self.context.fs()
+    }
+
+    fn record_include(&self, include: Include) {
+        self.graph.borrow_mut().includes.push(include.clone());
+        self.context.record_include(include);
+    }
+}
+
+/// Escape a path for a Makefile rule.
+fn depfile_escape(path: &Path) -> String {
+    let mut result = String::new();
+    for c in path.to_string_lossy().chars() {
+        match c {
+            ' ' | '#' | ':' | '\\' => {
+                result.push('\\');
+                result.push(c);
+            }
+            '$' => result.push_str("$$"),
+            c => result.push(c),
+        }
+    }
+
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        crate::{parser::KConfig, FsContext, IncludeKind, IncludeRecorder, MemoryFs},
+        std::{collections::HashMap, path::Path},
+    };
+
+    #[test_log::test]
+    fn include_graph() {
+        let fs = MemoryFs::new()
+            .with_file("/src/Kconfig", "source \"components/Kconfig\"\norsource \"optional/Kconfig\"\n")
+            .with_file("/src/components/Kconfig", "grsource \"*/Kconfig.in\"\n")
+            .with_file("/src/components/a/Kconfig.in", "config A\n    bool\n")
+            .with_file("/src/components/b/README", "");
+
+        let context = HashMap::<String, String>::new();
+        let context = FsContext::new(&context, &fs);
+        let recorder = IncludeRecorder::new(&context);
+        KConfig::from_file(Path::new("/src/Kconfig"), Path::new("/src"), &recorder).unwrap();
+        let graph = recorder.into_graph();
+
+        let kinds: Vec<(&str, IncludeKind)> =
+            graph.includes.iter().map(|include| (include.path.to_str().unwrap(), include.kind)).collect();
+        assert_eq!(
+            kinds,
+            vec![
+                ("/src/Kconfig", IncludeKind::Read),
+                ("/src/components/Kconfig", IncludeKind::Read),
+                ("/src/components", IncludeKind::Glob),
+                ("/src/components/b", IncludeKind::Glob),
+                ("/src/components/a/Kconfig.in", IncludeKind::Read),
+                ("/src/optional/Kconfig", IncludeKind::Missing),
+            ]
+        );
+
+        let chain: Vec<&Path> = graph
+            .chain(Path::new("/src/components/a/Kconfig.in"))
+            .iter()
+            .map(|include| include.path.as_path())
+            .collect();
+        assert_eq!(
+            chain,
+            vec![
+                Path::new("/src/Kconfig"),
+                Path::new("/src/components/Kconfig"),
+                Path::new("/src/components/a/Kconfig.in")
+            ]
+        );
+        assert_eq!(graph.missing().collect::<Vec<_>>(), vec![Path::new("/src/optional/Kconfig")]);
+
+        let mut depfile = Vec::new();
+        graph.write_depfile(&mut depfile, Path::new("build/sdkconfig.h")).unwrap();
+        assert_eq!(
+            String::from_utf8(depfile).unwrap(),
+            "build/sdkconfig.h: \\\n  /src \\\n  /src/Kconfig \\\n  /src/components \\\n  /src/components/Kconfig \\\n  \
+             /src/components/a/Kconfig.in \\\n  /src/components/b\n\n/src:\n\n/src/Kconfig:\n\n/src/components:\n\n\
+             /src/components/Kconfig:\n\n/src/components/a/Kconfig.in:\n\n/src/components/b:\n"
+        );
+
+        let mut rerun = Vec::new();
+        graph.write_rerun_if_changed(&mut rerun).unwrap();
+        assert!(String::from_utf8(rerun).unwrap().starts_with("cargo:rerun-if-changed=/src\n"));
+    }
+}
@@ -3,16 +3,30 @@
 #![allow(clippy::result_large_err)]
 #![warn(missing_docs)]
 
+mod components;
+mod confserver;
 mod context;
+mod edit;
+mod format;
+mod includes;
+mod matrix;
+mod project;
 mod resolve;
+mod roots;
+mod soc_caps;
 mod target;
+mod vfs;
 
+pub mod eval;
 pub mod parser;
-pub use {context::*, resolve::*, target::*};
+pub mod sdkconfig;
+pub use {
+    components::*, confserver::*, context::*, edit::*, format::*, includes::*, matrix::*, project::*, resolve::*,
+    roots::*, soc_caps::*, target::*, vfs::*,
+};
 
 /// Default KConfigs.in for `COMPONENT_KCONFIGS_SOURCE_FILE`.
 pub const KCONFIGS_IN: &str = include_str!("Kconfigs.in");
 
 /// Default KConfigs.projbuild.in for `COMPONENT_KCONFIGS_PROJBUILD_SOURCE_FILE`.
 pub const KCONFIGS_PROJBUILD_IN: &str = include_str!("Kconfigs.projbuild.in");
-
@@ -0,0 +1,346 @@
+use {
+    crate::{
+        eval::{menu_symbols, Evaluation, Value},
+        parser::{KConfigError, Tristate, Type},
+        sdkconfig::quote,
+        Target,
+    },
+    serde::Serialize,
+    serde_json::{ser::PrettyFormatter, Map as JsonMap, Serializer as JsonSerializer, Value as JsonValue},
+    std::{
+        collections::HashMap,
+        io::{Result as IoResult, Write},
+    },
+};
+
+/// A report of which symbols exist, are visible, and what values they take for each target.
+///
+/// Build one by evaluating the same Kconfig tree and user values once per target, then write it as CSV, JSON, or
+/// Markdown.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct TargetMatrix {
+    /// The targets, in column order.
+    pub targets: Vec<Target>,
+
+    /// One row per symbol, in menu order. Symbols that only exist for later targets follow the menu order of the first
+    /// target that defines them.
+    pub rows: Vec<MatrixRow>,
+
+    /// Row indices by symbol name.
+    by_name: HashMap<String, usize>,
+}
+
+/// The state of one symbol across targets.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct MatrixRow {
+    /// The name of the symbol (without the `CONFIG_` prefix).
+    pub name: String,
+
+    /// The titles of the menus containing the symbol, from the first target that defines it.
+    pub menu_path: Vec<String>,
+
+    /// The prompt of the symbol, if it has one.
+    pub prompt: Option<String>,
+
+    /// The state of the symbol for each target, or `None` where it is not defined.
+    pub cells: Vec<Option<MatrixCell>>,
+}
+
+/// The state of a symbol for one target.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct MatrixCell {
+    /// Whether the symbol's prompt is visible, so users can change its value.
+    pub visible: bool,
+
+    /// The value of the symbol as written in `sdkconfig`, or `None` if it is not written.
+    pub value: Option<String>,
+}
+
+impl TargetMatrix {
+    /// Create an empty matrix.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Build a matrix by calling `load` for each target to get its evaluated configuration.
+    pub fn build<F>(targets: &[Target], mut load: F) -> Result<Self, KConfigError>
+    where
+        F: FnMut(Target) -> Result<Evaluation, KConfigError>,
+    {
+        let mut matrix = Self::new();
+        for &target in targets {
+            let eval = load(target)?;
+            matrix.add_target(target, &eval);
+        }
+        Ok(matrix)
+    }
+
+    /// Add a column for a target with its evaluated configuration.
+    pub fn add_target(&mut self, target: Target, eval: &Evaluation) {
+        let column = self.targets.len();
+        self.targets.push(target);
+        for row in &mut self.rows {
+            row.cells.push(None);
+        }
+
+        for id in menu_symbols(eval) {
+            let symbol = &eval.table().symbols()[id];
+            if symbol.r#type == Type::Unknown {
+                continue;
+            }
+
+            let state = eval.symbol_state(id);
+            let value = match (symbol.r#type, &state.value) {
+                _ if !state.write => None,
+                (Type::String, Value::String(s)) => Some(quote(s)),
+                (_, value) => Some(value.to_string()),
+            };
+
+            let row = match self.by_name.get(&symbol.name) {
+                Some(&row) => row,
+                None => {
+                    let definition = symbol
+                        .definitions
+                        .iter()
+                        .find(|def| def.config.prompt.is_some())
+                        .or(symbol.definitions.first());
+                    self.by_name.insert(symbol.name.clone(), self.rows.len());
+                    self.rows.push(MatrixRow {
+                        name: symbol.name.clone(),
+                        menu_path: definition.map(|def| def.menu_path.clone()).unwrap_or_default(),
+                        prompt: definition
+                            .and_then(|def| def.config.prompt.as_ref())
+                            .map(|prompt| prompt.title.to_string()),
+                        cells: vec![None; column + 1],
+                    });
+                    self.rows.len() - 1
+                }
+            };
+
+            self.rows[row].cells[column] = Some(MatrixCell {
+                visible: state.visibility != Tristate::False,
+                value,
+            });
+        }
+    }
+
+    /// Returns the rows whose symbols differ between targets.
+    pub fn target_specific(&self) -> impl Iterator<Item = &MatrixRow> {
+        self.rows.iter().filter(|row| row.is_target_specific())
+    }
+
+    /// Write the matrix as CSV with `symbol`, `menu`, and `prompt` columns followed by one column per target.
+    ///
+    /// Each target cell holds the value of the symbol, in parentheses if its prompt is not visible. Symbols that are
+    /// defined but not written are `(unset)`, and symbols that are not defined are `-`. If `target_specific` is set,
+    /// only symbols that differ between targets are written.
+    pub fn write_csv<W: Write>(&self, writer: &mut W, target_specific: bool) -> IoResult<()> {
+        let mut header = vec!["symbol".to_string(), "menu".to_string(), "prompt".to_string()];
+        header.extend(self.targets.iter().map(|target| target.config_name().to_string()));
+        writeln!(writer, "{}", header.iter().map(|field| csv_field(field)).collect::<Vec<_>>().join(","))?;
+
+        for row in self.selected_rows(target_specific) {
+            let mut fields = vec![row.name.clone(), row.menu_path.join(" > "), row.prompt.clone().unwrap_or_default()];
+            fields.extend(row.cells.iter().map(cell_text));
+            writeln!(writer, "{}", fields.iter().map(|field| csv_field(field)).collect::<Vec<_>>().join(","))?;
+        }
+
+        Ok(())
+    }
+
+    /// Write the matrix as a Markdown table, using the same cell text as [`write_csv()`][Self::write_csv].
+    pub fn write_markdown<W: Write>(&self, writer: &mut W, target_specific: bool) -> IoResult<()> {
+        let mut header = vec!["Symbol".to_string(), "Prompt".to_string()];
+        header.extend(self.targets.iter().map(|target| target.config_name().to_string()));
+        writeln!(writer, "| {} |", header.join(" | "))?;
+        writeln!(writer, "|{}", "---|".repeat(header.len()))?;
+
+        for row in self.selected_rows(target_specific) {
+            let mut fields = vec![format!("`{}`", row.name), markdown_text(row.prompt.as_deref().unwrap_or_default())];
+            fields.extend(row.cells.iter().map(|cell| match cell_text(cell).as_str() {
+                "-" => "-".to_string(),
+                text => format!("`{}`", text.replace('|', "\\|")),
+            }));
+            writeln!(writer, "| {} |", fields.join(" | "))?;
+        }
+
+        Ok(())
+    }
+
+    /// Write the matrix as JSON: an object with a `targets` array of target names and a `symbols` array. Each symbol
+    /// has `name`, `menu_path`, `prompt`, `target_specific`, and `targets` keys; `targets` maps each target name to
+    /// `null` if the symbol is not defined there, or an object with `visible` and `value` keys.
+    pub fn write_json<W: Write>(&self, writer: &mut W, target_specific: bool) -> IoResult<()> {
+        let symbols = self
+            .selected_rows(target_specific)
+            .map(|row| {
+                let mut targets = JsonMap::new();
+                for (target, cell) in self.targets.iter().zip(&row.cells) {
+                    let cell = match cell {
+                        Some(cell) => {
+                            let mut result = JsonMap::new();
+                            result.insert("visible".to_string(), cell.visible.into());
+                            result.insert("value".to_string(), cell.value.clone().into());
+                            JsonValue::Object(result)
+                        }
+                        None => JsonValue::Null,
+                    };
+                    targets.insert(target.config_name().to_string(), cell);
+                }
+
+                let mut result = JsonMap::new();
+                result.insert("name".to_string(), row.name.clone().into());
+                result.insert("menu_path".to_string(), row.menu_path.clone().into());
+                result.insert("prompt".to_string(), row.prompt.clone().into());
+                result.insert("target_specific".to_string(), row.is_target_specific().into());
+                result.insert("targets".to_string(), JsonValue::Object(targets));
+                JsonValue::Object(result)
+            })
+            .collect();
+
+        let mut result = JsonMap::new();
+        result.insert(
+            "targets".to_string(),
+            self.targets.iter().map(|target| target.config_name()).collect::<Vec<_>>().into(),
+        );
+        result.insert("symbols".to_string(), JsonValue::Array(symbols));
+
+        let mut serializer = JsonSerializer::with_formatter(&mut *writer, PrettyFormatter::with_indent(b"    "));
+        JsonValue::Object(result).serialize(&mut serializer)?;
+        writeln!(writer)
+    }
+
+    fn selected_rows(&self, target_specific: bool) -> impl Iterator<Item = &MatrixRow> {
+        self.rows.iter().filter(move |row| !target_specific || row.is_target_specific())
+    }
+}
+
+impl MatrixRow {
+    /// Returns `true` if the symbol is not defined for every target, or its visibility or value differs between them.
+    pub fn is_target_specific(&self) -> bool {
+        self.cells.windows(2).any(|pair| pair[0] != pair[1])
+    }
+}
+
+/// Returns the text of a cell in CSV and Markdown output.
+fn cell_text(cell: &Option<MatrixCell>) -> String {
+    match cell {
+        None => "-".to_string(),
+        Some(cell) => {
+            let value = cell.value.as_deref().unwrap_or("unset");
+            if cell.visible && cell.value.is_some() {
+                value.to_string()
+            } else {
+                format!("({value})")
+            }
+        }
+    }
+}
+
+/// Quote a CSV field if needed.
+fn csv_field(field: &str) -> String {
+    if field.contains([',', '"', '\n']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
+
+/// Escape text for a Markdown table cell.
+fn markdown_text(text: &str) -> String {
+    text.replace('|', "\\|")
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        crate::{
+            eval::{Evaluation, SymbolTable},
+            parser::{KConfig, PeekableChars},
+            Target, TargetMatrix,
+        },
+        std::{collections::HashMap, path::Path},
+    };
+
+    const KCONFIG: &str = r##"
+config IDF_TARGET
+    string
+    default "$IDF_TARGET"
+
+config SOC_USB_SUPPORTED
+    bool
+    default y if IDF_TARGET = "esp32s3"
+
+menu "Drivers"
+config USB
+    bool "USB, serial, and JTAG"
+    depends on SOC_USB_SUPPORTED
+    default y
+
+config UART_COUNT
+    int "UARTs"
+    default 3 if IDF_TARGET = "esp32"
+    default 2
+endmenu
+
+config LOG
+    bool "Logging"
+    default y
+"##;
+
+    fn matrix() -> TargetMatrix {
+        TargetMatrix::build(&[Target::ESP32, Target::ESP32C3, Target::ESP32S3], |target| {
+            let context: HashMap<String, String> =
+                [("IDF_TARGET".to_string(), target.config_name().to_string())].into_iter().collect();
+            let kconfig =
+                KConfig::from_str(PeekableChars::new(KCONFIG, Path::new("test")), Path::new("/tmp"), &context)?;
+            let mut eval = Evaluation::new(SymbolTable::new(&kconfig));
+            eval.evaluate(&context)?;
+            Ok(eval)
+        })
+        .unwrap()
+    }
+
+    #[test_log::test]
+    fn matrix_csv_markdown() {
+        let matrix = matrix();
+        let names: Vec<&str> = matrix.target_specific().map(|row| row.name.as_str()).collect();
+        assert_eq!(names, vec!["IDF_TARGET", "SOC_USB_SUPPORTED", "USB", "UART_COUNT"]);
+
+        let mut csv = Vec::new();
+        matrix.write_csv(&mut csv, false).unwrap();
+        assert_eq!(
+            String::from_utf8(csv).unwrap(),
+            r##"symbol,menu,prompt,esp32,esp32c3,esp32s3
+IDF_TARGET,,,"(""esp32"")","(""esp32c3"")","(""esp32s3"")"
+SOC_USB_SUPPORTED,,,(unset),(unset),(y)
+USB,Drivers,"USB, serial, and JTAG",(unset),(unset),y
+UART_COUNT,Drivers,UARTs,3,2,2
+LOG,,Logging,y,y,y
+"##
+        );
+
+        let mut markdown = Vec::new();
+        matrix.write_markdown(&mut markdown, true).unwrap();
+        let markdown = String::from_utf8(markdown).unwrap();
+        assert!(markdown.starts_with("| Symbol | Prompt | esp32 | esp32c3 | esp32s3 |\n|---|---|---|---|---|\n"));
+        assert!(markdown.contains("| `USB` | USB, serial, and JTAG | `(unset)` | `(unset)` | `y` |\n"), "{markdown}");
+        assert!(!markdown.contains("`LOG`"));
+    }
+
+    #[test_log::test]
+    fn matrix_json() {
+        let mut json = Vec::new();
+        matrix().write_json(&mut json, false).unwrap();
+        let json: serde_json::Value = serde_json::from_slice(&json).unwrap();
+
+        assert_eq!(json["targets"], serde_json::json!(["esp32", "esp32c3", "esp32s3"]));
+        let usb = &json["symbols"][2];
+        assert_eq!(usb["name"], "USB");
+        assert_eq!(usb["menu_path"], serde_json::json!(["Drivers"]));
+        assert_eq!(usb["target_specific"], true);
+        assert_eq!(usb["targets"]["esp32"], serde_json::json!({"visible": false, "value": null}));
+        assert_eq!(usb["targets"]["esp32s3"], serde_json::json!({"visible": true, "value": "y"}));
+        assert_eq!(json["symbols"][4]["target_specific"], false);
+    }
+}
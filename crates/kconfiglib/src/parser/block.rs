@@ -193,6 +193,22 @@ impl ResolveBlock for Rc<RefCell<Block>> {
                 }
                 Ok(blocks)
             }
+            // Entries within an `if` block depend on the `if` condition.
+            Block::Choice(ref c) if parent_cond.is_some() => {
+                let mut choice = c.clone();
+                choice.depends_on.extend(parent_cond.cloned());
+                Ok(vec![Rc::new(RefCell::new(Block::Choice(choice)))])
+            }
+            Block::Config(ref c) if parent_cond.is_some() => {
+                let mut config = c.clone();
+                config.depends_on.extend(parent_cond.cloned());
+                Ok(vec![Rc::new(RefCell::new(Block::Config(config)))])
+            }
+            Block::MenuConfig(ref c) if parent_cond.is_some() => {
+                let mut config = c.clone();
+                config.depends_on.extend(parent_cond.cloned());
+                Ok(vec![Rc::new(RefCell::new(Block::MenuConfig(config)))])
+            }
             _ => Ok(vec![self.clone()]),
         }
     }
@@ -16,10 +16,7 @@ pub fn parse_comment(chars: &mut PeekableChars) -> Result<(), KConfigError> {
     // Eat the # character; don't include it in the comment.
     let mut comment = String::new();
 
-    loop {
-        let Some(c) = chars.next() else {
-            break;
-        };
+    while let Some(c) = chars.next() {
         if c == '\n' {
             break;
         } else if c == '\\' {
@@ -107,11 +107,7 @@ impl Config {
         let mut ranges = Vec::new();
         let mut comments = Vec::new();
 
-        loop {
-            let Some(tokens) = lines.peek() else {
-                break;
-            };
-
+        while let Some(tokens) = lines.peek() {
             let Some(cmd) = tokens.peek() else {
                 panic!("Expected config entry");
             };
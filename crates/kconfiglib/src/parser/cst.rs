@@ -0,0 +1,504 @@
+use {
+    crate::parser::{streams::parse_token, Expected, KConfigError, LocToken, Located, Location, PeekableChars, Token},
+    std::{
+        fmt::{Display, Formatter, Result as FmtResult},
+        fs::File,
+        io::Read,
+        iter::Peekable,
+        mem::take,
+        path::Path,
+    },
+};
+
+/// A lossless concrete syntax tree (CST) for a single Kconfig file.
+///
+/// Unlike [`KConfig`][crate::parser::KConfig], this keeps comments, blank lines, indentation, line continuations, and
+/// the original spelling of every token (e.g. `boolean` vs. `bool`). [`Display`]ing a `CstFile` reproduces the input
+/// exactly. `source` statements are not followed.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct CstFile {
+    /// The top-level nodes in the file.
+    pub nodes: Vec<CstNode>,
+}
+
+/// A node in a concrete syntax tree.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum CstNode {
+    /// A single line: a blank line, a comment, an attribute, or a statement without a body (`source`, `mainmenu`).
+    Line(CstLine),
+
+    /// An entry with a body: `config`, `menuconfig`, `comment`, `menu`, `choice`, or `if`.
+    Block(CstBlock),
+}
+
+/// An entry with a body in a concrete syntax tree.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct CstBlock {
+    /// The line that starts the block, e.g. `config FOO` or `menu "Foo"`.
+    pub header: CstLine,
+
+    /// The attributes and entries within the block.
+    ///
+    /// Blank lines and comments that follow the last attribute of a `config`, `menuconfig`, or `comment` entry are
+    /// placed in the enclosing block instead.
+    pub children: Vec<CstNode>,
+
+    /// The line that ends the block (`endmenu`, `endchoice`, or `endif`), if any.
+    pub end: Option<CstLine>,
+}
+
+/// A single line of a Kconfig file, including any help text that follows it.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct CstLine {
+    /// The tokens on the line, including any whitespace before each token.
+    pub tokens: Vec<CstToken>,
+
+    /// Whitespace after the last token (or the entire line if there are no tokens) and before any comment.
+    pub trailing: String,
+
+    /// A trailing comment, including the leading `#`.
+    pub comment: Option<String>,
+
+    /// The line terminator. This is empty for the last line of a file without a trailing newline.
+    pub eol: String,
+
+    /// For `help` lines, the raw lines of the help text, including indentation and line terminators.
+    pub help: Vec<String>,
+}
+
+/// A token along with its original spelling and the whitespace preceding it.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct CstToken {
+    /// Whitespace (including any line continuations) before the token.
+    pub leading: String,
+
+    /// The token as it was written in the source.
+    pub text: String,
+
+    /// The parsed token.
+    pub token: LocToken,
+}
+
+impl CstFile {
+    /// Read a concrete syntax tree from the given Kconfig file.
+    pub fn from_file(filename: &Path) -> Result<Self, KConfigError> {
+        let mut file = File::open(filename)?;
+        let mut input = String::new();
+        file.read_to_string(&mut input)?;
+        Self::parse(PeekableChars::new(input.as_str(), filename))
+    }
+
+    /// Parse a concrete syntax tree from the given string input.
+    pub fn parse(mut chars: PeekableChars) -> Result<Self, KConfigError> {
+        let mut lines = Vec::new();
+
+        while !chars.is_empty() {
+            lines.push(CstLine::parse(&mut chars)?);
+        }
+
+        let (nodes, _) = parse_nodes(&mut lines.into_iter().peekable(), None)?;
+        Ok(Self {
+            nodes,
+        })
+    }
+}
+
+impl Display for CstFile {
+    fn fmt(&self, f: &mut Formatter) -> FmtResult {
+        for node in &self.nodes {
+            Display::fmt(node, f)?;
+        }
+
+        Ok(())
+    }
+}
+
+impl CstNode {
+    /// Indicates whether this node is a blank or comment-only line.
+    #[inline(always)]
+    pub fn is_trivia(&self) -> bool {
+        matches!(self, Self::Line(line) if line.is_trivia())
+    }
+
+    /// If this is a line, return a reference to it; otherwise, return `None`.
+    #[inline(always)]
+    pub fn as_line(&self) -> Option<&CstLine> {
+        match self {
+            Self::Line(line) => Some(line),
+            _ => None,
+        }
+    }
+
+    /// If this is a block, return a reference to it; otherwise, return `None`.
+    #[inline(always)]
+    pub fn as_block(&self) -> Option<&CstBlock> {
+        match self {
+            Self::Block(block) => Some(block),
+            _ => None,
+        }
+    }
+}
+
+impl Display for CstNode {
+    fn fmt(&self, f: &mut Formatter) -> FmtResult {
+        match self {
+            Self::Line(line) => Display::fmt(line, f),
+            Self::Block(block) => Display::fmt(block, f),
+        }
+    }
+}
+
+impl CstBlock {
+    /// Returns the keyword that starts this block.
+    #[inline(always)]
+    pub fn keyword(&self) -> &Token {
+        self.header.keyword().expect("Block header without a keyword")
+    }
+}
+
+impl Display for CstBlock {
+    fn fmt(&self, f: &mut Formatter) -> FmtResult {
+        Display::fmt(&self.header, f)?;
+
+        for child in &self.children {
+            Display::fmt(child, f)?;
+        }
+
+        if let Some(end) = &self.end {
+            Display::fmt(end, f)?;
+        }
+
+        Ok(())
+    }
+}
+
+impl CstLine {
+    /// Parse the next line from the stream, including the help text if this is a `help` line.
+    fn parse(chars: &mut PeekableChars) -> Result<Self, KConfigError> {
+        let mut line = Self::default();
+        let mut whitespace = String::new();
+
+        while let Some(c) = chars.peek() {
+            match c {
+                '\n' => {
+                    _ = chars.next();
+                    line.eol.push(c);
+                    break;
+                }
+
+                '#' => {
+                    line.trailing = take(&mut whitespace);
+                    line.comment = Some(chars.read_until('\n').to_string());
+                }
+
+                '\\' if chars.starts_with("\\\n") => {
+                    chars.advance(2);
+                    whitespace.push_str("\\\n");
+                }
+
+                c if c.is_whitespace() => {
+                    _ = chars.next();
+                    whitespace.push(c);
+                }
+
+                _ => {
+                    let start = chars.offset();
+                    let token = parse_token(chars)?;
+                    let text = chars.base_str()[start..chars.offset()].to_string();
+
+                    line.tokens.push(CstToken {
+                        leading: take(&mut whitespace),
+                        text,
+                        token,
+                    });
+                }
+            }
+        }
+
+        if line.comment.is_none() {
+            line.trailing = whitespace;
+        }
+
+        if line.is_help() {
+            line.help = read_help_lines(chars);
+        }
+
+        Ok(line)
+    }
+
+    /// Returns the first token on the line, if any.
+    #[inline(always)]
+    pub fn keyword(&self) -> Option<&Token> {
+        self.tokens.first().map(|t| &t.token.token)
+    }
+
+    /// Indicates whether this line has no tokens (i.e. it is blank or only contains a comment).
+    #[inline(always)]
+    pub fn is_trivia(&self) -> bool {
+        self.tokens.is_empty()
+    }
+
+    /// Indicates whether this line is blank (no tokens and no comment).
+    #[inline(always)]
+    pub fn is_blank(&self) -> bool {
+        self.tokens.is_empty() && self.comment.is_none()
+    }
+
+    /// Indicates whether this line starts a help block.
+    #[inline(always)]
+    pub fn is_help(&self) -> bool {
+        self.tokens.len() == 1 && self.tokens[0].token.token == Token::Help
+    }
+
+    /// Returns the location of the first token on the line, if any.
+    #[inline(always)]
+    pub fn location(&self) -> Option<Location> {
+        self.tokens.first().map(|t| t.token.location())
+    }
+}
+
+impl Display for CstLine {
+    fn fmt(&self, f: &mut Formatter) -> FmtResult {
+        for token in &self.tokens {
+            f.write_str(&token.leading)?;
+            f.write_str(&token.text)?;
+        }
+
+        f.write_str(&self.trailing)?;
+
+        if let Some(comment) = &self.comment {
+            f.write_str(comment)?;
+        }
+
+        f.write_str(&self.eol)?;
+
+        for help in &self.help {
+            f.write_str(help)?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Indicates whether a token ends the attributes of a `config`, `menuconfig`, or `comment` entry.
+fn is_entry_keyword(token: &Token) -> bool {
+    matches!(
+        token,
+        Token::Choice
+            | Token::Comment
+            | Token::Config
+            | Token::EndChoice
+            | Token::EndIf
+            | Token::EndMenu
+            | Token::If
+            | Token::Mainmenu
+            | Token::Menu
+            | Token::MenuConfig
+    ) || token.is_source()
+}
+
+/// Read the raw lines of a help block.
+///
+/// The first non-blank line determines the indentation of the help text. The block continues until a non-blank line
+/// is found that does not start with this indentation. Blank lines at the end of the block are not included.
+fn read_help_lines(chars: &mut PeekableChars) -> Vec<String> {
+    let mut lookahead = chars.clone();
+    let mut lines = Vec::new();
+    let mut indent: Option<&str> = None;
+    let mut committed = 0;
+
+    while !lookahead.is_empty() {
+        let rest = &lookahead.base_str()[lookahead.offset()..];
+        let len = rest.find('\n').map(|n| n + 1).unwrap_or(rest.len());
+        let line = &rest[..len];
+
+        if !line.trim().is_empty() {
+            let line_indent = &line[..line.len() - line.trim_start().len()];
+
+            match indent {
+                None if line_indent.is_empty() => break,
+                None => indent = Some(line_indent),
+                Some(indent) if !line.starts_with(indent) => break,
+                Some(_) => (),
+            }
+        }
+
+        lines.push(line.to_string());
+        lookahead.advance(len);
+
+        if !line.trim().is_empty() {
+            *chars = lookahead.clone();
+            committed = lines.len();
+        }
+    }
+
+    lines.truncate(committed);
+    lines
+}
+
+/// Build nodes from the given lines until the end of the input or the end of the current block.
+///
+/// If `end` is specified, this is the keyword that ends the current block along with the location of the block's
+/// header. The line containing the keyword is returned along with the nodes.
+fn parse_nodes<I>(
+    lines: &mut Peekable<I>,
+    end: Option<(Token, Expected, Location)>,
+) -> Result<(Vec<CstNode>, Option<CstLine>), KConfigError>
+where
+    I: Iterator<Item = CstLine>,
+{
+    let mut nodes = Vec::new();
+
+    while let Some(line) = lines.next() {
+        let Some(keyword) = line.keyword().cloned() else {
+            nodes.push(CstNode::Line(line));
+            continue;
+        };
+
+        match keyword {
+            Token::Config | Token::MenuConfig | Token::Comment => {
+                let mut children = Vec::new();
+
+                while let Some(next) = lines.peek() {
+                    if next.keyword().map(is_entry_keyword).unwrap_or(false) {
+                        break;
+                    }
+
+                    children.push(CstNode::Line(lines.next().unwrap()));
+                }
+
+                // Blank lines and comments after the last attribute belong to the enclosing block.
+                let split = children.iter().rposition(|node| !node.is_trivia()).map(|i| i + 1).unwrap_or(0);
+                let trivia = children.split_off(split);
+
+                nodes.push(CstNode::Block(CstBlock {
+                    header: line,
+                    children,
+                    end: None,
+                }));
+                nodes.extend(trivia);
+            }
+
+            Token::Menu | Token::Choice | Token::If => {
+                let block_end = match keyword {
+                    Token::Menu => (Token::EndMenu, Expected::EndMenu),
+                    Token::Choice => (Token::EndChoice, Expected::EndChoice),
+                    _ => (Token::EndIf, Expected::EndIf),
+                };
+
+                let location = line.location().unwrap();
+                let (children, end) = parse_nodes(lines, Some((block_end.0, block_end.1, location)))?;
+
+                nodes.push(CstNode::Block(CstBlock {
+                    header: line,
+                    children,
+                    end,
+                }));
+            }
+
+            Token::EndMenu | Token::EndChoice | Token::EndIf => {
+                let location = line.location().unwrap();
+
+                match &end {
+                    Some((end_token, _, _)) if *end_token == keyword => return Ok((nodes, Some(line))),
+                    Some((_, expected, _)) => {
+                        return Err(KConfigError::unexpected(keyword, expected.clone(), location))
+                    }
+                    None => return Err(KConfigError::unexpected(keyword, Expected::KeywordOrSymbol, location)),
+                }
+            }
+
+            _ => nodes.push(CstNode::Line(line)),
+        }
+    }
+
+    if let Some((_, expected, location)) = end {
+        return Err(KConfigError::unexpected_eof(expected, location));
+    }
+
+    Ok((nodes, None))
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        crate::parser::{CstFile, CstNode, PeekableChars, Token},
+        std::{
+            env, fs,
+            path::{Path, PathBuf},
+        },
+    };
+
+    fn kconfig_files(dir: &Path, result: &mut Vec<PathBuf>) {
+        for entry in fs::read_dir(dir).unwrap() {
+            let path = entry.unwrap().path();
+            if path.is_dir() {
+                kconfig_files(&path, result);
+            } else if path.file_name().unwrap().to_string_lossy().starts_with("Kconfig") {
+                result.push(path);
+            }
+        }
+    }
+
+    #[test_log::test]
+    fn cst_structure() {
+        let input = r##"# Leading comment
+menu "Foo"
+    config FOO
+        boolean "Foo" # trailing
+        default y
+        ---help---
+            Say foo.
+
+            More foo.
+
+    # Between entries
+    choice BAR
+        prompt "Bar"
+        config BAR_A
+            bool "A"
+    endchoice
+endmenu
+"##;
+        let cst = CstFile::parse(PeekableChars::new(input, Path::new("test"))).unwrap();
+        assert_eq!(cst.to_string(), input);
+        assert_eq!(cst.nodes.len(), 2);
+        assert!(cst.nodes[0].is_trivia());
+
+        let menu = cst.nodes[1].as_block().unwrap();
+        assert_eq!(*menu.keyword(), Token::Menu);
+        assert!(menu.end.is_some());
+
+        // The config, the hoisted blank line and comment, and the choice.
+        assert_eq!(menu.children.len(), 4);
+        let CstNode::Block(config) = &menu.children[0] else {
+            panic!("Expected config block");
+        };
+        assert_eq!(config.children.len(), 3);
+
+        let help = config.children[2].as_line().unwrap();
+        assert!(help.is_help());
+        assert_eq!(help.help.len(), 3);
+        assert_eq!(config.children[0].as_line().unwrap().tokens[0].text, "boolean");
+    }
+
+    #[test_log::test]
+    fn cst_unterminated_menu() {
+        let input = "menu \"Foo\"\nconfig FOO\n    bool\n";
+        assert!(CstFile::parse(PeekableChars::new(input, Path::new("test"))).is_err());
+    }
+
+    #[test_log::test]
+    fn cst_lossless_esp_idf() {
+        let base_dir = PathBuf::from(env::var("CARGO_MANIFEST_DIR").unwrap());
+        let mut files = Vec::new();
+        kconfig_files(&base_dir.join("tests/esp-idf"), &mut files);
+        assert!(!files.is_empty());
+
+        for file in files {
+            let input = fs::read_to_string(&file).unwrap();
+            let cst = CstFile::from_file(&file).unwrap();
+            assert_eq!(cst.to_string(), input, "Round trip failed for {file:?}");
+        }
+    }
+}
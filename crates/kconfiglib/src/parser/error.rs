@@ -1,5 +1,5 @@
 use {
-    crate::parser::Location,
+    crate::parser::{Location, Type},
     std::{
         backtrace::Backtrace,
         error::Error,
@@ -31,11 +31,36 @@ impl KConfigError {
         }
     }
 
+    /// Create a new [KConfigError] with the given kind and no location. The backtrace will be captured automatically.
+    pub fn from_kind(kind: KConfigErrorKind) -> Self {
+        Self {
+            kind,
+            backtrace: Backtrace::capture(),
+            location: None,
+        }
+    }
+
+    /// Create a new [KConfigError] for a symbol name that is already in use.
+    pub fn duplicate_symbol(name: impl ToString, location: Location) -> Self {
+        Self::new(KConfigErrorKind::DuplicateSymbol(name.to_string()), location)
+    }
+
     /// Create a new [KConfigError] for an invalid environment variable.
     pub fn invalid_env(var: impl ToString, location: Location) -> Self {
         Self::new(KConfigErrorKind::InvalidEnv(var.to_string()), location)
     }
 
+    /// Create a new [KConfigError] for an environment variable whose value is not valid for the type of the symbol bound
+    /// to it with `option env`.
+    pub fn invalid_env_value(var: impl ToString, value: impl ToString, r#type: Type, location: Location) -> Self {
+        Self::new(KConfigErrorKind::InvalidEnvValue(var.to_string(), value.to_string(), r#type), location)
+    }
+
+    /// Create a new [KConfigError] for an invalid or unknown target name.
+    pub fn invalid_target(name: impl ToString) -> Self {
+        Self::from_kind(KConfigErrorKind::InvalidTarget(name.to_string()))
+    }
+
     /// Create a new [KConfigError] for an invalid integer literal.
     pub fn invalid_integer(value: impl ToString, location: Location) -> Self {
         Self::new(KConfigErrorKind::InvalidInteger(value.to_string()), location)
@@ -70,6 +95,16 @@ impl KConfigError {
     pub fn unknown_env(var: impl ToString, location: Location) -> Self {
         Self::new(KConfigErrorKind::UnknownEnv(var.to_string()), location)
     }
+
+    /// Create a new [KConfigError] for a menu that could not be found.
+    pub fn unknown_menu(prompt: impl ToString) -> Self {
+        Self::from_kind(KConfigErrorKind::UnknownMenu(prompt.to_string()))
+    }
+
+    /// Create a new [KConfigError] for a symbol that could not be found.
+    pub fn unknown_symbol(name: impl ToString) -> Self {
+        Self::from_kind(KConfigErrorKind::UnknownSymbol(name.to_string()))
+    }
 }
 
 impl Display for KConfigError {
@@ -84,11 +119,7 @@ impl Display for KConfigError {
 
 impl From<IoError> for KConfigError {
     fn from(e: IoError) -> Self {
-        Self {
-            kind: KConfigErrorKind::Io(e),
-            backtrace: Backtrace::capture(),
-            location: None,
-        }
+        Self::from_kind(KConfigErrorKind::Io(e))
     }
 }
 
@@ -97,12 +128,21 @@ impl Error for KConfigError {}
 /// The types of errors that can occur while parsing a KConfig file.
 #[derive(Debug)]
 pub enum KConfigErrorKind {
+    /// A symbol with the given name already exists.
+    DuplicateSymbol(String),
+
     /// Invalid environment variable.
     InvalidEnv(String),
 
+    /// Environment variable value (variable, value, type) that is not valid for the type of an `option env` symbol.
+    InvalidEnvValue(String, String, Type),
+
     /// Invalid integer literal.
     InvalidInteger(String),
 
+    /// Invalid or unknown target name.
+    InvalidTarget(String),
+
     /// Invalid Unicode value.
     InvalidUnicode(u32),
 
@@ -126,13 +166,24 @@ pub enum KConfigErrorKind {
 
     /// Unknown variable in filename expansion.
     UnknownEnv(String),
+
+    /// A menu with the given prompt could not be found.
+    UnknownMenu(String),
+
+    /// A symbol with the given name could not be found.
+    UnknownSymbol(String),
 }
 
 impl Display for KConfigErrorKind {
     fn fmt(&self, f: &mut Formatter) -> FmtResult {
         match self {
+            Self::DuplicateSymbol(name) => write!(f, "Symbol already exists: {name}"),
             Self::InvalidEnv(var) => write!(f, "Non-Unicode environment variable: {var}"),
+            Self::InvalidEnvValue(var, value, r#type) => {
+                write!(f, "Invalid {type} value for environment variable {var}: {value:?}")
+            }
             Self::InvalidInteger(value) => write!(f, "Invalid integer literal: {value}"),
+            Self::InvalidTarget(name) => write!(f, "Invalid target: {name:?}"),
             Self::InvalidUnicode(value) => write!(f, "Invalid Unicode value: \\u{{{value:x}}}"),
             Self::Io(e) => write!(f, "I/O error: {e}"),
             Self::Missing(expected) => write!(f, "Missing {expected}"),
@@ -149,6 +200,8 @@ impl Display for KConfigErrorKind {
                 }
             }
             Self::UnknownEnv(var) => write!(f, "Unknown variable: {var}"),
+            Self::UnknownMenu(prompt) => write!(f, "Unknown menu: {prompt:?}"),
+            Self::UnknownSymbol(name) => write!(f, "Unknown symbol: {name}"),
         }
     }
 }
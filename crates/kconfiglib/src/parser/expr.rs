@@ -152,13 +152,13 @@ impl LocExpr {
 
         let op = tokens.next().unwrap();
         let loc = lhs.location();
-        let rhs = Self::parse_top(op.location(), tokens)?;
+        let rhs = Self::parse_or(op.location(), tokens)?;
         Ok(Self::new(Expr::Or(lhs.into(), rhs.into()), loc))
     }
 
-    /// Parse an AND ('&&') expression, or return the underlying comparison expression.
+    /// Parse an AND ('&&') expression, or return the underlying unary-not expression.
     fn parse_and(prev: Location, tokens: &mut TokenLine) -> Result<Self, KConfigError> {
-        let lhs = Self::parse_comparison(prev, tokens)?;
+        let lhs = Self::parse_unary_not(prev, tokens)?;
         let Some(op) = tokens.peek() else {
             return Ok(lhs);
         };
@@ -169,13 +169,13 @@ impl LocExpr {
 
         let op = tokens.next().unwrap();
         let loc = lhs.location();
-        let rhs = Self::parse_top(op.location(), tokens)?;
+        let rhs = Self::parse_and(op.location(), tokens)?;
         Ok(Self::new(Expr::And(lhs.into(), rhs.into()), loc))
     }
 
-    /// Parse a comparison expression, or return the underlying unary-not expression.
+    /// Parse a comparison expression, or return the underlying terminal expression.
     fn parse_comparison(prev: Location, tokens: &mut TokenLine) -> Result<Self, KConfigError> {
-        let lhs = Self::parse_unary_not(prev, tokens)?;
+        let lhs = Self::parse_terminal(prev, tokens)?;
 
         let Some(op) = tokens.peek() else {
             return Ok(lhs);
@@ -188,14 +188,17 @@ impl LocExpr {
         let op = op.clone();
 
         _ = tokens.next();
-        let rhs = Self::parse_top(op.location(), tokens)?;
+        let rhs = Self::parse_terminal(op.location(), tokens)?;
         let loc = lhs.location();
         let cmp = op.token.try_into().unwrap();
 
         Ok(Self::new(Expr::Cmp(cmp, lhs.into(), rhs.into()), loc))
     }
 
-    /// Parse a unary not expression, or return the underlying terminal expression.
+    /// Parse a unary not expression, or return the underlying comparison expression.
+    ///
+    /// As in the Linux kconfig grammar, `!` applies to the comparison or terminal that immediately follows it, so
+    /// `!A && B` is `(!A) && B` and `!A = B` is `!(A = B)`.
     fn parse_unary_not(prev: Location, tokens: &mut TokenLine) -> Result<Self, KConfigError> {
         let Some(token) = tokens.peek() else {
             return Err(KConfigError::missing(Expected::Expr, prev));
@@ -204,10 +207,10 @@ impl LocExpr {
         if token.token == Token::Not {
             let loc = token.location();
             _ = tokens.next();
-            let expr = Self::parse_top(prev, tokens)?;
+            let expr = Self::parse_unary_not(loc, tokens)?;
             Ok(Self::new(Expr::Not(expr.into()), loc))
         } else {
-            Self::parse_terminal(prev, tokens)
+            Self::parse_comparison(prev, tokens)
         }
     }
 
@@ -263,6 +266,49 @@ impl LocExpr {
     }
 }
 
+impl Expr {
+    /// Format the expression the way kconfiglib's `expr_str()` does, for output read by ESP-IDF tools.
+    ///
+    /// This differs from the [`Display`] form: equality is written as `=`, string literals are quoted without Rust
+    /// escapes, and the operands of `||` that are `&&` expressions are parenthesized.
+    pub fn kconfiglib_str(&self) -> String {
+        // Operands of a comparison and `!` are always terminals in kconfiglib; parenthesize anything else.
+        let operand = |expr: &LocExpr| match expr.expr {
+            Self::Symbol(_) | Self::Hex(_) | Self::Int(_) | Self::String(_) => expr.expr.kconfiglib_str(),
+            _ => format!("({})", expr.expr.kconfiglib_str()),
+        };
+
+        match self {
+            Self::Symbol(s) => s.name.clone(),
+            Self::Hex(i) => format!("0x{i:x}"),
+            Self::Int(i) => i.to_string(),
+            Self::String(s) => format!("\"{}\"", s.replace('\\', "\\\\").replace('"', "\\\"")),
+            Self::Cmp(op, lhs, rhs) => {
+                let op = match op {
+                    ExprCmpOp::Eq => "=".to_string(),
+                    op => op.to_string(),
+                };
+                format!("{} {op} {}", operand(lhs), operand(rhs))
+            }
+            Self::Not(inner) => format!("!{}", operand(inner)),
+            Self::And(lhs, rhs) => {
+                let side = |expr: &LocExpr| match expr.expr {
+                    Self::Or(_, _) => format!("({})", expr.expr.kconfiglib_str()),
+                    _ => expr.expr.kconfiglib_str(),
+                };
+                format!("{} && {}", side(lhs), side(rhs))
+            }
+            Self::Or(lhs, rhs) => {
+                let side = |expr: &LocExpr| match expr.expr {
+                    Self::And(_, _) => format!("({})", expr.expr.kconfiglib_str()),
+                    _ => expr.expr.kconfiglib_str(),
+                };
+                format!("{} || {}", side(lhs), side(rhs))
+            }
+        }
+    }
+}
+
 impl Display for Expr {
     fn fmt(&self, f: &mut Formatter) -> FmtResult {
         match self {
@@ -379,4 +425,98 @@ mod tests {
         let mut token_line = crate::parser::TokenLine::new(&tokens);
         let _expr = super::LocExpr::parse(Location::new(path, 1, 1), &mut token_line).unwrap();
     }
+
+    #[test_log::test]
+    fn precedence() {
+        let path = Path::new("test");
+        let sym = |name: &str, col| LocToken::new(Token::Symbol(name.to_string()), Location::new(path, 1, col));
+        let op = |token, col| LocToken::new(token, Location::new(path, 1, col));
+
+        // !A && B = C || D
+        let tokens = vec![
+            op(Token::Not, 1),
+            sym("A", 2),
+            op(Token::And, 4),
+            sym("B", 7),
+            op(Token::Eq, 8),
+            sym("C", 9),
+            op(Token::Or, 11),
+            sym("D", 14),
+        ];
+
+        let mut token_line = crate::parser::TokenLine::new(&tokens);
+        let expr = super::LocExpr::parse(Location::new(path, 1, 1), &mut token_line).unwrap();
+        assert_eq!(expr.expr.to_string(), "!A && B == C || D");
+
+        let super::Expr::Or(lhs, _) = &expr.expr else {
+            panic!("Expected || at the top level: {expr:?}");
+        };
+        let super::Expr::And(not_a, _) = &lhs.expr else {
+            panic!("Expected && under ||: {lhs:?}");
+        };
+        assert!(matches!(not_a.expr, super::Expr::Not(_)));
+    }
+
+    /// Format an expression with every subexpression parenthesized, to show how it was grouped.
+    fn grouped(expr: &super::LocExpr) -> String {
+        match &expr.expr {
+            super::Expr::Cmp(op, lhs, rhs) => format!("({} {op} {})", grouped(lhs), grouped(rhs)),
+            super::Expr::Not(e) => format!("!{}", grouped(e)),
+            super::Expr::And(lhs, rhs) => format!("({} && {})", grouped(lhs), grouped(rhs)),
+            super::Expr::Or(lhs, rhs) => format!("({} || {})", grouped(lhs), grouped(rhs)),
+            e => e.to_string(),
+        }
+    }
+
+    #[test_log::test]
+    fn operand_grouping() {
+        let path = Path::new("test");
+        let parse = |tokens: Vec<Token>| {
+            let tokens: Vec<LocToken> = tokens
+                .into_iter()
+                .enumerate()
+                .map(|(i, token)| LocToken::new(token, Location::new(path, 1, i + 1)))
+                .collect();
+            let mut token_line = crate::parser::TokenLine::new(&tokens);
+            grouped(&super::LocExpr::parse(Location::new(path, 1, 1), &mut token_line).unwrap())
+        };
+        let sym = |name: &str| Token::Symbol(name.to_string());
+
+        // These were previously parsed as `(A && (B || C))`, `(A == (B && C))`, and `!(A && B)`.
+        assert_eq!(parse(vec![sym("A"), Token::And, sym("B"), Token::Or, sym("C")]), "((A && B) || C)");
+        assert_eq!(parse(vec![sym("A"), Token::Eq, sym("B"), Token::And, sym("C")]), "((A == B) && C)");
+        assert_eq!(parse(vec![Token::Not, sym("A"), Token::And, sym("B")]), "(!A && B)");
+
+        assert_eq!(parse(vec![Token::Not, sym("A"), Token::Eq, sym("B")]), "!(A == B)");
+        assert_eq!(parse(vec![sym("A"), Token::Or, sym("B"), Token::And, sym("C")]), "(A || (B && C))");
+    }
+
+    #[test_log::test]
+    fn kconfiglib_str() {
+        let path = Path::new("test");
+        let tokens: Vec<LocToken> = [
+            Token::Symbol("TARGET".to_string()),
+            Token::Eq,
+            Token::StrLit("esp\"32".to_string()),
+            Token::And,
+            Token::Not,
+            Token::LParen,
+            Token::Symbol("B".to_string()),
+            Token::Or,
+            Token::Symbol("C".to_string()),
+            Token::RParen,
+            Token::Or,
+            Token::Symbol("D".to_string()),
+            Token::Ne,
+            Token::IntLit(5),
+        ]
+        .into_iter()
+        .enumerate()
+        .map(|(i, token)| LocToken::new(token, Location::new(path, 1, i + 1)))
+        .collect();
+
+        let mut token_line = crate::parser::TokenLine::new(&tokens);
+        let expr = super::LocExpr::parse(Location::new(path, 1, 1), &mut token_line).unwrap();
+        assert_eq!(expr.expr.kconfiglib_str(), "(TARGET = \"esp\\\"32\" && !(B || C)) || D != 5");
+    }
 }
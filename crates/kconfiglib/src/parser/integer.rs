@@ -33,11 +33,7 @@ fn parse_dec_literal(chars: &mut PeekableChars) -> Result<Token, KConfigError> {
         _ = chars.next();
     }
 
-    loop {
-        let Some(c) = chars.peek() else {
-            break;
-        };
-
+    while let Some(c) = chars.peek() {
         if c.is_ascii_digit() {
             literal.push(c);
             _ = chars.next();
@@ -70,11 +66,7 @@ fn parse_hex_literal(chars: &mut PeekableChars) -> Result<Token, KConfigError> {
         return Err(KConfigError::unexpected(c, Expected::IntegerLiteral, start));
     }
 
-    loop {
-        let Some(c) = chars.peek() else {
-            break;
-        };
-
+    while let Some(c) = chars.peek() {
         if c.is_ascii_hexdigit() {
             literal.push(c);
             _ = chars.next();
@@ -104,11 +96,7 @@ fn parse_dec_oct_literal(chars: &mut PeekableChars) -> Result<Token, KConfigErro
         return Err(KConfigError::unexpected(c, Expected::IntegerLiteral, start));
     }
 
-    loop {
-        let Some(c) = chars.peek() else {
-            break;
-        };
-
+    while let Some(c) = chars.peek() {
         if ('0'..='7').contains(&c) {
             literal.push(c);
             _ = chars.next();
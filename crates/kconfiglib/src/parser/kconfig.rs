@@ -1,9 +1,9 @@
 use {
     crate::{
         parser::{parse_stream, Block, KConfigError, LocExpr, PeekableChars, PeekableTokenLinesExt},
-        Context, ResolveBlock,
+        Context, Include, IncludeKind, ResolveBlock,
     },
-    std::{cell::RefCell, fs::File, io::Read, path::Path, rc::Rc},
+    std::{cell::RefCell, path::Path, rc::Rc},
 };
 
 /// A parsed KConfig hierarchy.
@@ -17,14 +17,29 @@ impl KConfig {
     /// Read a full Kconfig tree starting with the given Kconfig file.
     ///
     /// This recursively reads any configuration files in `source` (or `osource`, `orsource`, `rsource`) statements.
+    /// Files are read from the context's [filesystem][Context::fs].
     pub fn from_file<C>(filename: &Path, base_dir: &Path, context: &C) -> Result<Self, KConfigError>
     where
         C: Context,
     {
-        let mut file = File::open(filename)?;
-        let mut input = String::new();
-        file.read_to_string(&mut input)?;
-        Self::from_str(PeekableChars::new(input.as_str(), filename), base_dir, context)
+        context.record_include(Include::new(filename.to_path_buf(), IncludeKind::Read, None, context.fs()));
+        Self::from_file_cond(filename, base_dir, context, None)
+    }
+
+    /// Read a full Kconfig tree starting with the given Kconfig file, adding `parent_cond` as a dependency of every
+    /// entry. This is used for files sourced from within an `if` block.
+    pub(crate) fn from_file_cond<C>(
+        filename: &Path,
+        base_dir: &Path,
+        context: &C,
+        parent_cond: Option<&LocExpr>,
+    ) -> Result<Self, KConfigError>
+    where
+        C: Context,
+    {
+        let input = context.fs().read_to_string(filename)?;
+        let result = Self::from_str_raw(PeekableChars::new(input.as_str(), filename), base_dir, context)?;
+        result.resolve_block(base_dir, context, parent_cond)
     }
 
     /// Create a KConfig file from the given string input.
@@ -84,7 +99,7 @@ mod tests {
         crate::parser::{Block, Expr, KConfig, PeekableChars},
         std::{
             collections::HashMap,
-            env,
+            env, fs,
             path::{Path, PathBuf},
         },
     };
@@ -198,4 +213,53 @@ config BAZ
             panic!("Expected symbol");
         }
     }
+
+    #[test_log::test]
+    fn if_conditions() {
+        let context = HashMap::default();
+        let sourced = env::temp_dir().join(format!("kconfig-if-conditions-{}", std::process::id()));
+        fs::write(&sourced, "config QUX\n    bool \"Qux\"\n").unwrap();
+
+        let kconfig = KConfig::from_str(
+            PeekableChars::new(
+                &format!(
+                    r##"if FOO
+config BAR
+    bool "Bar"
+    depends on BAZ
+
+menu "Menu"
+    config BAZ
+        bool "Baz"
+endmenu
+
+source "{}"
+endif
+"##,
+                    sourced.display()
+                ),
+                Path::new("test"),
+            ),
+            Path::new("/tmp"),
+            &context,
+        )
+        .unwrap();
+        _ = fs::remove_file(&sourced);
+
+        let depends_on = |block: &Block| match block {
+            Block::Config(c) => c.depends_on.iter().map(|e| e.expr.to_string()).collect::<Vec<_>>(),
+            Block::Menu(m) => m.depends_on.iter().map(|e| e.expr.to_string()).collect(),
+            block => panic!("Unexpected block: {block:?}"),
+        };
+
+        // Entries in an `if` block (including sourced ones) previously lost the condition.
+        assert_eq!(kconfig.blocks.len(), 3);
+        assert_eq!(depends_on(&kconfig.blocks[0].borrow()), ["BAZ", "FOO"]);
+        assert_eq!(depends_on(&kconfig.blocks[2].borrow()), ["FOO"]);
+
+        // Entries in a menu inherit the condition from the menu.
+        let menu = kconfig.blocks[1].borrow();
+        assert_eq!(depends_on(&menu), ["FOO"]);
+        assert_eq!(depends_on(&menu.as_menu().unwrap().blocks[0].borrow()), Vec::<String>::new());
+    }
 }
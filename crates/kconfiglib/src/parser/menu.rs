@@ -122,13 +122,17 @@ impl ResolveBlock for Menu {
     {
         // Fields that are cloned.
         let prompt = self.prompt.clone();
-        let depends_on = self.depends_on.clone();
+        let mut depends_on = self.depends_on.clone();
         let visibility = self.visibility.clone();
         let comments = self.comments.clone();
 
+        // The condition of an enclosing `if` block becomes a dependency of the menu; the items in the menu inherit it
+        // from there.
+        depends_on.extend(parent_cond.cloned());
+
         log::debug!("Loading menu: {:?}", prompt);
         // Load the blocks.
-        let blocks = self.blocks.resolve_block(base_dir, context, parent_cond)?;
+        let blocks = self.blocks.resolve_block(base_dir, context, None)?;
         for block in blocks.iter() {
             assert!(block.borrow().as_if().is_none(), "Unresolved if block: {:?}", block.borrow());
         }
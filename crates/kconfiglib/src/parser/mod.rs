@@ -4,6 +4,7 @@ mod block;
 mod choice;
 mod comment;
 mod config;
+mod cst;
 mod error;
 mod expr;
 mod integer;
@@ -20,6 +21,6 @@ mod types;
 mod whitespace;
 
 pub use {
-    block::*, choice::*, config::*, error::*, expr::*, kconfig::*, lit_value::*, location::*, menu::*,
+    block::*, choice::*, config::*, cst::*, error::*, expr::*, kconfig::*, lit_value::*, location::*, menu::*,
     prompt::*, source::*, streams::*, string_literal::*, token::*, types::*,
 };
@@ -1,18 +1,16 @@
 use {
     crate::{
         context_closure,
-        parser::{
-            cache_path, Block, KConfig, KConfigError, KConfigErrorKind, LocExpr, LocString, Located, PeekableChars,
-            TokenLine,
-        },
-        Context, ResolveBlock,
+        parser::{cache_path, Block, KConfig, KConfigError, KConfigErrorKind, LocExpr, LocString, Located, TokenLine},
+        vfs::{glob, is_glob},
+        Context, FsContext, Include, IncludeKind, KConfigFs, MemoryFs, OverlayFs, ResolveBlock,
     },
     log::{debug, error, trace},
     shellexpand::env_with_context,
     std::{
         cell::RefCell,
         env::VarError,
-        io::ErrorKind as IoErrorKind,
+        io::{Error as IoError, ErrorKind as IoErrorKind},
         path::{Path, PathBuf},
         rc::Rc,
     },
@@ -24,7 +22,7 @@ pub struct Source {
     /// The filename/glob pattern to read.
     pub filename: LocString,
 
-    /// Whether the source statement is optional (`osource` or `orsource``).
+    /// Whether the source statement is optional (`osource`, `orsource`, `gsource`, or `grsource`).
     pub optional: bool,
 
     /// Whether the filename is relative to the current Kconfig file (`orsource` or `rsource`).
@@ -34,8 +32,9 @@ pub struct Source {
     pub base_dir: PathBuf,
 }
 
-/// The URL prefix for an inline source file.
-const INLINE_PREFIX: &str = "inline:";
+/// The URL prefix for an inline source file. The rest of the filename is the text of the file, which is read from the
+/// path [`INLINE_PREFIX`] in the filesystem returned by [`inline_fs()`].
+pub(crate) const INLINE_PREFIX: &str = "inline:";
 
 impl Source {
     /// Parse a source line.
@@ -59,6 +58,75 @@ impl Source {
             base_dir,
         })
     }
+
+    /// Return the filename with any `${ENV}` variables expanded.
+    pub fn expand_filename<C>(&self, context: &C) -> Result<String, KConfigError>
+    where
+        C: Context,
+    {
+        let filename = &self.filename;
+
+        match env_with_context(filename.as_str(), context_closure(context)) {
+            Ok(s) => Ok(s.into_owned()),
+            Err(e) => Err(match e.cause {
+                VarError::NotPresent => KConfigError::unknown_env(e.var_name, filename.location()),
+                VarError::NotUnicode(_) => KConfigError::invalid_env(e.var_name, filename.location()),
+            }),
+        }
+    }
+
+    /// Return the directory the filename is relative to: the directory of the current Kconfig file for `rsource`
+    /// and `orsource`, or `base_dir` otherwise.
+    pub fn source_dir<'a>(&'a self, base_dir: &'a Path) -> &'a Path {
+        if self.relative {
+            &self.base_dir
+        } else {
+            base_dir
+        }
+    }
+
+    /// Return the files to read for an expanded, non-inline filename.
+    ///
+    /// If the filename contains glob wildcards, this returns the matching files in sorted order. It is an error for a
+    /// glob in a non-optional source statement to match nothing. Otherwise, this returns the single file named; whether
+    /// it exists is checked when it is read.
+    pub fn paths<C>(&self, s_filename: &str, base_dir: &Path, context: &C) -> Result<Vec<PathBuf>, KConfigError>
+    where
+        C: Context,
+    {
+        let path = self.source_dir(base_dir).join(s_filename);
+        if !is_glob(s_filename) {
+            return Ok(vec![path]);
+        }
+
+        let mut searched = Vec::new();
+        let paths = glob(context.fs(), &path, &mut searched)?;
+        for dir in searched {
+            context.record_include(Include::new(dir, IncludeKind::Glob, Some(self.filename.location()), context.fs()));
+        }
+
+        if paths.is_empty() {
+            if self.optional {
+                debug!("No files match optional source pattern {path:?}");
+            } else {
+                let io_error = IoError::new(IoErrorKind::NotFound, format!("No files match {}", path.display()));
+                return Err(KConfigError::new(KConfigErrorKind::Io(io_error), self.filename.location()));
+            }
+        }
+
+        Ok(paths)
+    }
+
+    /// Returns the text of an inline source, or `None` if the (expanded) filename refers to a file.
+    pub(crate) fn inline_text(s_filename: &str) -> Option<&str> {
+        s_filename.strip_prefix(INLINE_PREFIX)
+    }
+}
+
+/// Returns a filesystem serving the text of an inline source as the file [`INLINE_PREFIX`], with every other path read
+/// from `fs`.
+pub(crate) fn inline_fs<'a>(text: &str, fs: &'a dyn KConfigFs) -> OverlayFs<'a> {
+    OverlayFs::new().with_layer(MemoryFs::new().with_file(INLINE_PREFIX, text)).with_layer(fs)
 }
 
 impl ResolveBlock for Source {
@@ -74,59 +142,106 @@ impl ResolveBlock for Source {
     where
         C: Context,
     {
-        let self_base_dir = &self.base_dir;
-        let filename = &self.filename;
-
-        // Expand any ${ENV} variables in the filename.
-        let s_filename = match env_with_context(filename.as_str(), context_closure(context)) {
-            Ok(s) => s,
-            Err(e) => {
-                return Err(match e.cause {
-                    VarError::NotPresent => KConfigError::unknown_env(e.var_name, filename.location()),
-                    VarError::NotUnicode(_) => KConfigError::invalid_env(e.var_name, filename.location()),
-                })
-            }
-        };
-
-        // Check if the filename is an inline source file, read from memory via the context.s
-        if let Some(source) = s_filename.strip_prefix(INLINE_PREFIX) {
-            // Read the source file from the context.
-            let inline = cache_path(Path::new(INLINE_PREFIX));
-
-            let peek = PeekableChars::new(source, inline);
-            let s_kconfig = KConfig::from_str_raw(peek, base_dir, context)?;
-            let s_kconfig = s_kconfig.resolve_block(base_dir, context, parent_cond)?;
+        let s_filename = self.expand_filename(context)?;
+
+        // Inline sources are read like any other file, from a filesystem holding their text. Nesting goes through
+        // `dyn Context` so each level has the same context type.
+        if let Some(text) = Self::inline_text(&s_filename) {
+            let fs = inline_fs(text, context.fs());
+            let context = FsContext::new(context as &dyn Context, &fs);
+            let s_kconfig = KConfig::from_file_cond(Path::new(INLINE_PREFIX), base_dir, &context, parent_cond)?;
             return Ok(s_kconfig.blocks);
         }
 
-        let base_dir = if self.relative {
-            // Relative to this source.
-            self_base_dir
-        } else {
-            // Relative to the current base directory.
-            base_dir
-        };
-
-        let s_filename = base_dir.join(s_filename.as_ref());
-        let s_filename = cache_path(&s_filename);
-
-        trace!("Reading source file {s_filename:?}");
-        match KConfig::from_file(s_filename, base_dir, context) {
-            Ok(s_kconfig) => Ok(s_kconfig.blocks),
-            Err(e) => {
-                let KConfigErrorKind::Io(io_error) = &e.kind else {
-                    error!("Unexpected non-I/O error while reading {s_filename:?}: {e}");
-                    return Err(e);
-                };
-
-                if io_error.kind() != IoErrorKind::NotFound || !self.optional {
-                    error!("Unable to read {s_filename:?}: {io_error}");
-                    return Err(e);
-                }
+        let source_dir = self.source_dir(base_dir);
+        let mut blocks = Vec::new();
+
+        for s_filename in self.paths(&s_filename, base_dir, context)? {
+            let s_filename = cache_path(&s_filename);
+            let location = Some(self.filename.location());
+
+            // Record missing optional files so callers can re-run when they appear.
+            if self.optional && !context.fs().is_file(s_filename) {
+                debug!("Optional source file not found: {s_filename:?}");
+                context.record_include(Include::new(
+                    s_filename.to_path_buf(),
+                    IncludeKind::Missing,
+                    location,
+                    context.fs(),
+                ));
+                continue;
+            }
 
-                debug!("Ignoring NotFound error for optional source file: {s_filename:?}");
-                Ok(Vec::new())
+            context.record_include(Include::new(s_filename.to_path_buf(), IncludeKind::Read, location, context.fs()));
+            trace!("Reading source file {s_filename:?}");
+            match KConfig::from_file_cond(s_filename, source_dir, context, parent_cond) {
+                Ok(s_kconfig) => blocks.extend(s_kconfig.blocks),
+                Err(e) => {
+                    let KConfigErrorKind::Io(io_error) = &e.kind else {
+                        error!("Unexpected non-I/O error while reading {s_filename:?}: {e}");
+                        return Err(e);
+                    };
+
+                    if io_error.kind() != IoErrorKind::NotFound || !self.optional {
+                        error!("Unable to read {s_filename:?}: {io_error}");
+                        return Err(e);
+                    }
+
+                    debug!("Ignoring NotFound error for optional source file: {s_filename:?}");
+                }
             }
         }
+
+        Ok(blocks)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        crate::{
+            parser::{Block, KConfig, KConfigErrorKind},
+            FsContext, MemoryFs,
+        },
+        std::{collections::HashMap, io::ErrorKind as IoErrorKind, path::Path},
+    };
+
+    fn config_names(kconfig: &KConfig) -> Vec<String> {
+        kconfig
+            .blocks
+            .iter()
+            .filter_map(|block| match &*block.borrow() {
+                Block::Config(config) => Some(config.name.to_string()),
+                _ => None,
+            })
+            .collect()
+    }
+
+    fn load(top: &str) -> Result<KConfig, crate::parser::KConfigError> {
+        let fs = MemoryFs::new()
+            .with_file("/src/Kconfig", top)
+            .with_file("/src/components/zeta/Kconfig", "config ZETA\n    bool\n")
+            .with_file("/src/components/alpha/Kconfig", "config ALPHA\n    bool\n")
+            .with_file("/src/components/alpha/Kconfig.projbuild", "config ALPHA_PROJECT\n    bool\n")
+            .with_file("/src/components/beta/Kconfig", "config BETA\n    bool\ngrsource \"*/Kconfig.in\"\n")
+            .with_file("/src/components/beta/b2/Kconfig.in", "config BETA_2\n    bool\n")
+            .with_file("/src/components/beta/b1/Kconfig.in", "config BETA_1\n    bool\n");
+
+        let context = HashMap::<String, String>::new();
+        let context = FsContext::new(&context, &fs);
+        KConfig::from_file(Path::new("/src/Kconfig"), Path::new("/src"), &context)
+    }
+
+    #[test_log::test]
+    fn source_glob() {
+        let kconfig = load("source \"components/*/Kconfig\"\n").unwrap();
+        assert_eq!(config_names(&kconfig), vec!["ALPHA", "BETA", "BETA_1", "BETA_2", "ZETA"]);
+
+        let kconfig = load("gsource \"components/*/Kconfig.projbuild\"\nosource \"missing/*/Kconfig\"\n").unwrap();
+        assert_eq!(config_names(&kconfig), vec!["ALPHA_PROJECT"]);
+
+        let e = load("source \"missing/*/Kconfig\"\n").unwrap_err();
+        assert!(matches!(&e.kind, KConfigErrorKind::Io(io) if io.kind() == IoErrorKind::NotFound));
+        assert_eq!(e.location.unwrap().line, 1);
     }
 }
@@ -94,7 +94,7 @@ impl<'buf> PeekableChars<'buf> {
     //     &self.base[self.offset..]
     // }
 
-    /// Return the section of the string that has already been processed.
+    // /// Return the section of the string that has already been processed.
     // #[inline(always)]
     // pub fn processed(&self) -> &'a str {
     //     &self.base[..self.offset]
@@ -196,7 +196,7 @@ impl<'buf> Iterator for PeekableChars<'buf> {
 
     fn size_hint(&self) -> (usize, Option<usize>) {
         let max = self.base.len() - self.offset;
-        let min = (max + 3) / 4;
+        let min = max.div_ceil(4);
         (min, Some(max))
     }
 }
@@ -269,8 +269,8 @@ impl<'buf> PeekableTokenLines<'buf> {
         &self.base[..self.offset]
     }
 
-    /// Move the offset to the current position. After this,
-    /// [`processed()`][PeekableTokenLines::processed] will return an empty slice.
+    // /// Move the offset to the current position. After this,
+    // /// [`processed()`][PeekableTokenLines::processed] will return an empty slice.
     // #[inline(always)]
     // pub fn set_to_current(&mut self) {
     //     self.base = &self.base[self.offset..];
@@ -311,11 +311,11 @@ impl<'buf> FusedIterator for PeekableTokenLines<'buf> {}
 /// An extension trait for `&[Vec<Token>]` that provides `peek_lines()`.
 pub trait PeekableTokenLinesExt {
     /// Return a [`PeekableTokenLines`] iterator over the slice.
-    fn peek_lines(&self) -> PeekableTokenLines;
+    fn peek_lines(&self) -> PeekableTokenLines<'_>;
 }
 
 impl PeekableTokenLinesExt for [Vec<LocToken>] {
-    fn peek_lines(&self) -> PeekableTokenLines {
+    fn peek_lines(&self) -> PeekableTokenLines<'_> {
         PeekableTokenLines {
             base: self,
             offset: 0,
@@ -553,108 +553,103 @@ pub fn parse_line(chars: &mut PeekableChars) -> Result<Vec<LocToken>, KConfigErr
                     }
                 }
 
-                '"' | '\'' => {
-                    let start = chars.location();
-                    let s = parse_string_literal(chars, c)?;
-                    tokens.push(LocToken::new(Token::StrLit(s), start));
-                }
-
-                '+' | '-' | '0'..='9' => {
-                    let start = chars.location();
-                    let tok = parse_int_hex_literal(chars)?;
-                    tokens.push(LocToken::new(tok, start));
-                }
-
                 c if c.is_whitespace() => {
                     _ = chars.next();
                 }
 
-                c if c.is_alphabetic() || c == '_' => {
-                    let token = parse_keyword_or_symbol(chars)?;
-                    tokens.push(token);
-                }
-
-                '&' if chars.starts_with("&&") => {
-                    let start = chars.location();
-                    _ = chars.next();
-                    _ = chars.next();
-                    tokens.push(LocToken::new(Token::And, start));
-                }
-
-                '|' if chars.starts_with("||") => {
-                    let start = chars.location();
+                '\\' if chars.starts_with("\\\n") => {
+                    // Line continuation. Skip the backslash and newline.
                     _ = chars.next();
                     _ = chars.next();
-                    tokens.push(LocToken::new(Token::Or, start));
                 }
 
-                '=' => {
-                    let start = chars.location();
-                    _ = chars.next();
-                    tokens.push(LocToken::new(Token::Eq, start));
-                }
+                _ => tokens.push(parse_token(chars)?),
+            }
+        }
+    }
+}
 
-                '!' => {
-                    let start = chars.location();
-                    _ = chars.next();
-                    let op = if chars.peek() == Some('=') {
-                        _ = chars.next();
-                        Token::Ne
-                    } else {
-                        Token::Not
-                    };
+/// Parse a single token from the stream.
+///
+/// The stream must be pointing at the first character of the token; whitespace, comments, and line continuations
+/// are not handled here.
+pub(crate) fn parse_token(chars: &mut PeekableChars) -> Result<LocToken, KConfigError> {
+    let start = chars.location();
+
+    let Some(c) = chars.peek() else {
+        return Err(KConfigError::unexpected_eof(Expected::Any, start));
+    };
+
+    let token = match c {
+        '"' | '\'' => Token::StrLit(parse_string_literal(chars, c)?),
+        // `---help---` is an obsolete spelling of `help` that would otherwise be read as a malformed integer.
+        '-' if chars.starts_with("---help---") => {
+            chars.advance("---help---".len());
+            Token::Help
+        }
 
-                    tokens.push(LocToken::new(op, start));
-                }
+        '+' | '-' | '0'..='9' => parse_int_hex_literal(chars)?,
+        c if c.is_alphabetic() || c == '_' => return parse_keyword_or_symbol(chars),
 
-                '(' => {
-                    let start = chars.location();
-                    _ = chars.next();
-                    tokens.push(LocToken::new(Token::LParen, start));
-                }
+        '&' if chars.starts_with("&&") => {
+            chars.advance(2);
+            Token::And
+        }
 
-                ')' => {
-                    let start = chars.location();
-                    _ = chars.next();
-                    tokens.push(LocToken::new(Token::RParen, start));
-                }
+        '|' if chars.starts_with("||") => {
+            chars.advance(2);
+            Token::Or
+        }
 
-                '<' => {
-                    let start = chars.location();
-                    _ = chars.next();
-                    let op = if chars.peek() == Some('=') {
-                        _ = chars.next();
-                        Token::Le
-                    } else {
-                        Token::Lt
-                    };
+        '=' => {
+            _ = chars.next();
+            Token::Eq
+        }
 
-                    tokens.push(LocToken::new(op, start));
-                }
+        '!' => {
+            _ = chars.next();
+            if chars.peek() == Some('=') {
+                _ = chars.next();
+                Token::Ne
+            } else {
+                Token::Not
+            }
+        }
 
-                '>' => {
-                    let start = chars.location();
-                    _ = chars.next();
-                    let op = if chars.peek() == Some('=') {
-                        _ = chars.next();
-                        Token::Ge
-                    } else {
-                        Token::Gt
-                    };
+        '(' => {
+            _ = chars.next();
+            Token::LParen
+        }
 
-                    tokens.push(LocToken::new(op, start));
-                }
+        ')' => {
+            _ = chars.next();
+            Token::RParen
+        }
 
-                '\\' if chars.starts_with("\\\n") => {
-                    // Line continuation. Skip the backslash and newline.
-                    _ = chars.next();
-                    _ = chars.next();
-                }
+        '<' => {
+            _ = chars.next();
+            if chars.peek() == Some('=') {
+                _ = chars.next();
+                Token::Le
+            } else {
+                Token::Lt
+            }
+        }
 
-                _ => return Err(KConfigError::syntax(c, chars.location())),
+        '>' => {
+            _ = chars.next();
+            if chars.peek() == Some('=') {
+                _ = chars.next();
+                Token::Ge
+            } else {
+                Token::Gt
             }
         }
-    }
+
+        _ => return Err(KConfigError::syntax(c, start)),
+    };
+
+    Ok(LocToken::new(token, start))
 }
 
 /// Read a help block from the stream.
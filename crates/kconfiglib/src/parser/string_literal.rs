@@ -68,11 +68,7 @@ pub(crate) fn parse_escape(chars: &mut PeekableChars, interior: &mut String) ->
         c if c.is_whitespace() => {
             // Consume all whitespace
             _ = chars.next();
-            loop {
-                let Some(c) = chars.peek() else {
-                    break;
-                };
-
+            while let Some(c) = chars.peek() {
                 if !c.is_whitespace() {
                     break;
                 }
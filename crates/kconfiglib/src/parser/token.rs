@@ -85,8 +85,8 @@ pub struct LocToken {
 }
 
 impl Token {
-    /// Indicates whether a string is expected after this token. This is used to tell strings from constant symbol
-    /// references durng tokenization, both of which are enclosed in quotes.
+    // /// Indicates whether a string is expected after this token. This is used to tell strings from constant symbol
+    // /// references durng tokenization, both of which are enclosed in quotes.
     // pub fn expects_string(&self) -> bool {
     //     matches!(
     //         self,
@@ -338,13 +338,13 @@ impl LocToken {
 
     /// Returns the symbol name or `None` if this isn't a symbol.
     #[inline(always)]
-    pub fn symbol_value(&self) -> Option<LocStr> {
+    pub fn symbol_value(&self) -> Option<LocStr<'_>> {
         self.token.symbol_value().map(|s| LocStr::new(s, self.location))
     }
 
     /// Returns the string literal value or `None` if this isn't a string literal.
     #[inline(always)]
-    pub fn string_literal_value(&self) -> Option<LocStr> {
+    pub fn string_literal_value(&self) -> Option<LocStr<'_>> {
         self.token.string_literal_value().map(|s| LocStr::new(s, self.location))
     }
 
@@ -390,11 +390,7 @@ pub(crate) fn parse_keyword_or_symbol(chars: &mut PeekableChars) -> Result<LocTo
 
     ident.push(c);
 
-    loop {
-        let Some(c) = chars.peek() else {
-            break;
-        };
-
+    while let Some(c) = chars.peek() {
         if c.is_alphanumeric() || c == '_' {
             ident.push(c);
             _ = chars.next();
@@ -0,0 +1,165 @@
+use {
+    crate::{
+        parser::{KConfig, KConfigError, KConfigErrorKind},
+        ComponentDiscovery, Components, Context, Target,
+    },
+    std::{
+        collections::HashMap,
+        path::{self, Path, PathBuf},
+    },
+};
+
+/// An ESP-IDF project: a project directory built for a target against an ESP-IDF tree.
+///
+/// This sets up the variables the ESP-IDF Kconfig tree expects (`IDF_PATH`, `IDF_TARGET`, and the
+/// `COMPONENT_KCONFIGS_*` source lists for the project's components) and loads the tree from `$IDF_PATH/Kconfig`.
+///
+/// ```no_run
+/// # use {modular_esp_idf_kconfig_lib::{IdfProject, Target}, std::env};
+/// let project = IdfProject::new("my-project", Target::ESP32C3).with_vars(env::vars());
+/// let context = project.context().unwrap();
+/// let kconfig = project.load(&context).unwrap();
+/// ```
+#[derive(Clone, Debug)]
+pub struct IdfProject {
+    project_dir: PathBuf,
+    target: Target,
+    idf_path: Option<PathBuf>,
+    extra_component_dirs: Vec<PathBuf>,
+    vars: HashMap<String, String>,
+}
+
+impl IdfProject {
+    /// Create a project for the given project directory and target.
+    pub fn new(project_dir: impl Into<PathBuf>, target: Target) -> Self {
+        Self {
+            project_dir: project_dir.into(),
+            target,
+            idf_path: None,
+            extra_component_dirs: Vec::new(),
+            vars: HashMap::new(),
+        }
+    }
+
+    /// Set the path to the ESP-IDF tree. Defaults to the `IDF_PATH` variable.
+    pub fn with_idf_path(mut self, idf_path: impl Into<PathBuf>) -> Self {
+        self.idf_path = Some(idf_path.into());
+        self
+    }
+
+    /// Add an extra component directory, as in `EXTRA_COMPONENT_DIRS`.
+    pub fn with_extra_component_dir(mut self, dir: impl Into<PathBuf>) -> Self {
+        self.extra_component_dirs.push(dir.into());
+        self
+    }
+
+    /// Add variables to the context, such as the process environment. Later values replace earlier ones, but the
+    /// variables set by the project itself take precedence.
+    pub fn with_vars(mut self, vars: impl IntoIterator<Item = (String, String)>) -> Self {
+        self.vars.extend(vars);
+        self
+    }
+
+    /// Returns the project directory.
+    #[inline(always)]
+    pub fn project_dir(&self) -> &Path {
+        &self.project_dir
+    }
+
+    /// Returns the target the project is configured for.
+    #[inline(always)]
+    pub fn target(&self) -> Target {
+        self.target
+    }
+
+    /// Returns the absolute path to the ESP-IDF tree: the path given to [`with_idf_path()`][Self::with_idf_path], or
+    /// the `IDF_PATH` variable.
+    pub fn idf_path(&self) -> Result<PathBuf, KConfigError> {
+        let idf_path = match (&self.idf_path, self.vars.get("IDF_PATH")) {
+            (Some(idf_path), _) => idf_path.clone(),
+            (None, Some(idf_path)) if !idf_path.is_empty() => PathBuf::from(idf_path),
+            _ => return Err(KConfigError::from_kind(KConfigErrorKind::UnknownEnv("IDF_PATH".to_string()))),
+        };
+
+        // The generated source lists hold paths relative to the current directory, but they are read relative to
+        // $IDF_PATH, so make sure they are absolute.
+        Ok(path::absolute(idf_path)?)
+    }
+
+    /// Find the components of the project, including its `main` component.
+    pub fn components(&self) -> Result<Components, KConfigError> {
+        let project_dir = path::absolute(&self.project_dir)?;
+        let mut discovery = ComponentDiscovery::new(self.idf_path()?).with_project_dir(project_dir);
+        for dir in &self.extra_component_dirs {
+            discovery = discovery.with_extra_component_dir(path::absolute(dir)?);
+        }
+
+        discovery.discover()
+    }
+
+    /// Returns the variables for loading and evaluating the project's Kconfig tree: the variables given to
+    /// [`with_vars()`][Self::with_vars] along with `IDF_PATH`, `IDF_TARGET`, `COMPONENT_KCONFIGS_SOURCE_FILE`, and
+    /// `COMPONENT_KCONFIGS_PROJBUILD_SOURCE_FILE`.
+    pub fn context(&self) -> Result<HashMap<String, String>, KConfigError> {
+        let idf_path = self.idf_path()?;
+        let mut context = self.vars.clone();
+        context.insert("IDF_PATH".to_string(), idf_path.to_string_lossy().into_owned());
+        context.insert("IDF_TARGET".to_string(), self.target.config_name().to_string());
+        self.components()?.insert_context(&mut context);
+        Ok(context)
+    }
+
+    /// Load the Kconfig tree from `$IDF_PATH/Kconfig` using `context`, usually the result of
+    /// [`context()`][Self::context] or a context wrapping it.
+    pub fn load<C>(&self, context: &C) -> Result<KConfig, KConfigError>
+    where
+        C: Context,
+    {
+        let idf_path = self.idf_path()?;
+        KConfig::from_file(&idf_path.join("Kconfig"), &idf_path, context)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        crate::{
+            eval::{Evaluation, SymbolTable},
+            IdfProject, Target,
+        },
+        std::{env, fs, path::Path},
+    };
+
+    #[test_log::test]
+    fn idf_project() {
+        let idf_path = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests").join("esp-idf");
+        let project_dir = env::temp_dir().join(format!("kconfig-project-{}", std::process::id()));
+        let main = project_dir.join("main");
+        fs::create_dir_all(&main).unwrap();
+        fs::write(main.join("CMakeLists.txt"), "idf_component_register(SRCS \"main.c\")\n").unwrap();
+        fs::write(
+            main.join("Kconfig.projbuild"),
+            "menu \"Example\"\n    config EXAMPLE_GREETING\n        string \"Greeting\"\n        default \"hello\"\nendmenu\n",
+        )
+        .unwrap();
+
+        assert!(IdfProject::new(&project_dir, Target::ESP32C3).context().is_err());
+
+        let project = IdfProject::new(&project_dir, Target::ESP32C3)
+            .with_vars([("IDF_PATH".to_string(), idf_path.to_string_lossy().into_owned())]);
+        assert_eq!(project.idf_path().unwrap(), idf_path);
+        assert!(project.components().unwrap().get("main").is_some());
+
+        let context = project.context().unwrap();
+        assert_eq!(context["IDF_TARGET"], "esp32c3");
+        assert!(context["COMPONENT_KCONFIGS_PROJBUILD_SOURCE_FILE"].contains("Kconfig.projbuild"));
+
+        let kconfig = project.load(&context).unwrap();
+        let mut eval = Evaluation::new(SymbolTable::new(&kconfig));
+        eval.evaluate(&context).unwrap();
+        assert_eq!(eval.value("IDF_TARGET").unwrap().to_string(), "esp32c3");
+        assert_eq!(eval.value("EXAMPLE_GREETING").unwrap().to_string(), "hello");
+
+        fs::remove_dir_all(&project_dir).unwrap();
+    }
+}
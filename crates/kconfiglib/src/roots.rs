@@ -0,0 +1,180 @@
+use {
+    crate::{
+        parser::{Block, KConfig, KConfigError, Located, Location},
+        Context,
+    },
+    log::warn,
+    std::{
+        cell::RefCell,
+        collections::{btree_map::Entry, BTreeMap},
+        fmt::{Display, Formatter, Result as FmtResult},
+        path::PathBuf,
+        rc::Rc,
+    },
+};
+
+/// One of several Kconfig trees loaded together by [`KConfig::from_roots()`], such as the ESP-IDF tree, a board
+/// support package, or a project repository.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct KConfigRoot {
+    /// A name for the root, used in collision reports.
+    pub name: String,
+
+    /// The top-level Kconfig file of the root.
+    pub kconfig: PathBuf,
+
+    /// The directory that non-relative `source` statements in this root are relative to.
+    pub base_dir: PathBuf,
+}
+
+/// A symbol defined in more than one root.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct SymbolCollision {
+    /// The name of the symbol.
+    pub name: String,
+
+    /// The name of the first root defining the symbol.
+    pub first_root: String,
+
+    /// The first definition of the symbol in the first root.
+    pub first: Location,
+
+    /// The name of the later root that defines the symbol again.
+    pub second_root: String,
+
+    /// The first definition of the symbol in the later root.
+    pub second: Location,
+}
+
+impl KConfigRoot {
+    /// Create a root from its name, top-level Kconfig file, and base directory.
+    pub fn new(name: impl Into<String>, kconfig: impl Into<PathBuf>, base_dir: impl Into<PathBuf>) -> Self {
+        Self {
+            name: name.into(),
+            kconfig: kconfig.into(),
+            base_dir: base_dir.into(),
+        }
+    }
+}
+
+impl KConfig {
+    /// Read several Kconfig trees, each with its own base directory, and combine them in order into one tree.
+    ///
+    /// A symbol defined in more than one root is merged as if the definitions were in a single tree, which is usually
+    /// a mistake (two components choosing the same name) rather than intended. Each such symbol is returned as a
+    /// [`SymbolCollision`] for every root after the first one that defines it.
+    pub fn from_roots<C>(roots: &[KConfigRoot], context: &C) -> Result<(Self, Vec<SymbolCollision>), KConfigError>
+    where
+        C: Context,
+    {
+        let mut result = KConfig::default();
+        let mut defined: BTreeMap<String, (usize, Location)> = BTreeMap::new();
+        let mut collisions = Vec::new();
+
+        for (index, root) in roots.iter().enumerate() {
+            let kconfig = KConfig::from_file(&root.kconfig, &root.base_dir, context)?;
+
+            let mut symbols = BTreeMap::new();
+            collect_symbols(&kconfig.blocks, &mut symbols);
+
+            for (name, location) in symbols {
+                match defined.entry(name) {
+                    Entry::Vacant(entry) => {
+                        entry.insert((index, location));
+                    }
+                    Entry::Occupied(entry) => {
+                        let (first_index, first) = *entry.get();
+                        let collision = SymbolCollision {
+                            name: entry.key().clone(),
+                            first_root: roots[first_index].name.clone(),
+                            first,
+                            second_root: root.name.clone(),
+                            second: location,
+                        };
+                        warn!("{collision}");
+                        collisions.push(collision);
+                    }
+                }
+            }
+
+            result.blocks.extend(kconfig.blocks);
+        }
+
+        Ok((result, collisions))
+    }
+}
+
+impl Display for SymbolCollision {
+    fn fmt(&self, f: &mut Formatter) -> FmtResult {
+        write!(
+            f,
+            "Symbol {} is defined in {} at {} and in {} at {}",
+            self.name, self.first_root, self.first, self.second_root, self.second
+        )
+    }
+}
+
+/// Record the first definition of each symbol in a tree.
+fn collect_symbols(blocks: &[Rc<RefCell<Block>>], symbols: &mut BTreeMap<String, Location>) {
+    for block in blocks {
+        match &*block.borrow() {
+            Block::Config(config) | Block::MenuConfig(config) => {
+                symbols.entry(config.name.to_string()).or_insert_with(|| config.name.location());
+            }
+            Block::Choice(choice) => {
+                for config in &choice.configs {
+                    symbols.entry(config.name.to_string()).or_insert_with(|| config.name.location());
+                }
+            }
+            Block::If(if_block) => collect_symbols(&if_block.items, symbols),
+            Block::Menu(menu) => collect_symbols(&menu.blocks, symbols),
+            Block::Mainmenu(_) | Block::Source(_) => (),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        crate::{
+            eval::{Evaluation, SymbolTable},
+            parser::KConfig,
+            FsContext, KConfigRoot, MemoryFs,
+        },
+        std::collections::HashMap,
+    };
+
+    #[test_log::test]
+    fn from_roots() {
+        let fs = MemoryFs::new()
+            .with_file("/idf/Kconfig", "source \"components/Kconfig\"\n")
+            .with_file("/idf/components/Kconfig", "config IDF_FOO\n    bool \"Foo\"\n    default y\n")
+            .with_file("/bsp/Kconfig", "source \"boards/Kconfig\"\n")
+            .with_file(
+                "/bsp/boards/Kconfig",
+                "menu \"Board\"\n    config BOARD_LED\n        int \"LED\"\n        default 5\n\n    config IDF_FOO\n        \
+                 bool\nendmenu\n",
+            );
+
+        let context = HashMap::<String, String>::new();
+        let context = FsContext::new(&context, &fs);
+        let roots =
+            [KConfigRoot::new("esp-idf", "/idf/Kconfig", "/idf"), KConfigRoot::new("bsp", "/bsp/Kconfig", "/bsp")];
+
+        let (kconfig, collisions) = KConfig::from_roots(&roots, &context).unwrap();
+        assert_eq!(collisions.len(), 1);
+        assert_eq!(
+            collisions[0].to_string(),
+            "Symbol IDF_FOO is defined in esp-idf at /idf/components/Kconfig 1:8 and in bsp at /bsp/boards/Kconfig 6:12"
+        );
+
+        let mut eval = Evaluation::new(SymbolTable::new(&kconfig));
+        eval.evaluate(&context).unwrap();
+        assert_eq!(eval.value("BOARD_LED").unwrap().to_string(), "5");
+        assert_eq!(eval.value("IDF_FOO").unwrap().to_string(), "y");
+
+        // `source` statements are relative to the base directory of their own root.
+        let roots = [KConfigRoot::new("bsp", "/bsp/Kconfig", "/idf")];
+        assert!(KConfig::from_roots(&roots, &context).is_err());
+    }
+}
@@ -0,0 +1,10 @@
+//! Reading and writing `sdkconfig` files and the configuration outputs generated from them.
+
+mod reader;
+mod rename;
+mod writer;
+
+pub use {reader::*, rename::*, writer::*};
+
+/// The prefix for symbol names in `sdkconfig` files and generated outputs.
+pub const CONFIG_PREFIX: &str = "CONFIG_";
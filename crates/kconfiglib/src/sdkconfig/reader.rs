@@ -0,0 +1,185 @@
+use {
+    crate::{
+        parser::{KConfigError, Located, Location},
+        sdkconfig::CONFIG_PREFIX,
+    },
+    log::warn,
+    std::{fs, path::Path},
+};
+
+/// The contents of an `sdkconfig` or `sdkconfig.defaults` file.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct SdkConfig {
+    /// The assignments in the file, in the order they appear.
+    pub entries: Vec<SdkConfigEntry>,
+}
+
+/// An assignment in an `sdkconfig` file: `CONFIG_NAME=value` or `# CONFIG_NAME is not set`.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct SdkConfigEntry {
+    /// The name of the symbol, without the `CONFIG_` prefix.
+    pub name: String,
+
+    /// The value as written, with quotes and escapes removed from strings. `# CONFIG_NAME is not set` has the value
+    /// `n`.
+    pub value: String,
+
+    /// Whether the value was written as a quoted string.
+    pub quoted: bool,
+
+    /// The location of the assignment.
+    pub location: Location,
+}
+
+impl SdkConfig {
+    /// Read an `sdkconfig` file.
+    pub fn from_file(filename: &Path) -> Result<Self, KConfigError> {
+        let input = fs::read_to_string(filename)?;
+        Self::parse(&input, filename)
+    }
+
+    /// Parse the contents of an `sdkconfig` file.
+    ///
+    /// Comments and blank lines are skipped. Lines that are not assignments are ignored with a warning, as kconfig
+    /// does; an unterminated string is an error.
+    pub fn parse(input: &str, filename: &Path) -> Result<Self, KConfigError> {
+        let mut entries = Vec::new();
+
+        for (line_no, line) in input.lines().enumerate() {
+            let location = Location::new(filename, line_no + 1, 0);
+            let line = line.trim();
+
+            if let Some(comment) = line.strip_prefix('#') {
+                let unset =
+                    comment.trim().strip_prefix(CONFIG_PREFIX).and_then(|rest| rest.strip_suffix(" is not set"));
+
+                if let Some(name) = unset {
+                    entries.push(SdkConfigEntry {
+                        name: name.to_string(),
+                        value: "n".to_string(),
+                        quoted: false,
+                        location,
+                    });
+                }
+
+                continue;
+            }
+
+            if line.is_empty() {
+                continue;
+            }
+
+            let Some((name, raw_value)) = line.strip_prefix(CONFIG_PREFIX).and_then(|rest| rest.split_once('=')) else {
+                warn!("{location}: Ignoring malformed line: {line:?}");
+                continue;
+            };
+
+            let (value, quoted) = if raw_value.starts_with('"') {
+                (unquote(raw_value, location)?, true)
+            } else {
+                (raw_value.to_string(), false)
+            };
+
+            entries.push(SdkConfigEntry {
+                name: name.to_string(),
+                value,
+                quoted,
+                location,
+            });
+        }
+
+        Ok(Self {
+            entries,
+        })
+    }
+
+    /// Returns the last assignment to the given symbol, if any.
+    pub fn get(&self, name: &str) -> Option<&SdkConfigEntry> {
+        self.entries.iter().rev().find(|entry| entry.name == name)
+    }
+}
+
+impl Located for SdkConfigEntry {
+    fn location(&self) -> Location {
+        self.location
+    }
+}
+
+/// Remove the quotes and backslash escapes from a quoted string value.
+fn unquote(raw: &str, location: Location) -> Result<String, KConfigError> {
+    let mut result = String::with_capacity(raw.len());
+    let mut chars = raw.chars().skip(1);
+
+    while let Some(c) = chars.next() {
+        match c {
+            '"' => {
+                let rest: String = chars.collect();
+                if !rest.trim().is_empty() {
+                    warn!("{location}: Ignoring trailing characters after string: {rest:?}");
+                }
+
+                return Ok(result);
+            }
+            '\\' => match chars.next() {
+                Some(escaped) => result.push(escaped),
+                None => break,
+            },
+            _ => result.push(c),
+        }
+    }
+
+    Err(KConfigError::syntax(format!("Unterminated string: {raw}"), location))
+}
+
+/// Quote a string value for an `sdkconfig` file or C header, escaping backslashes and quotes.
+pub(crate) fn quote(value: &str) -> String {
+    let mut result = String::with_capacity(value.len() + 2);
+    result.push('"');
+
+    for c in value.chars() {
+        if matches!(c, '"' | '\\') {
+            result.push('\\');
+        }
+        result.push(c);
+    }
+
+    result.push('"');
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use {crate::sdkconfig::SdkConfig, std::path::Path};
+
+    #[test_log::test]
+    fn sdkconfig_parse() {
+        let config = SdkConfig::parse(
+            r##"#
+# Automatically generated file. DO NOT EDIT.
+#
+CONFIG_FOO=y
+# CONFIG_BAR is not set
+CONFIG_NAME="say \"hi\" \\ bye"
+CONFIG_SIZE=0x100
+garbage
+CONFIG_FOO=n
+"##,
+            Path::new("sdkconfig"),
+        )
+        .unwrap();
+
+        let names: Vec<_> = config.entries.iter().map(|e| (e.name.as_str(), e.value.as_str(), e.quoted)).collect();
+        assert_eq!(
+            names,
+            vec![
+                ("FOO", "y", false),
+                ("BAR", "n", false),
+                ("NAME", r#"say "hi" \ bye"#, true),
+                ("SIZE", "0x100", false),
+                ("FOO", "n", false),
+            ]
+        );
+        assert_eq!(config.get("FOO").unwrap().location.line, 9);
+        assert!(SdkConfig::parse("CONFIG_NAME=\"oops\n", Path::new("sdkconfig")).is_err());
+    }
+}
@@ -0,0 +1,278 @@
+use {
+    crate::{
+        parser::{KConfigError, KConfigErrorKind, Location},
+        sdkconfig::{SdkConfig, CONFIG_PREFIX},
+        Target,
+    },
+    log::{debug, warn},
+    std::{collections::HashMap, fs, io::ErrorKind as IoErrorKind, path::Path},
+};
+
+/// The name of the file in a component directory listing renamed options.
+pub const SDKCONFIG_RENAME: &str = "sdkconfig.rename";
+
+/// A deprecated option name and its replacement, from an `sdkconfig.rename` file.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Rename {
+    /// The deprecated name, without the `CONFIG_` prefix.
+    pub old_name: String,
+
+    /// The replacement name, without the `CONFIG_` prefix.
+    pub new_name: String,
+
+    /// Whether the replacement is the inverse of the deprecated option (written as `!CONFIG_NEW_NAME`). This is only
+    /// meaningful for `bool` options.
+    pub inverted: bool,
+
+    /// The location of the mapping.
+    pub location: Location,
+}
+
+/// The renamed options from a set of `sdkconfig.rename` and `sdkconfig.rename.<target>` files.
+#[derive(Clone, Debug, Default)]
+pub struct Renames {
+    /// The renames in the order they were added.
+    renames: Vec<Rename>,
+
+    /// Index into `renames` by old name.
+    by_old_name: HashMap<String, usize>,
+}
+
+impl Renames {
+    /// Create an empty set of renames.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Read a single `sdkconfig.rename` file.
+    pub fn from_file(filename: &Path) -> Result<Self, KConfigError> {
+        let input = fs::read_to_string(filename)?;
+        Self::parse(&input, filename)
+    }
+
+    /// Parse the contents of an `sdkconfig.rename` file.
+    ///
+    /// Each non-comment line has the form `CONFIG_OLD_NAME CONFIG_NEW_NAME` or `CONFIG_OLD_NAME !CONFIG_NEW_NAME`.
+    pub fn parse(input: &str, filename: &Path) -> Result<Self, KConfigError> {
+        let mut result = Self::new();
+
+        for (line_no, line) in input.lines().enumerate() {
+            let location = Location::new(filename, line_no + 1, 0);
+            let line = line.split_once('#').map(|(before, _)| before).unwrap_or(line).trim();
+
+            if line.is_empty() {
+                continue;
+            }
+
+            let fields: Vec<&str> = line.split_whitespace().collect();
+            let [old_name, new_name] = fields[..] else {
+                return Err(KConfigError::syntax(
+                    format!("Expected CONFIG_OLD_NAME CONFIG_NEW_NAME, found {line:?}"),
+                    location,
+                ));
+            };
+
+            let (inverted, new_name) = match new_name.strip_prefix('!') {
+                Some(name) => (true, name),
+                None => (false, new_name),
+            };
+
+            let (Some(old_name), Some(new_name)) =
+                (old_name.strip_prefix(CONFIG_PREFIX), new_name.strip_prefix(CONFIG_PREFIX))
+            else {
+                return Err(KConfigError::syntax(
+                    format!("Option names must start with {CONFIG_PREFIX}: {line:?}"),
+                    location,
+                ));
+            };
+
+            result.insert(Rename {
+                old_name: old_name.to_string(),
+                new_name: new_name.to_string(),
+                inverted,
+                location,
+            })?;
+        }
+
+        Ok(result)
+    }
+
+    /// Read the `sdkconfig.rename` file from each component directory, along with `sdkconfig.rename.<target>` if a
+    /// target is given. Directories without these files are skipped.
+    pub fn discover<I, P>(component_dirs: I, target: Option<Target>) -> Result<Self, KConfigError>
+    where
+        I: IntoIterator<Item = P>,
+        P: AsRef<Path>,
+    {
+        let mut result = Self::new();
+
+        for dir in component_dirs {
+            let dir = dir.as_ref();
+            let mut filenames = vec![dir.join(SDKCONFIG_RENAME)];
+
+            if let Some(target) = target {
+                filenames.push(dir.join(format!("{SDKCONFIG_RENAME}.{}", target.config_name())));
+            }
+
+            for filename in filenames {
+                match Self::from_file(&filename) {
+                    Ok(renames) => {
+                        debug!("Read {} renames from {filename:?}", renames.len());
+                        result.extend(renames)?;
+                    }
+                    Err(e) if matches!(&e.kind, KConfigErrorKind::Io(io) if io.kind() == IoErrorKind::NotFound) => (),
+                    Err(e) => return Err(e),
+                }
+            }
+        }
+
+        Ok(result)
+    }
+
+    /// Add a rename. It is an error to rename an option to two different names.
+    pub fn insert(&mut self, rename: Rename) -> Result<(), KConfigError> {
+        if let Some(&existing) = self.by_old_name.get(&rename.old_name) {
+            let existing = &self.renames[existing];
+            if existing.new_name != rename.new_name || existing.inverted != rename.inverted {
+                return Err(KConfigError::syntax(
+                    format!(
+                        "{CONFIG_PREFIX}{} is already renamed to {CONFIG_PREFIX}{} at {}",
+                        rename.old_name, existing.new_name, existing.location
+                    ),
+                    rename.location,
+                ));
+            }
+
+            return Ok(());
+        }
+
+        self.by_old_name.insert(rename.old_name.clone(), self.renames.len());
+        self.renames.push(rename);
+        Ok(())
+    }
+
+    /// Add all renames from another set.
+    pub fn extend(&mut self, other: Renames) -> Result<(), KConfigError> {
+        for rename in other.renames {
+            self.insert(rename)?;
+        }
+
+        Ok(())
+    }
+
+    /// Returns the rename for a deprecated option name, if any.
+    pub fn get(&self, old_name: &str) -> Option<&Rename> {
+        self.by_old_name.get(old_name).map(|&i| &self.renames[i])
+    }
+
+    /// Returns the renames whose replacement is the given option.
+    pub fn by_new_name<'a>(&'a self, new_name: &'a str) -> impl Iterator<Item = &'a Rename> + 'a {
+        self.renames.iter().filter(move |r| r.new_name == new_name)
+    }
+
+    /// Returns all renames in the order they were added.
+    pub fn iter(&self) -> impl Iterator<Item = &Rename> {
+        self.renames.iter()
+    }
+
+    /// Returns the number of renames.
+    #[inline(always)]
+    pub fn len(&self) -> usize {
+        self.renames.len()
+    }
+
+    /// Indicates whether there are no renames.
+    #[inline(always)]
+    pub fn is_empty(&self) -> bool {
+        self.renames.is_empty()
+    }
+
+    /// Replace deprecated option names in an `sdkconfig` file with their new names, inverting the values of inverted
+    /// options. This returns the number of entries that were changed.
+    pub fn apply(&self, sdkconfig: &mut SdkConfig) -> usize {
+        let mut count = 0;
+
+        for entry in sdkconfig.entries.iter_mut() {
+            let Some(rename) = self.get(&entry.name) else {
+                continue;
+            };
+
+            debug!("{}: Replacing deprecated option {} with {}", entry.location, entry.name, rename.new_name);
+            entry.name = rename.new_name.clone();
+
+            if rename.inverted {
+                entry.value = match entry.value.as_str() {
+                    "y" => "n".to_string(),
+                    "n" => "y".to_string(),
+                    value => {
+                        warn!("{}: Cannot invert non-bool value {value:?} for {}", entry.location, rename.old_name);
+                        value.to_string()
+                    }
+                };
+            }
+
+            count += 1;
+        }
+
+        count
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        crate::{
+            sdkconfig::{Renames, SdkConfig},
+            Target,
+        },
+        std::{env, fs, path::Path},
+    };
+
+    #[test_log::test]
+    fn rename_parse_apply() {
+        let renames = Renames::parse(
+            r##"# sdkconfig replacement configurations for deprecated options formatted as
+# CONFIG_DEPRECATED_OPTION CONFIG_NEW_OPTION
+
+CONFIG_OLD_FOO          CONFIG_FOO
+CONFIG_OLD_NO_BAR       !CONFIG_BAR   # inverted
+CONFIG_OLD_SIZE         CONFIG_SIZE
+"##,
+            Path::new("sdkconfig.rename"),
+        )
+        .unwrap();
+
+        assert_eq!(renames.len(), 3);
+        assert!(renames.get("OLD_NO_BAR").unwrap().inverted);
+        assert_eq!(renames.by_new_name("FOO").next().unwrap().old_name, "OLD_FOO");
+
+        let mut sdkconfig = SdkConfig::parse(
+            "CONFIG_OLD_FOO=y\n# CONFIG_OLD_NO_BAR is not set\nCONFIG_OLD_SIZE=12\nCONFIG_OTHER=y\n",
+            Path::new("sdkconfig"),
+        )
+        .unwrap();
+
+        assert_eq!(renames.apply(&mut sdkconfig), 3);
+        let entries: Vec<_> = sdkconfig.entries.iter().map(|e| (e.name.as_str(), e.value.as_str())).collect();
+        assert_eq!(entries, vec![("FOO", "y"), ("BAR", "y"), ("SIZE", "12"), ("OTHER", "y")]);
+
+        assert!(Renames::parse("CONFIG_A CONFIG_B CONFIG_C\n", Path::new("bad")).is_err());
+        assert!(Renames::parse("CONFIG_A B\n", Path::new("bad")).is_err());
+        assert!(Renames::parse("CONFIG_A CONFIG_B\nCONFIG_A CONFIG_C\n", Path::new("bad")).is_err());
+    }
+
+    #[test_log::test]
+    fn rename_discover() {
+        let dir = env::temp_dir().join(format!("kconfig-rename-{}", std::process::id()));
+        let component = dir.join("component");
+        fs::create_dir_all(&component).unwrap();
+        fs::write(component.join("sdkconfig.rename"), "CONFIG_OLD_A CONFIG_A\n").unwrap();
+        fs::write(component.join("sdkconfig.rename.esp32s3"), "CONFIG_OLD_B CONFIG_B\n").unwrap();
+
+        let dirs = [component.clone(), dir.join("missing")];
+        assert_eq!(Renames::discover(&dirs, Some(Target::ESP32)).unwrap().len(), 1);
+        assert_eq!(Renames::discover(&dirs, Some(Target::ESP32S3)).unwrap().len(), 2);
+
+        _ = fs::remove_dir_all(&dir);
+    }
+}
@@ -0,0 +1,903 @@
+use {
+    crate::{
+        eval::{parse_int, tri_from_level, Evaluation, MenuItem, MenuNode, Value},
+        parser::{Expr, ExprCmpOp, LocExpr, LocString, Tristate, Type},
+        sdkconfig::{quote, Renames, CONFIG_PREFIX},
+    },
+    serde::Serialize,
+    serde_json::{ser::PrettyFormatter, Map as JsonMap, Serializer as JsonSerializer, Value as JsonValue},
+    std::{
+        collections::HashSet,
+        io::{Error as IoError, ErrorKind as IoErrorKind, Result as IoResult, Write},
+    },
+};
+
+/// Writes the configuration files generated from an [`Evaluation`].
+#[derive(Clone, Debug, Default)]
+pub struct SdkConfigWriter {
+    /// The ESP-IDF version recorded in the file headers, if known.
+    pub idf_version: Option<String>,
+
+    /// Renamed options. The deprecated names are written alongside the new ones for backward compatibility.
+    pub renames: Renames,
+}
+
+impl SdkConfigWriter {
+    /// Create a writer with no ESP-IDF version and no renames.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Write an `sdkconfig` file.
+    ///
+    /// Symbols are written in menu order, with a comment block at the start and end of each visible menu. Deprecated
+    /// names for written symbols follow in a separate section at the end.
+    pub fn write_config<W: Write>(&self, eval: &Evaluation, writer: &mut W) -> IoResult<()> {
+        writeln!(writer, "#")?;
+        writeln!(writer, "# Automatically generated file. DO NOT EDIT.")?;
+        writeln!(writer, "# Espressif IoT Development Framework (ESP-IDF) {}Project Configuration", self.version())?;
+        writeln!(writer, "#")?;
+
+        let mut visitor = ConfigVisitor {
+            eval,
+            written: vec![false; eval.table().symbols().len()],
+            after_end_comment: false,
+            order: Vec::new(),
+        };
+        visitor.visit(eval.table().menu(), writer)?;
+
+        let mut deprecated = Vec::new();
+        for id in visitor.order {
+            let symbol = &eval.table().symbols()[id];
+            for rename in self.renames.by_new_name(&symbol.name) {
+                let mut value = eval.symbol_state(id).value.clone();
+                if rename.inverted {
+                    if let Value::Tristate(t) = value {
+                        value = Value::Tristate(invert(t));
+                    }
+                }
+
+                deprecated.push(config_string(&rename.old_name, symbol.r#type, &value));
+            }
+        }
+
+        if !deprecated.is_empty() {
+            writeln!(writer)?;
+            writeln!(writer, "# Deprecated options for backward compatibility")?;
+            for line in deprecated {
+                writer.write_all(line.as_bytes())?;
+            }
+            writeln!(writer, "# End of deprecated options")?;
+        }
+
+        Ok(())
+    }
+
+    /// Write an `sdkconfig.defaults` file containing only the symbols with user values, in menu order.
+    ///
+    /// Pass the result of [`Evaluation::savedefconfig()`] to write the minimal defaults file for a configuration.
+    pub fn write_defaults<W: Write>(&self, eval: &Evaluation, writer: &mut W) -> IoResult<()> {
+        for id in written_symbols(eval) {
+            let symbol = &eval.table().symbols()[id];
+            if eval.user_value(&symbol.name).is_some() {
+                writer
+                    .write_all(config_string(&symbol.name, symbol.r#type, &eval.symbol_state(id).value).as_bytes())?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Write an `sdkconfig.h` C header.
+    ///
+    /// Written symbols are defined in menu order. Enabled `bool` symbols are defined as `1`; disabled ones are not
+    /// defined. Deprecated names of written symbols are defined in terms of the new names in a separate section at the
+    /// end.
+    pub fn write_header<W: Write>(&self, eval: &Evaluation, writer: &mut W) -> IoResult<()> {
+        writeln!(writer, "/*")?;
+        writeln!(writer, " * Automatically generated file. DO NOT EDIT.")?;
+        writeln!(writer, " * Espressif IoT Development Framework (ESP-IDF) {}Configuration Header", self.version())?;
+        writeln!(writer, " */")?;
+        writeln!(writer, "#pragma once")?;
+
+        for id in written_symbols(eval) {
+            let symbol = &eval.table().symbols()[id];
+            let value = &eval.symbol_state(id).value;
+            if let Some(text) = header_value(symbol.r#type, value) {
+                writeln!(writer, "#define {CONFIG_PREFIX}{}{text}", header_name(&symbol.name, value))?;
+            }
+        }
+
+        if !self.renames.is_empty() {
+            let mut renames: Vec<_> = self.renames.iter().collect();
+            renames.sort_by(|a, b| a.old_name.cmp(&b.old_name));
+
+            writeln!(writer)?;
+            writeln!(writer, "/* List of deprecated options */")?;
+
+            for rename in renames {
+                let Some(state) = eval.state(&rename.new_name) else {
+                    continue;
+                };
+
+                if rename.inverted {
+                    if state.write && state.value == Value::Tristate(Tristate::False) {
+                        writeln!(writer, "#define {CONFIG_PREFIX}{} 1", rename.old_name)?;
+                    }
+                } else if state.write && is_defined(&state.value) {
+                    writeln!(writer, "#define {CONFIG_PREFIX}{} {CONFIG_PREFIX}{}", rename.old_name, rename.new_name)?;
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Write an `sdkconfig.cmake` include file.
+    ///
+    /// Each written symbol becomes a `set(CONFIG_NAME "value")` line, with disabled `bool` symbols set to an empty
+    /// string. A `CONFIGS_LIST` variable lists every variable that was set, including deprecated names.
+    pub fn write_cmake<W: Write>(&self, eval: &Evaluation, writer: &mut W) -> IoResult<()> {
+        writeln!(writer, "#")?;
+        writeln!(writer, "# Automatically generated file. DO NOT EDIT.")?;
+        writeln!(
+            writer,
+            "# Espressif IoT Development Framework (ESP-IDF) {}Configuration cmake include file",
+            self.version()
+        )?;
+        writeln!(writer, "#")?;
+
+        let mut configs_list = Vec::new();
+        let mut deprecated = Vec::new();
+
+        for id in written_symbols(eval) {
+            let symbol = &eval.table().symbols()[id];
+            let value = &eval.symbol_state(id).value;
+            writeln!(writer, "set({CONFIG_PREFIX}{} {})", symbol.name, cmake_value(symbol.r#type, value))?;
+            configs_list.push(format!("{CONFIG_PREFIX}{}", symbol.name));
+
+            for rename in self.renames.by_new_name(&symbol.name) {
+                let value = match value {
+                    Value::Tristate(t) if rename.inverted => Value::Tristate(invert(*t)),
+                    value => value.clone(),
+                };
+
+                deprecated.push(format!(
+                    "set({CONFIG_PREFIX}{} {})\n",
+                    rename.old_name,
+                    cmake_value(symbol.r#type, &value)
+                ));
+                configs_list.push(format!("{CONFIG_PREFIX}{}", rename.old_name));
+            }
+        }
+
+        write!(writer, "set(CONFIGS_LIST {})", configs_list.join(";"))?;
+
+        if !deprecated.is_empty() {
+            writeln!(writer)?;
+            writeln!(writer, "# List of deprecated options for backward compatibility")?;
+            for line in deprecated {
+                writer.write_all(line.as_bytes())?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Write an `sdkconfig.json` file.
+    ///
+    /// This is a single object mapping symbol names (without the `CONFIG_` prefix) to typed values, sorted by name:
+    /// `bool` symbols are JSON booleans, `int` and `hex` symbols are numbers, and `string` symbols are strings.
+    pub fn write_json<W: Write>(&self, eval: &Evaluation, writer: &mut W) -> IoResult<()> {
+        let mut config = json_values(eval);
+        config.sort_keys();
+        write_json_value(&JsonValue::Object(config), writer)
+    }
+
+    /// Write a `kconfig_menus.json` file describing the menu tree for IDE and GUI configurators.
+    ///
+    /// Each menu, choice with a prompt, and symbol becomes an object with its type, title, dependencies, help text,
+    /// active range, and a unique id, nested under the menu or choice that contains it. Symbols without a prompt have a
+    /// `null` title. Entries following a `menuconfig` symbol that depend on it are nested under the symbol.
+    /// Dependencies are written in kconfiglib's expression syntax. Symbols are identified by name; menus and choices by
+    /// the lowercased titles of the entries enclosing them. As in ESP-IDF, two entries with the same id (such as a
+    /// symbol defined in two places) are an error.
+    pub fn write_menus<W: Write>(&self, eval: &Evaluation, writer: &mut W) -> IoResult<()> {
+        let mut visitor = MenusVisitor {
+            eval,
+            ids: HashSet::new(),
+            slugs: Vec::new(),
+        };
+        let items = visitor.visit(eval.table().menu())?;
+        write_json_value(&JsonValue::Array(items), writer)
+    }
+
+    /// Returns the version string for file headers, with a trailing space, or an empty string.
+    fn version(&self) -> String {
+        match &self.idf_version {
+            Some(version) => format!("{version} "),
+            None => String::new(),
+        }
+    }
+}
+
+/// State for walking the menu tree while writing an `sdkconfig` file.
+struct ConfigVisitor<'a> {
+    eval: &'a Evaluation,
+
+    /// Whether each symbol has been written already (symbols with multiple definitions are written once).
+    written: Vec<bool>,
+
+    /// Whether the last thing written was an `# end of` comment.
+    after_end_comment: bool,
+
+    /// The symbols that were written, in order.
+    order: Vec<usize>,
+}
+
+impl ConfigVisitor<'_> {
+    fn visit<W: Write>(&mut self, nodes: &[MenuNode], writer: &mut W) -> IoResult<()> {
+        for node in nodes {
+            match &node.item {
+                MenuItem::Symbol {
+                    symbol,
+                    ..
+                } => {
+                    if self.written[*symbol] {
+                        continue;
+                    }
+                    self.written[*symbol] = true;
+
+                    let state = self.eval.symbol_state(*symbol);
+                    let info = &self.eval.table().symbols()[*symbol];
+                    if !state.write || info.r#type == Type::Unknown {
+                        continue;
+                    }
+
+                    if self.after_end_comment {
+                        writeln!(writer)?;
+                        self.after_end_comment = false;
+                    }
+
+                    writer.write_all(config_string(&info.name, info.r#type, &state.value).as_bytes())?;
+                    self.order.push(*symbol);
+                }
+
+                MenuItem::Choice(_) => self.visit(&node.children, writer)?,
+
+                MenuItem::Menu {
+                    id,
+                    prompt,
+                    ..
+                } => {
+                    let state = self.eval.menu_state(*id);
+                    let visible = state.dependency != Tristate::False && state.visibility != Tristate::False;
+
+                    if visible {
+                        write!(writer, "\n#\n# {prompt}\n#\n")?;
+                        self.after_end_comment = false;
+                    }
+
+                    self.visit(&node.children, writer)?;
+
+                    if visible {
+                        writeln!(writer, "# end of {prompt}")?;
+                        self.after_end_comment = true;
+                    }
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// State for walking the menu tree while writing a `kconfig_menus.json` file.
+struct MenusVisitor<'a> {
+    eval: &'a Evaluation,
+
+    /// The ids written so far. Ids must be unique.
+    ids: HashSet<String>,
+
+    /// The title slugs of the enclosing menus and choices, outermost first.
+    slugs: Vec<String>,
+}
+
+impl MenusVisitor<'_> {
+    fn visit(&mut self, nodes: &[MenuNode]) -> IoResult<Vec<JsonValue>> {
+        let mut items = Vec::with_capacity(nodes.len());
+        let mut next = 0;
+
+        while let Some(node) = nodes.get(next) {
+            next += 1;
+            let depends_on = match &node.dependency {
+                Some(dep) => JsonValue::String(dep.expr.kconfiglib_str()),
+                None => JsonValue::Null,
+            };
+
+            let mut item = JsonMap::new();
+            item.insert("depends_on".to_string(), depends_on);
+
+            match &node.item {
+                MenuItem::Menu {
+                    prompt,
+                    ..
+                } => {
+                    item.insert("type".to_string(), "menu".into());
+                    item.insert("title".to_string(), prompt.as_str().into());
+                    item.insert("id".to_string(), self.slug_id(prompt).into());
+
+                    self.slugs.push(slug(prompt));
+                    item.insert("children".to_string(), self.visit(&node.children)?.into());
+                    self.slugs.pop();
+                }
+
+                MenuItem::Choice(choice) => {
+                    let choice = &self.eval.table().choices()[*choice].choice;
+                    let Some(prompt) = &choice.prompt else {
+                        continue;
+                    };
+
+                    let name = match choice.name.is_empty() {
+                        true => JsonValue::Null,
+                        false => choice.name.as_str().into(),
+                    };
+
+                    item.insert("type".to_string(), "choice".into());
+                    item.insert("title".to_string(), prompt.title.as_str().into());
+                    item.insert("name".to_string(), name);
+                    item.insert("help".to_string(), help_json(choice.help.as_ref()));
+                    item.insert("id".to_string(), self.slug_id(&prompt.title).into());
+
+                    self.slugs.push(slug(&prompt.title));
+                    item.insert("children".to_string(), self.visit(&node.children)?.into());
+                    self.slugs.pop();
+                }
+
+                MenuItem::Symbol {
+                    symbol,
+                    definition,
+                } => {
+                    let info = &self.eval.table().symbols()[*symbol];
+                    let def = &info.definitions[*definition];
+                    let title = def.config.prompt.as_ref().map(|prompt| prompt.title.as_str());
+                    let range = match self.eval.symbol_state(*symbol).range {
+                        Some((low, high)) => JsonValue::Array(vec![json_number(low), json_number(high)]),
+                        None => JsonValue::Null,
+                    };
+
+                    if def.menuconfig {
+                        item.insert("type".to_string(), "menu".into());
+                        item.insert("is_menuconfig".to_string(), true.into());
+                    } else {
+                        item.insert("type".to_string(), info.r#type.to_string().into());
+                    }
+
+                    item.insert("title".to_string(), title.into());
+                    item.insert("name".to_string(), info.name.as_str().into());
+                    item.insert("help".to_string(), help_json(def.config.help.as_ref()));
+                    item.insert("range".to_string(), range);
+                    item.insert("id".to_string(), info.name.as_str().into());
+
+                    // As in kconfiglib, the entries following a `menuconfig` symbol that depend on it are shown
+                    // within it.
+                    let mut children = Vec::new();
+                    if def.menuconfig {
+                        let end = next
+                            + nodes[next..]
+                                .iter()
+                                .take_while(|child| {
+                                    child.dependency.as_ref().is_some_and(|dep| depends_on_symbol(dep, &info.name))
+                                })
+                                .count();
+
+                        self.slugs.push(slug(title.unwrap_or(&info.name)));
+                        children = self.visit(&nodes[next..end])?;
+                        self.slugs.pop();
+                        next = end;
+                    }
+
+                    item.insert("children".to_string(), children.into());
+                }
+            }
+
+            let id = item["id"].as_str().unwrap_or_default().to_string();
+            if !self.ids.insert(id.clone()) {
+                return Err(IoError::new(
+                    IoErrorKind::InvalidData,
+                    format!("Config file contains two items with the same id: {id}"),
+                ));
+            }
+
+            item.sort_keys();
+            items.push(JsonValue::Object(item));
+        }
+
+        Ok(items)
+    }
+
+    /// Returns the id of a menu or choice with the given title: the slugs of its title and those of the enclosing
+    /// entries, joined with `-`.
+    fn slug_id(&self, title: &str) -> String {
+        let mut slugs = self.slugs.clone();
+        slugs.push(slug(title));
+        slugs.join("-")
+    }
+}
+
+/// Returns `true` if an expression can only be true when the named symbol is, i.e. it is the symbol itself, compares
+/// the symbol with `y` or `n` to the same effect, or is a conjunction containing such a term.
+fn depends_on_symbol(expr: &LocExpr, name: &str) -> bool {
+    match &expr.expr {
+        Expr::Symbol(symbol) => symbol.name == name,
+        Expr::And(lhs, rhs) => depends_on_symbol(lhs, name) || depends_on_symbol(rhs, name),
+        Expr::Cmp(op, lhs, rhs) => {
+            let constant = match (&lhs.expr, &rhs.expr) {
+                (Expr::Symbol(symbol), Expr::Symbol(constant)) if symbol.name == name => &constant.name,
+                (Expr::Symbol(constant), Expr::Symbol(symbol)) if symbol.name == name => &constant.name,
+                _ => return false,
+            };
+
+            matches!((op, constant.as_str()), (ExprCmpOp::Eq, "y") | (ExprCmpOp::Ne, "n"))
+        }
+        _ => false,
+    }
+}
+
+/// Returns the typed values of the symbols written to configuration files, in menu order.
+pub(crate) fn json_values(eval: &Evaluation) -> JsonMap<String, JsonValue> {
+    let mut config = JsonMap::new();
+
+    for id in written_symbols(eval) {
+        let symbol = &eval.table().symbols()[id];
+        config.insert(symbol.name.clone(), json_value(symbol.r#type, &eval.symbol_state(id).value));
+    }
+
+    config
+}
+
+/// Returns the symbols written to configuration files, in menu order. Symbols with multiple definitions appear once.
+fn written_symbols(eval: &Evaluation) -> Vec<usize> {
+    fn visit(eval: &Evaluation, nodes: &[MenuNode], seen: &mut [bool], result: &mut Vec<usize>) {
+        for node in nodes {
+            if let MenuItem::Symbol {
+                symbol,
+                ..
+            } = &node.item
+            {
+                let state = eval.symbol_state(*symbol);
+                if !seen[*symbol] && state.write && eval.table().symbols()[*symbol].r#type != Type::Unknown {
+                    result.push(*symbol);
+                }
+                seen[*symbol] = true;
+            }
+
+            visit(eval, &node.children, seen, result);
+        }
+    }
+
+    let mut seen = vec![false; eval.table().symbols().len()];
+    let mut result = Vec::new();
+    visit(eval, eval.table().menu(), &mut seen, &mut result);
+    result
+}
+
+/// Format a symbol assignment for an `sdkconfig` file.
+pub(crate) fn config_string(name: &str, r#type: Type, value: &Value) -> String {
+    match (r#type, value) {
+        (_, Value::Tristate(Tristate::False)) => format!("# {CONFIG_PREFIX}{name} is not set\n"),
+        (Type::String, Value::String(s)) => format!("{CONFIG_PREFIX}{name}={}\n", quote(s)),
+        (_, value) => format!("{CONFIG_PREFIX}{name}={value}\n"),
+    }
+}
+
+/// Returns the macro name suffix and value for a symbol in a C header, or `None` if the symbol is not defined.
+fn header_value(r#type: Type, value: &Value) -> Option<String> {
+    match (r#type, value) {
+        (_, Value::Tristate(Tristate::False)) => None,
+        (_, Value::Tristate(_)) => Some(" 1".to_string()),
+        (Type::String, Value::String(s)) => Some(format!(" {}", quote(s))),
+        (_, Value::String(s)) if s.is_empty() => None,
+        (Type::Hex, Value::String(s)) if !s.starts_with("0x") && !s.starts_with("0X") => Some(format!(" 0x{s}")),
+        (_, Value::String(s)) => Some(format!(" {s}")),
+    }
+}
+
+/// Returns the quoted value of a symbol in a CMake `set()` command. Disabled `bool` symbols are empty strings and
+/// `hex` values always have a `0x` prefix.
+fn cmake_value(r#type: Type, value: &Value) -> String {
+    match (r#type, value) {
+        (_, Value::Tristate(Tristate::False)) => quote(""),
+        (Type::Hex, Value::String(s)) => match parse_int(s, Type::Hex) {
+            Some(n) if n >= 0 => format!("\"{n:#x}\""),
+            _ => quote(s),
+        },
+        (_, value) => quote(&value.to_string()),
+    }
+}
+
+/// Returns the typed value of a symbol in an `sdkconfig.json` file. Numeric symbols without a valid value are `null`.
+fn json_value(r#type: Type, value: &Value) -> JsonValue {
+    match (r#type, value) {
+        (_, Value::Tristate(t)) => JsonValue::Bool(*t != Tristate::False),
+        (Type::Int | Type::Hex, Value::String(s)) => parse_int(s, r#type).map(json_number).unwrap_or(JsonValue::Null),
+        (_, Value::String(s)) => JsonValue::String(s.clone()),
+    }
+}
+
+/// Convert an integer to a JSON number, or `null` if it is out of range.
+pub(crate) fn json_number(n: i128) -> JsonValue {
+    i64::try_from(n).map(JsonValue::from).or_else(|_| u64::try_from(n).map(JsonValue::from)).unwrap_or(JsonValue::Null)
+}
+
+/// Convert help text to JSON, removing trailing whitespace. Missing help is `null`.
+fn help_json(help: Option<&LocString>) -> JsonValue {
+    match help {
+        Some(help) => help.trim_end().into(),
+        None => JsonValue::Null,
+    }
+}
+
+/// Convert a menu title to an id component: runs of characters other than letters, digits, and `_` are replaced with
+/// `-` and the result is lowercased.
+pub(crate) fn slug(title: &str) -> String {
+    let mut result = String::with_capacity(title.len());
+
+    for c in title.chars() {
+        if c.is_alphanumeric() || c == '_' {
+            result.extend(c.to_lowercase());
+        } else if !result.ends_with('-') {
+            result.push('-');
+        }
+    }
+
+    result
+}
+
+/// Write a JSON value indented by four spaces, as Python's `json.dump()` does.
+fn write_json_value<W: Write>(value: &JsonValue, writer: &mut W) -> IoResult<()> {
+    let mut serializer = JsonSerializer::with_formatter(writer, PrettyFormatter::with_indent(b"    "));
+    value.serialize(&mut serializer)?;
+    Ok(())
+}
+
+/// Returns the macro name for a symbol in a C header. `m` values of tristate symbols get a `_MODULE` suffix.
+fn header_name(name: &str, value: &Value) -> String {
+    match value {
+        Value::Tristate(Tristate::Maybe) => format!("{name}_MODULE"),
+        _ => name.to_string(),
+    }
+}
+
+/// Indicates whether a value results in a macro definition in a C header.
+fn is_defined(value: &Value) -> bool {
+    match value {
+        Value::Tristate(t) => *t != Tristate::False,
+        Value::String(s) => !s.is_empty(),
+    }
+}
+
+/// Invert a `bool` value.
+fn invert(value: Tristate) -> Tristate {
+    tri_from_level(2 - crate::eval::tri_level(value))
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        crate::{
+            eval::{Evaluation, SymbolTable},
+            parser::{KConfig, PeekableChars},
+            sdkconfig::{Renames, SdkConfig, SdkConfigWriter},
+        },
+        serde_json::json,
+        std::{collections::HashMap, path::Path},
+    };
+
+    const KCONFIG: &str = r##"
+config FOO
+    bool "Foo"
+    default y
+
+menu "Network"
+    config BAR
+        bool "Bar"
+
+    config NAME
+        string "Name"
+        default "a \"b\""
+
+    menu "Hidden"
+        visible if n
+
+        config SIZE
+            hex "Size"
+            default 0x20
+    endmenu
+endmenu
+
+config LAST
+    int "Last"
+    default 3
+
+config LIMIT
+    int
+    range 10 20
+"##;
+
+    fn evaluation(old_sdkconfig: &str, renames: &Renames) -> Evaluation {
+        let context = HashMap::<String, String>::new();
+        let kconfig =
+            KConfig::from_str(PeekableChars::new(KCONFIG, Path::new("test")), Path::new("/tmp"), &context).unwrap();
+        let mut sdkconfig = SdkConfig::parse(old_sdkconfig, Path::new("sdkconfig")).unwrap();
+        renames.apply(&mut sdkconfig);
+
+        let mut eval = Evaluation::new(SymbolTable::new(&kconfig));
+        eval.load_sdkconfig(&sdkconfig);
+        eval.evaluate(&context).unwrap();
+        eval
+    }
+
+    #[test_log::test]
+    fn write_config_and_header() {
+        let renames = Renames::parse(
+            "CONFIG_OLD_FOO CONFIG_FOO\n\
+             CONFIG_NO_BAR !CONFIG_BAR\n\
+             CONFIG_OLD_SIZE CONFIG_SIZE\n\
+             CONFIG_OLD_LIMIT CONFIG_LIMIT\n",
+            Path::new("sdkconfig.rename"),
+        )
+        .unwrap();
+        let eval = evaluation("CONFIG_OLD_FOO=y\nCONFIG_NO_BAR=y\n", &renames);
+        assert_eq!(eval.value("BAR").unwrap().to_string(), "n");
+
+        let writer = SdkConfigWriter {
+            idf_version: Some("v5.1".to_string()),
+            renames,
+        };
+
+        let mut config = Vec::new();
+        writer.write_config(&eval, &mut config).unwrap();
+        assert_eq!(
+            String::from_utf8(config).unwrap(),
+            r##"#
+# Automatically generated file. DO NOT EDIT.
+# Espressif IoT Development Framework (ESP-IDF) v5.1 Project Configuration
+#
+CONFIG_FOO=y
+
+#
+# Network
+#
+# CONFIG_BAR is not set
+CONFIG_NAME="a \"b\""
+CONFIG_SIZE=0x20
+# end of Network
+
+CONFIG_LAST=3
+
+# Deprecated options for backward compatibility
+CONFIG_OLD_FOO=y
+CONFIG_NO_BAR=y
+CONFIG_OLD_SIZE=0x20
+# End of deprecated options
+"##
+        );
+
+        let mut header = Vec::new();
+        writer.write_header(&eval, &mut header).unwrap();
+        assert_eq!(
+            String::from_utf8(header).unwrap(),
+            r##"/*
+ * Automatically generated file. DO NOT EDIT.
+ * Espressif IoT Development Framework (ESP-IDF) v5.1 Configuration Header
+ */
+#pragma once
+#define CONFIG_FOO 1
+#define CONFIG_NAME "a \"b\""
+#define CONFIG_SIZE 0x20
+#define CONFIG_LAST 3
+
+/* List of deprecated options */
+#define CONFIG_NO_BAR 1
+#define CONFIG_OLD_FOO CONFIG_FOO
+#define CONFIG_OLD_SIZE CONFIG_SIZE
+"##
+        );
+    }
+
+    #[test_log::test]
+    fn write_cmake_and_json() {
+        let renames =
+            Renames::parse("CONFIG_NO_BAR !CONFIG_BAR\nCONFIG_OLD_LAST CONFIG_LAST\n", Path::new("sdkconfig.rename"))
+                .unwrap();
+        let eval = evaluation("CONFIG_SIZE=0X1F\n", &renames);
+        let writer = SdkConfigWriter {
+            idf_version: None,
+            renames,
+        };
+
+        let mut cmake = Vec::new();
+        writer.write_cmake(&eval, &mut cmake).unwrap();
+        assert_eq!(
+            String::from_utf8(cmake).unwrap(),
+            r##"#
+# Automatically generated file. DO NOT EDIT.
+# Espressif IoT Development Framework (ESP-IDF) Configuration cmake include file
+#
+set(CONFIG_FOO "y")
+set(CONFIG_BAR "")
+set(CONFIG_NAME "a \"b\"")
+set(CONFIG_SIZE "0x20")
+set(CONFIG_LAST "3")
+set(CONFIGS_LIST CONFIG_FOO;CONFIG_BAR;CONFIG_NO_BAR;CONFIG_NAME;CONFIG_SIZE;CONFIG_LAST;CONFIG_OLD_LAST)
+# List of deprecated options for backward compatibility
+set(CONFIG_NO_BAR "y")
+set(CONFIG_OLD_LAST "3")
+"##
+        );
+
+        let mut json = Vec::new();
+        writer.write_json(&eval, &mut json).unwrap();
+        assert_eq!(
+            String::from_utf8(json).unwrap(),
+            r##"{
+    "BAR": false,
+    "FOO": true,
+    "LAST": 3,
+    "NAME": "a \"b\"",
+    "SIZE": 32
+}"##
+        );
+    }
+
+    #[test_log::test]
+    fn write_menus() {
+        const MENUS_KCONFIG: &str = r##"
+menu "Serial (UART) Options"
+    depends on !DISABLED
+
+    config BAUD
+        int "Baud rate"
+        range 1200 115200 if !FAST
+        range 1200 921600
+        default 115200
+        help
+            The baud rate.
+
+    choice PARITY
+        prompt "Parity"
+
+        config PARITY_NONE
+            bool "None"
+        config PARITY_EVEN
+            bool "Even"
+    endchoice
+endmenu
+
+menuconfig DISABLED
+    bool "Disable serial"
+
+config DISABLE_LOGS
+    bool "Disable logs"
+    depends on DISABLED && TARGET = "esp32"
+
+config FAST
+    bool
+    default y
+"##;
+
+        let context = HashMap::<String, String>::new();
+        let kconfig =
+            KConfig::from_str(PeekableChars::new(MENUS_KCONFIG, Path::new("test")), Path::new("/tmp"), &context)
+                .unwrap();
+        let mut eval = Evaluation::new(SymbolTable::new(&kconfig));
+        eval.evaluate(&context).unwrap();
+
+        let mut menus = Vec::new();
+        SdkConfigWriter::new().write_menus(&eval, &mut menus).unwrap();
+        let menus: serde_json::Value = serde_json::from_slice(&menus).unwrap();
+
+        assert_eq!(
+            menus,
+            json!([
+                {
+                    "type": "menu",
+                    "title": "Serial (UART) Options",
+                    "id": "serial-uart-options",
+                    "depends_on": "!DISABLED",
+                    "children": [
+                        {
+                            "type": "int",
+                            "name": "BAUD",
+                            "title": "Baud rate",
+                            "id": "BAUD",
+                            "depends_on": "!DISABLED",
+                            "help": "The baud rate.",
+                            "range": [1200, 921600],
+                            "children": [],
+                        },
+                        {
+                            "type": "choice",
+                            "name": "PARITY",
+                            "title": "Parity",
+                            "id": "serial-uart-options-parity",
+                            "depends_on": "!DISABLED",
+                            "help": null,
+                            "children": [
+                                {
+                                    "type": "bool",
+                                    "name": "PARITY_NONE",
+                                    "title": "None",
+                                    "id": "PARITY_NONE",
+                                    "depends_on": "!DISABLED",
+                                    "help": null,
+                                    "range": null,
+                                    "children": [],
+                                },
+                                {
+                                    "type": "bool",
+                                    "name": "PARITY_EVEN",
+                                    "title": "Even",
+                                    "id": "PARITY_EVEN",
+                                    "depends_on": "!DISABLED",
+                                    "help": null,
+                                    "range": null,
+                                    "children": [],
+                                },
+                            ],
+                        },
+                    ],
+                },
+                {
+                    "type": "menu",
+                    "is_menuconfig": true,
+                    "name": "DISABLED",
+                    "title": "Disable serial",
+                    "id": "DISABLED",
+                    "depends_on": null,
+                    "help": null,
+                    "range": null,
+                    "children": [
+                        {
+                            "type": "bool",
+                            "name": "DISABLE_LOGS",
+                            "title": "Disable logs",
+                            "id": "DISABLE_LOGS",
+                            "depends_on": "DISABLED && TARGET = \"esp32\"",
+                            "help": null,
+                            "range": null,
+                            "children": [],
+                        },
+                    ],
+                },
+                {
+                    "type": "bool",
+                    "name": "FAST",
+                    "title": null,
+                    "id": "FAST",
+                    "depends_on": null,
+                    "help": null,
+                    "range": null,
+                    "children": [],
+                },
+            ])
+        );
+
+        let duplicate = "menu \"A\"\nendmenu\nmenu \"A\"\nendmenu\n";
+        let kconfig =
+            KConfig::from_str(PeekableChars::new(duplicate, Path::new("test")), Path::new("/tmp"), &context).unwrap();
+        let mut eval = Evaluation::new(SymbolTable::new(&kconfig));
+        eval.evaluate(&context).unwrap();
+        assert!(SdkConfigWriter::new().write_menus(&eval, &mut Vec::new()).is_err());
+
+        // A symbol with a prompt in two places gets the same id twice, which ESP-IDF rejects.
+        let duplicate = "config A\n    bool \"A\"\nconfig A\n    bool \"Also A\"\n";
+        let kconfig =
+            KConfig::from_str(PeekableChars::new(duplicate, Path::new("test")), Path::new("/tmp"), &context).unwrap();
+        let mut eval = Evaluation::new(SymbolTable::new(&kconfig));
+        eval.evaluate(&context).unwrap();
+        let e = SdkConfigWriter::new().write_menus(&eval, &mut Vec::new()).unwrap_err();
+        assert_eq!(e.to_string(), "Config file contains two items with the same id: A");
+    }
+}
@@ -0,0 +1,231 @@
+use {
+    crate::{
+        eval::{parse_int, Evaluation, SymbolTable, Value},
+        parser::{KConfig, KConfigError, Tristate, Type},
+        Target,
+    },
+    std::{
+        collections::{btree_map::Iter as BTreeMapIter, BTreeMap, HashMap},
+        fmt::{Display, Formatter, Result as FmtResult},
+        path::Path,
+    },
+};
+
+/// The prefix of SoC capability symbols.
+pub const SOC_PREFIX: &str = "SOC_";
+
+/// The capabilities of a target, read from `components/soc/<target>/include/soc/Kconfig.soc_caps.in` in an ESP-IDF
+/// tree.
+///
+/// Capabilities are keyed by their full symbol name (e.g. `SOC_BT_SUPPORTED`), but lookups also accept the name without
+/// the `SOC_` prefix.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct SocCaps {
+    target: Target,
+    caps: BTreeMap<String, SocCap>,
+}
+
+/// The value of a SoC capability.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum SocCap {
+    /// A `bool` capability, usually named `SOC_*_SUPPORTED`.
+    Bool(bool),
+
+    /// An `int` capability, such as `SOC_CPU_CORES_NUM`.
+    Int(i128),
+
+    /// A `hex` capability, such as a register address or mask.
+    Hex(i128),
+
+    /// A `string` capability. ESP-IDF uses these for capabilities whose value could not be determined when the file was
+    /// generated.
+    String(String),
+}
+
+impl Target {
+    /// Read the SoC capabilities of this target from the ESP-IDF tree at `idf_path`.
+    pub fn soc_caps(self, idf_path: &Path) -> Result<SocCaps, KConfigError> {
+        let filename = idf_path
+            .join("components")
+            .join("soc")
+            .join(self.config_name())
+            .join("include")
+            .join("soc")
+            .join("Kconfig.soc_caps.in");
+        SocCaps::from_file(self, &filename)
+    }
+}
+
+impl SocCaps {
+    /// Read the SoC capabilities of a target from a `Kconfig.soc_caps.in` file.
+    pub fn from_file(target: Target, filename: &Path) -> Result<Self, KConfigError> {
+        let context = target_context(target);
+        let base_dir = filename.parent().unwrap_or(Path::new("."));
+        let kconfig = KConfig::from_file(filename, base_dir, &context)?;
+        Self::from_kconfig(target, &kconfig)
+    }
+
+    /// Evaluate the SoC capabilities of a target from a parsed `Kconfig.soc_caps.in` file. Symbols without the `SOC_`
+    /// prefix are ignored.
+    pub fn from_kconfig(target: Target, kconfig: &KConfig) -> Result<Self, KConfigError> {
+        let context = target_context(target);
+        let mut eval = Evaluation::new(SymbolTable::new(kconfig));
+        eval.evaluate(&context)?;
+
+        let mut caps = BTreeMap::new();
+        for (id, symbol) in eval.table().symbols().iter().enumerate() {
+            if !symbol.name.starts_with(SOC_PREFIX) {
+                continue;
+            }
+
+            let cap = match (symbol.r#type, &eval.symbol_state(id).value) {
+                (Type::Bool | Type::Tristate, Value::Tristate(value)) => SocCap::Bool(*value != Tristate::False),
+                (Type::Int, Value::String(s)) => match parse_int(s, Type::Int) {
+                    Some(value) => SocCap::Int(value),
+                    None => continue,
+                },
+                (Type::Hex, Value::String(s)) => match parse_int(s, Type::Hex) {
+                    Some(value) => SocCap::Hex(value),
+                    None => continue,
+                },
+                (Type::String, Value::String(s)) => SocCap::String(s.clone()),
+                _ => continue,
+            };
+
+            caps.insert(symbol.name.clone(), cap);
+        }
+
+        Ok(Self {
+            target,
+            caps,
+        })
+    }
+
+    /// Returns the target these capabilities describe.
+    #[inline(always)]
+    pub fn target(&self) -> Target {
+        self.target
+    }
+
+    /// Returns the capability with the given name, with or without the `SOC_` prefix.
+    pub fn get(&self, name: &str) -> Option<&SocCap> {
+        match self.caps.get(name) {
+            Some(cap) => Some(cap),
+            None => self.caps.get(&format!("{SOC_PREFIX}{name}")),
+        }
+    }
+
+    /// Returns the value of a `bool` capability, or `None` if it is not defined or is not a `bool`.
+    pub fn bool(&self, name: &str) -> Option<bool> {
+        self.get(name).and_then(SocCap::as_bool)
+    }
+
+    /// Returns the value of an `int` or `hex` capability, or `None` if it is not defined or is not an integer.
+    pub fn int(&self, name: &str) -> Option<i128> {
+        self.get(name).and_then(SocCap::as_int)
+    }
+
+    /// Returns `true` if the `SOC_<feature>_SUPPORTED` capability is set, e.g. `supports("BT")` for
+    /// `SOC_BT_SUPPORTED`.
+    pub fn supports(&self, feature: &str) -> bool {
+        self.bool(&format!("{feature}_SUPPORTED")).unwrap_or(false)
+    }
+
+    /// Returns an iterator over the capabilities in name order.
+    pub fn iter(&self) -> BTreeMapIter<'_, String, SocCap> {
+        self.caps.iter()
+    }
+
+    /// Returns the number of capabilities.
+    #[inline(always)]
+    pub fn len(&self) -> usize {
+        self.caps.len()
+    }
+
+    /// Returns `true` if there are no capabilities.
+    #[inline(always)]
+    pub fn is_empty(&self) -> bool {
+        self.caps.is_empty()
+    }
+}
+
+impl<'a> IntoIterator for &'a SocCaps {
+    type Item = (&'a String, &'a SocCap);
+    type IntoIter = BTreeMapIter<'a, String, SocCap>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.caps.iter()
+    }
+}
+
+impl SocCap {
+    /// Returns the value of a `bool` capability.
+    pub fn as_bool(&self) -> Option<bool> {
+        match self {
+            Self::Bool(value) => Some(*value),
+            _ => None,
+        }
+    }
+
+    /// Returns the value of an `int` or `hex` capability.
+    pub fn as_int(&self) -> Option<i128> {
+        match self {
+            Self::Int(value) | Self::Hex(value) => Some(*value),
+            _ => None,
+        }
+    }
+
+    /// Returns the value of a `string` capability.
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            Self::String(value) => Some(value),
+            _ => None,
+        }
+    }
+}
+
+/// Returns the context for evaluating the capabilities of a target.
+fn target_context(target: Target) -> HashMap<String, String> {
+    [("IDF_TARGET".to_string(), target.config_name().to_string())].into_iter().collect()
+}
+
+/// Formats the capability as it appears in `sdkconfig`.
+impl Display for SocCap {
+    fn fmt(&self, f: &mut Formatter) -> FmtResult {
+        match self {
+            Self::Bool(true) => f.write_str("y"),
+            Self::Bool(false) => f.write_str("n"),
+            Self::Int(value) => write!(f, "{value}"),
+            Self::Hex(value) => write!(f, "{value:#x}"),
+            Self::String(value) => write!(f, "{value:?}"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        crate::{SocCap, Target},
+        std::path::Path,
+    };
+
+    #[test_log::test]
+    fn soc_caps() {
+        let idf_path = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests").join("esp-idf");
+
+        let esp32 = Target::ESP32.soc_caps(&idf_path).unwrap();
+        assert_eq!(esp32.target(), Target::ESP32);
+        assert!(esp32.supports("BT"));
+        assert_eq!(esp32.bool("SOC_BT_SUPPORTED"), Some(true));
+        assert_eq!(esp32.int("CPU_CORES_NUM"), Some(2));
+        assert_eq!(esp32.get("SOC_DPORT_WORKAROUND"), Some(&SocCap::String("Not determined".to_string())));
+        assert!(esp32.iter().all(|(name, _)| name.starts_with("SOC_")));
+
+        let esp32s2 = Target::ESP32S2.soc_caps(&idf_path).unwrap();
+        assert!(!esp32s2.supports("BT"));
+        assert_eq!(esp32s2.int("SOC_CPU_CORES_NUM"), Some(1));
+        assert!(esp32s2.len() != esp32.len());
+
+        assert!(Target::ESP32P4.soc_caps(&idf_path).is_err());
+    }
+}
@@ -1,93 +1,373 @@
-use std::fmt::{Display, Formatter, Result as FmtResult};
+use {
+    crate::parser::KConfigError,
+    log::debug,
+    once_cell::sync::Lazy,
+    std::{
+        collections::{hash_map::Entry, HashMap},
+        fmt::{Debug, Display, Formatter, Result as FmtResult},
+        fs,
+        path::Path,
+        str::FromStr,
+        sync::{Mutex, RwLock},
+    },
+};
 
-/// ESP32 targets.
-#[derive(Clone, Copy, Debug, Eq, PartialEq)]
-pub enum Target {
-    /// ESP32
-    Esp32,
+/// ESP-IDF targets.
+///
+/// The targets known when this crate was released are available as constants. Others can be added at runtime with
+/// [`Target::register()`], or found in an ESP-IDF tree with [`Target::discover()`], so a new chip does not need a new
+/// release of this crate.
+#[derive(Clone, Copy, Eq, Hash, PartialEq)]
+pub struct Target(&'static TargetInfo);
 
-    /// ESP32-C2
-    Esp32c2,
+/// The names of a target.
+#[derive(Eq, Hash, PartialEq)]
+struct TargetInfo {
+    /// The configuration name, e.g. `esp32c3`.
+    config_name: &'static str,
 
-    /// ESP32-C3
-    Esp32c3,
+    /// The upper-case name, e.g. `ESP32C3`.
+    name: &'static str,
 
-    /// ESP32-C6
-    Esp32c6,
-
-    /// ESP32-H2
-    Esp32h2,
+    /// The name of the SdkConfig struct, e.g. `SdkConfigEsp32c3`.
+    sdkconfig: &'static str,
+}
 
-    /// ESP32-S2
-    Esp32s2,
+/// A set of registered targets, in registration order.
+///
+/// The [`Target`] functions use a process-wide registry. A separate registry, e.g. for tests, starts with the same
+/// built-in targets and does not affect the process-wide one.
+pub struct TargetRegistry {
+    targets: RwLock<Vec<Target>>,
 
-    /// ESP32-S3
-    Esp32s3,
+    /// The targets as a slice for [`clap::ValueEnum`], leaked when first requested after a registration.
+    #[cfg(feature = "clap")]
+    variants: Mutex<Option<&'static [Target]>>,
 }
 
-impl Display for Target {
-    fn fmt(&self, f: &mut Formatter) -> FmtResult {
-        f.write_str(self.config_name())
-    }
+/// The process-wide registry.
+static REGISTRY: Lazy<TargetRegistry> = Lazy::new(TargetRegistry::new);
+
+/// The targets created by any registry, by configuration name, so each one's names are only allocated once.
+static TARGET_INFOS: Lazy<Mutex<HashMap<String, Target>>> = Lazy::new(Default::default);
+
+macro_rules! builtin_target {
+    ($(#[$meta:meta])* $ident:ident, $old:ident, $config_name:literal, $name:literal, $sdkconfig:literal) => {
+        builtin_target!($(#[$meta])* $ident, $config_name, $name, $sdkconfig);
+
+        #[doc = concat!("Deprecated name for [`Target::", stringify!($ident), "`].")]
+        #[allow(non_upper_case_globals)]
+        #[deprecated(note = "use the upper-case constant instead")]
+        pub const $old: Self = Self::$ident;
+    };
+    ($(#[$meta:meta])* $ident:ident, $config_name:literal, $name:literal, $sdkconfig:literal) => {
+        $(#[$meta])*
+        pub const $ident: Self = Self(&TargetInfo {
+            config_name: $config_name,
+            name: $name,
+            sdkconfig: $sdkconfig,
+        });
+    };
 }
 
 impl Target {
-    /// Return all targets as a slice.
-    pub fn all() -> &'static [Self] {
-        &[Self::Esp32, Self::Esp32c2, Self::Esp32c3, Self::Esp32c6, Self::Esp32h2, Self::Esp32s2, Self::Esp32s3]
+    builtin_target!(
+        /// ESP32
+        ESP32, Esp32, "esp32", "ESP32", "SdkConfigEsp32"
+    );
+    builtin_target!(
+        /// ESP32-C2
+        ESP32C2, Esp32c2, "esp32c2", "ESP32C2", "SdkConfigEsp32c2"
+    );
+    builtin_target!(
+        /// ESP32-C3
+        ESP32C3, Esp32c3, "esp32c3", "ESP32C3", "SdkConfigEsp32c3"
+    );
+    builtin_target!(
+        /// ESP32-C6
+        ESP32C6, Esp32c6, "esp32c6", "ESP32C6", "SdkConfigEsp32c6"
+    );
+    builtin_target!(
+        /// ESP32-H2
+        ESP32H2, Esp32h2, "esp32h2", "ESP32H2", "SdkConfigEsp32h2"
+    );
+    builtin_target!(
+        /// ESP32-S2
+        ESP32S2, Esp32s2, "esp32s2", "ESP32S2", "SdkConfigEsp32s2"
+    );
+    builtin_target!(
+        /// ESP32-S3
+        ESP32S3, Esp32s3, "esp32s3", "ESP32S3", "SdkConfigEsp32s3"
+    );
+    builtin_target!(
+        /// ESP32-C5
+        ESP32C5, "esp32c5", "ESP32C5", "SdkConfigEsp32c5"
+    );
+    builtin_target!(
+        /// ESP32-C61
+        ESP32C61, "esp32c61", "ESP32C61", "SdkConfigEsp32c61"
+    );
+    builtin_target!(
+        /// ESP32-P4
+        ESP32P4, "esp32p4", "ESP32P4", "SdkConfigEsp32p4"
+    );
+    builtin_target!(
+        /// The Linux host target (`IDF_TARGET_LINUX`).
+        LINUX, "linux", "LINUX", "SdkConfigLinux"
+    );
+
+    /// The targets registered by default.
+    const BUILTIN: &'static [Self] = &[
+        Self::ESP32,
+        Self::ESP32C2,
+        Self::ESP32C3,
+        Self::ESP32C6,
+        Self::ESP32H2,
+        Self::ESP32S2,
+        Self::ESP32S3,
+        Self::ESP32C5,
+        Self::ESP32C61,
+        Self::ESP32P4,
+        Self::LINUX,
+    ];
+
+    /// Return all targets in the process-wide registry.
+    pub fn all() -> Vec<Self> {
+        REGISTRY.all()
+    }
+
+    /// Return the target in the process-wide registry with the given configuration name or upper-case name, if any.
+    pub fn from_name(name: &str) -> Option<Self> {
+        REGISTRY.from_name(name)
+    }
+
+    /// Register a target in the process-wide registry. See [`TargetRegistry::register()`].
+    pub fn register(config_name: &str) -> Result<Self, KConfigError> {
+        REGISTRY.register(config_name)
+    }
+
+    /// Register the targets supported by an ESP-IDF tree in the process-wide registry. See
+    /// [`TargetRegistry::discover()`].
+    pub fn discover(idf_path: &Path) -> Result<Vec<Self>, KConfigError> {
+        REGISTRY.discover(idf_path)
     }
 
     /// Return the name of the SdkConfig struct for this target.
     pub fn sdkconfig(self) -> &'static str {
-        match self {
-            Self::Esp32 => "SdkConfigEsp32",
-            Self::Esp32c2 => "SdkConfigEsp32c2",
-            Self::Esp32c3 => "SdkConfigEsp32c3",
-            Self::Esp32c6 => "SdkConfigEsp32c6",
-            Self::Esp32h2 => "SdkConfigEsp32h2",
-            Self::Esp32s2 => "SdkConfigEsp32s2",
-            Self::Esp32s3 => "SdkConfigEsp32s3",
-        }
+        self.0.sdkconfig
     }
 
     /// Return the configuration name for this target as a str.
     pub fn config_name(self) -> &'static str {
-        match self {
-            Self::Esp32 => "esp32",
-            Self::Esp32c2 => "esp32c2",
-            Self::Esp32c3 => "esp32c3",
-            Self::Esp32c6 => "esp32c6",
-            Self::Esp32h2 => "esp32h2",
-            Self::Esp32s2 => "esp32s2",
-            Self::Esp32s3 => "esp32s3",
-        }
+        self.0.config_name
     }
 
     /// Return the name of the target as a str.
     pub fn name(self) -> &'static str {
-        match self {
-            Self::Esp32 => "ESP32",
-            Self::Esp32c2 => "ESP32C2",
-            Self::Esp32c3 => "ESP32C3",
-            Self::Esp32c6 => "ESP32C6",
-            Self::Esp32h2 => "ESP32H2",
-            Self::Esp32s2 => "ESP32S2",
-            Self::Esp32s3 => "ESP32S3",
+        self.0.name
+    }
+}
+
+impl TargetRegistry {
+    /// Create a registry containing the built-in targets.
+    pub fn new() -> Self {
+        Self {
+            targets: RwLock::new(Target::BUILTIN.to_vec()),
+            #[cfg(feature = "clap")]
+            variants: Mutex::new(None),
+        }
+    }
+
+    /// Return all registered targets.
+    pub fn all(&self) -> Vec<Target> {
+        self.targets.read().unwrap().clone()
+    }
+
+    /// Return the registered target with the given configuration name or upper-case name, if any.
+    pub fn from_name(&self, name: &str) -> Option<Target> {
+        self.targets
+            .read()
+            .unwrap()
+            .iter()
+            .find(|target| target.config_name() == name || target.name().eq_ignore_ascii_case(name))
+            .copied()
+    }
+
+    /// Register a target by its configuration name (e.g. `esp32c5`), returning the existing target if it is already
+    /// registered.
+    ///
+    /// The configuration name must be a lower-case letter followed by lower-case letters, digits, or underscores, as
+    /// used for `IDF_TARGET` and the `components/soc/<target>` directory. The names of a new target are allocated
+    /// once and live for the rest of the process.
+    pub fn register(&self, config_name: &str) -> Result<Target, KConfigError> {
+        let mut chars = config_name.chars();
+        if !chars.next().is_some_and(|c| c.is_ascii_lowercase())
+            || !chars.all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '_')
+        {
+            return Err(KConfigError::invalid_target(config_name));
         }
+
+        let mut targets = self.targets.write().unwrap();
+        if let Some(target) = targets.iter().find(|target| target.config_name() == config_name) {
+            return Ok(*target);
+        }
+
+        // Reuse the names of a target already known to another registry.
+        let target = match TARGET_INFOS.lock().unwrap().entry(config_name.to_string()) {
+            Entry::Occupied(entry) => *entry.get(),
+            Entry::Vacant(entry) => {
+                let sdkconfig = format!("SdkConfig{}{}", config_name[..1].to_ascii_uppercase(), &config_name[1..]);
+                *entry.insert(Target(Box::leak(Box::new(TargetInfo {
+                    config_name: config_name.to_string().leak(),
+                    name: config_name.to_ascii_uppercase().leak(),
+                    sdkconfig: sdkconfig.leak(),
+                }))))
+            }
+        };
+
+        debug!("Registered target {config_name}");
+        targets.push(target);
+
+        #[cfg(feature = "clap")]
+        {
+            *self.variants.lock().unwrap() = None;
+        }
+
+        Ok(target)
+    }
+
+    /// Register the targets supported by an ESP-IDF tree, returning them sorted by name.
+    ///
+    /// A target is supported if `components/soc/<target>/include/soc` under `idf_path` contains `Kconfig.soc_caps.in`
+    /// or `soc_caps.h`.
+    pub fn discover(&self, idf_path: &Path) -> Result<Vec<Target>, KConfigError> {
+        let mut result = Vec::new();
+
+        for entry in fs::read_dir(idf_path.join("components").join("soc"))? {
+            let entry = entry?;
+            let soc_dir = entry.path().join("include").join("soc");
+            if !soc_dir.join("Kconfig.soc_caps.in").is_file() && !soc_dir.join("soc_caps.h").is_file() {
+                continue;
+            }
+
+            if let Some(config_name) = entry.file_name().to_str() {
+                result.push(self.register(config_name)?);
+            }
+        }
+
+        result.sort_by_key(|target| target.config_name());
+        Ok(result)
+    }
+}
+
+impl Default for TargetRegistry {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Debug for Target {
+    fn fmt(&self, f: &mut Formatter) -> FmtResult {
+        f.debug_tuple("Target").field(&self.config_name()).finish()
+    }
+}
+
+impl Display for Target {
+    fn fmt(&self, f: &mut Formatter) -> FmtResult {
+        f.write_str(self.config_name())
+    }
+}
+
+/// Parses a registered target from its configuration name or upper-case name.
+impl FromStr for Target {
+    type Err = KConfigError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::from_name(s).ok_or_else(|| KConfigError::invalid_target(s))
     }
 }
 
 #[cfg(feature = "clap")]
 impl clap::ValueEnum for Target {
     fn value_variants<'a>() -> &'a [Self] {
-        Self::all()
+        // Clap needs a slice that outlives the registry's lock, so a copy is leaked once per change to the registry.
+        let mut variants = REGISTRY.variants.lock().unwrap();
+        variants.get_or_insert_with(|| REGISTRY.all().leak())
     }
 
     fn to_possible_value(&self) -> Option<clap::builder::PossibleValue> {
         Some(
             clap::builder::PossibleValue::new(self.config_name())
-            .alias(self.name())
-            .help(format!("Use {} as the target MCU", self.config_name()))
+                .alias(self.name())
+                .help(format!("Use {} as the target MCU", self.config_name())),
         )
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        crate::{Target, TargetRegistry},
+        std::{env, fs, path::Path},
+    };
+
+    #[test_log::test]
+    fn builtin_targets() {
+        assert_eq!(Target::ESP32C3.config_name(), "esp32c3");
+        assert_eq!(Target::ESP32C3.name(), "ESP32C3");
+        assert_eq!(Target::ESP32C3.sdkconfig(), "SdkConfigEsp32c3");
+        assert_eq!(Target::LINUX.name(), "LINUX");
+        assert_eq!("esp32s3".parse::<Target>().unwrap(), Target::ESP32S3);
+        assert_eq!("ESP32P4".parse::<Target>().unwrap(), Target::ESP32P4);
+        assert!(Target::all().starts_with(&[Target::ESP32, Target::ESP32C2]));
+        assert!("esp99".parse::<Target>().is_err());
+    }
+
+    #[test_log::test]
+    fn register_targets() {
+        let registry = TargetRegistry::new();
+        let target = registry.register("esp32x7").unwrap();
+        assert_eq!(target.name(), "ESP32X7");
+        assert_eq!(target.sdkconfig(), "SdkConfigEsp32x7");
+        assert_eq!(registry.register("esp32x7").unwrap(), target);
+        assert_eq!(registry.from_name("esp32x7"), Some(target));
+        assert!(registry.all().contains(&target));
+        assert_eq!(registry.register("esp32").unwrap(), Target::ESP32);
+        assert!(registry.register("ESP32X8").is_err());
+        assert!(registry.register("../esp32").is_err());
+
+        // The process-wide registry is not affected.
+        assert_eq!(Target::from_name("esp32x7"), None);
+        assert_eq!(TargetRegistry::new().from_name("esp32x7"), None);
+
+        let idf_path = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests").join("esp-idf");
+        let targets = registry.discover(&idf_path).unwrap();
+        let names: Vec<&str> = targets.iter().map(|target| target.config_name()).collect();
+        assert_eq!(names, vec!["esp32", "esp32c2", "esp32c3", "esp32c6", "esp32h2", "esp32s2", "esp32s3", "linux"]);
+
+        let dir = env::temp_dir().join(format!("kconfig-targets-{}", std::process::id()));
+        fs::create_dir_all(dir.join("components/soc/esp32x9/include/soc")).unwrap();
+        fs::create_dir_all(dir.join("components/soc/include/soc")).unwrap();
+        fs::write(dir.join("components/soc/esp32x9/include/soc/soc_caps.h"), "").unwrap();
+        let targets = registry.discover(&dir).unwrap();
+        let len = registry.all().len();
+        assert_eq!(registry.discover(&dir).unwrap(), targets);
+        assert_eq!(registry.all().len(), len);
+        fs::remove_dir_all(&dir).unwrap();
+        assert_eq!(targets.len(), 1);
+        assert_eq!(targets[0].name(), "ESP32X9");
+        assert_eq!(registry.from_name("esp32x9"), Some(targets[0]));
+    }
+
+    #[test_log::test]
+    #[allow(deprecated)]
+    fn deprecated_targets() {
+        assert_eq!(Target::Esp32c3, Target::ESP32C3);
+        let name = match Target::ESP32S3 {
+            Target::Esp32 => "esp32",
+            Target::Esp32s3 => "esp32s3",
+            _ => "other",
+        };
+        assert_eq!(name, "esp32s3");
+    }
+}
@@ -0,0 +1,635 @@
+use {
+    crate::{Context, Include},
+    std::{
+        collections::BTreeMap,
+        env::VarError,
+        fmt::{Debug, Formatter, Result as FmtResult},
+        fs,
+        io::{Error as IoError, ErrorKind as IoErrorKind, Result as IoResult},
+        path::{Component, Path, PathBuf},
+    },
+};
+
+#[cfg(any(feature = "tar", feature = "zip"))]
+use std::io::Read;
+
+/// A filesystem that Kconfig files are read from.
+///
+/// `source` statements are resolved through the filesystem returned by [`Context::fs()`], which is the local disk
+/// unless the context is wrapped in an [`FsContext`].
+pub trait KConfigFs {
+    /// Read the contents of a file. A missing file is reported with [`IoErrorKind::NotFound`].
+    fn read_to_string(&self, path: &Path) -> IoResult<String>;
+
+    /// Returns `true` if the path names a file.
+    fn is_file(&self, path: &Path) -> bool;
+
+    /// Returns `true` if the path names a directory.
+    fn is_dir(&self, path: &Path) -> bool;
+
+    /// Returns the paths of the entries in a directory, sorted by name.
+    fn read_dir(&self, path: &Path) -> IoResult<Vec<PathBuf>>;
+}
+
+/// The local disk.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct DiskFs;
+
+/// A filesystem held in memory, e.g. for hermetic tests or Kconfig trees bundled into a binary.
+///
+/// Paths are normalized lexically, so `a/../b/Kconfig` and `b/Kconfig` name the same file. Directories exist
+/// implicitly whenever they contain a file.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct MemoryFs {
+    files: BTreeMap<PathBuf, String>,
+}
+
+/// A stack of filesystems. Files are read from the first layer that has them, so earlier layers override later ones,
+/// and directory listings are merged across layers.
+#[derive(Default)]
+pub struct OverlayFs<'a> {
+    layers: Vec<Box<dyn KConfigFs + 'a>>,
+}
+
+/// A [context][Context] that reads Kconfig files from the given filesystem and looks up variables in another context.
+pub struct FsContext<'a, C: ?Sized> {
+    context: &'a C,
+    fs: &'a dyn KConfigFs,
+}
+
+impl KConfigFs for DiskFs {
+    fn read_to_string(&self, path: &Path) -> IoResult<String> {
+        fs::read_to_string(path)
+    }
+
+    fn is_file(&self, path: &Path) -> bool {
+        path.is_file()
+    }
+
+    fn is_dir(&self, path: &Path) -> bool {
+        path.is_dir()
+    }
+
+    fn read_dir(&self, path: &Path) -> IoResult<Vec<PathBuf>> {
+        let mut result =
+            fs::read_dir(path)?.map(|entry| entry.map(|entry| entry.path())).collect::<IoResult<Vec<_>>>()?;
+        result.sort();
+        Ok(result)
+    }
+}
+
+impl MemoryFs {
+    /// Create an empty filesystem.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a file, replacing any existing file at the same path.
+    pub fn insert(&mut self, path: impl AsRef<Path>, contents: impl Into<String>) {
+        self.files.insert(normalize(path.as_ref()), contents.into());
+    }
+
+    /// Add a file and return the filesystem, for building filesystems inline.
+    pub fn with_file(mut self, path: impl AsRef<Path>, contents: impl Into<String>) -> Self {
+        self.insert(path, contents);
+        self
+    }
+
+    /// Returns the paths of all files.
+    pub fn files(&self) -> impl Iterator<Item = &Path> {
+        self.files.keys().map(PathBuf::as_path)
+    }
+
+    /// Returns the number of files.
+    #[inline(always)]
+    pub fn len(&self) -> usize {
+        self.files.len()
+    }
+
+    /// Returns `true` if there are no files.
+    #[inline(always)]
+    pub fn is_empty(&self) -> bool {
+        self.files.is_empty()
+    }
+
+    /// Add the files in a tar archive under `root`. Entries that are not regular files are skipped, as are files that
+    /// are not UTF-8 and files whose paths are absolute or contain `..`, which could escape `root`.
+    #[cfg(feature = "tar")]
+    pub fn load_tar<R: Read>(&mut self, reader: R, root: &Path) -> IoResult<()> {
+        let mut archive = tar::Archive::new(reader);
+        for entry in archive.entries()? {
+            let mut entry = entry?;
+            if !entry.header().entry_type().is_file() {
+                continue;
+            }
+
+            let path = entry.path()?;
+            if !is_enclosed(&path) {
+                log::warn!("Skipping tar entry outside of the archive: {}", path.display());
+                continue;
+            }
+
+            let path = root.join(path);
+            let mut contents = String::new();
+            if entry.read_to_string(&mut contents).is_ok() {
+                self.insert(path, contents);
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Add the files in a zip archive under `root`. Directories are skipped, as are files that are not UTF-8.
+    #[cfg(feature = "zip")]
+    pub fn load_zip<R: Read + std::io::Seek>(&mut self, reader: R, root: &Path) -> IoResult<()> {
+        let mut archive = zip::ZipArchive::new(reader).map_err(IoError::other)?;
+        for i in 0..archive.len() {
+            let mut file = archive.by_index(i).map_err(IoError::other)?;
+            if !file.is_file() {
+                continue;
+            }
+
+            let Some(path) = file.enclosed_name() else {
+                continue;
+            };
+
+            let path = root.join(path);
+            let mut contents = String::new();
+            if file.read_to_string(&mut contents).is_ok() {
+                self.insert(path, contents);
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl KConfigFs for MemoryFs {
+    fn read_to_string(&self, path: &Path) -> IoResult<String> {
+        match self.files.get(&normalize(path)) {
+            Some(contents) => Ok(contents.clone()),
+            None => Err(IoError::new(IoErrorKind::NotFound, format!("{} not found", path.display()))),
+        }
+    }
+
+    fn is_file(&self, path: &Path) -> bool {
+        self.files.contains_key(&normalize(path))
+    }
+
+    fn is_dir(&self, path: &Path) -> bool {
+        let path = normalize(path);
+        self.files.keys().any(|file| file != &path && file.starts_with(&path))
+    }
+
+    fn read_dir(&self, path: &Path) -> IoResult<Vec<PathBuf>> {
+        let path = normalize(path);
+        let mut result = Vec::new();
+
+        for file in self.files.keys() {
+            let Ok(rest) = file.strip_prefix(&path) else {
+                continue;
+            };
+
+            if let Some(name) = rest.components().next() {
+                let entry = path.join(name);
+                if result.last() != Some(&entry) {
+                    result.push(entry);
+                }
+            }
+        }
+
+        if result.is_empty() {
+            return Err(IoError::new(IoErrorKind::NotFound, format!("{} not found", path.display())));
+        }
+
+        Ok(result)
+    }
+}
+
+impl<'a> OverlayFs<'a> {
+    /// Create an overlay with no layers.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a layer below the existing layers.
+    pub fn with_layer(mut self, layer: impl KConfigFs + 'a) -> Self {
+        self.layers.push(Box::new(layer));
+        self
+    }
+}
+
+impl Debug for OverlayFs<'_> {
+    fn fmt(&self, f: &mut Formatter) -> FmtResult {
+        f.debug_struct("OverlayFs").field("layers", &self.layers.len()).finish()
+    }
+}
+
+impl KConfigFs for OverlayFs<'_> {
+    fn read_to_string(&self, path: &Path) -> IoResult<String> {
+        for layer in &self.layers {
+            match layer.read_to_string(path) {
+                Err(e) if e.kind() == IoErrorKind::NotFound => continue,
+                result => return result,
+            }
+        }
+
+        Err(IoError::new(IoErrorKind::NotFound, format!("{} not found", path.display())))
+    }
+
+    fn is_file(&self, path: &Path) -> bool {
+        self.layers.iter().any(|layer| layer.is_file(path))
+    }
+
+    fn is_dir(&self, path: &Path) -> bool {
+        self.layers.iter().any(|layer| layer.is_dir(path))
+    }
+
+    fn read_dir(&self, path: &Path) -> IoResult<Vec<PathBuf>> {
+        let mut result = Vec::new();
+        let mut found = false;
+
+        for layer in &self.layers {
+            match layer.read_dir(path) {
+                Ok(entries) => {
+                    found = true;
+                    result.extend(entries);
+                }
+                Err(e) if e.kind() == IoErrorKind::NotFound => (),
+                Err(e) => return Err(e),
+            }
+        }
+
+        if !found {
+            return Err(IoError::new(IoErrorKind::NotFound, format!("{} not found", path.display())));
+        }
+
+        result.sort();
+        result.dedup();
+        Ok(result)
+    }
+}
+
+impl<T: KConfigFs + ?Sized> KConfigFs for &T {
+    fn read_to_string(&self, path: &Path) -> IoResult<String> {
+        (**self).read_to_string(path)
+    }
+
+    fn is_file(&self, path: &Path) -> bool {
+        (**self).is_file(path)
+    }
+
+    fn is_dir(&self, path: &Path) -> bool {
+        (**self).is_dir(path)
+    }
+
+    fn read_dir(&self, path: &Path) -> IoResult<Vec<PathBuf>> {
+        (**self).read_dir(path)
+    }
+}
+
+impl<'a, C: ?Sized> FsContext<'a, C> {
+    /// Create a context that reads files from `fs` and looks up variables in `context`.
+    pub fn new(context: &'a C, fs: &'a dyn KConfigFs) -> Self {
+        Self {
+            context,
+            fs,
+        }
+    }
+}
+
+impl<C: Context + ?Sized> Context for FsContext<'_, C> {
+    fn var(&self, name: &str) -> Result<String, VarError> {
+        self.context.var(name)
+    }
+
+    fn fs(&self) -> &dyn KConfigFs {
+        self.fs
+    }
+
+    fn record_include(&self, include: Include) {
+        self.context.record_include(include);
+    }
+}
+
+/// Returns `true` if the pattern contains glob wildcards (`*`, `?`, or `[`).
+pub(crate) fn is_glob(pattern: &str) -> bool {
+    pattern.contains(['*', '?', '['])
+}
+
+/// Returns the files matching a glob pattern, sorted by path.
+///
+/// Wildcards match within a single path component: `*` matches any run of characters, `?` matches one character, and
+/// `[...]` matches one character from a set such as `[abc]`, `[a-z]`, or `[!a-z]`. As with shell globs, names starting
+/// with `.` are only matched by patterns that start with `.`.
+///
+/// Every directory the pattern depends on is added to `searched`: the directories listed to match a wildcard, and the
+/// directories expected to hold the literal components after the last wildcard. A file appearing in any of them can
+/// change the result. Directories that don't exist are added too.
+pub(crate) fn glob(fs: &dyn KConfigFs, pattern: &Path, searched: &mut Vec<PathBuf>) -> IoResult<Vec<PathBuf>> {
+    let mut candidates = vec![PathBuf::new()];
+
+    for component in pattern.components() {
+        let Component::Normal(name) = component else {
+            candidates.iter_mut().for_each(|candidate| candidate.push(component));
+            continue;
+        };
+
+        let name = name.to_string_lossy();
+        if !is_glob(&name) {
+            candidates.iter_mut().for_each(|candidate| candidate.push(name.as_ref()));
+            continue;
+        }
+
+        let mut matches = Vec::new();
+        for candidate in &candidates {
+            let dir = if candidate.as_os_str().is_empty() {
+                Path::new(".")
+            } else {
+                candidate.as_path()
+            };
+
+            searched.push(dir.to_path_buf());
+            if !fs.is_dir(dir) {
+                continue;
+            }
+
+            for entry in fs.read_dir(dir)? {
+                let Some(entry_name) = entry.file_name().and_then(|entry_name| entry_name.to_str()) else {
+                    continue;
+                };
+
+                if (!entry_name.starts_with('.') || name.starts_with('.')) && fnmatch(&name, entry_name) {
+                    matches.push(candidate.join(entry_name));
+                }
+            }
+        }
+
+        candidates = matches;
+    }
+
+    let (mut result, missing): (Vec<PathBuf>, Vec<PathBuf>) =
+        candidates.into_iter().partition(|candidate| fs.is_file(candidate));
+    searched.extend(
+        missing.iter().filter_map(|candidate| candidate.parent()).filter(|dir| !fs.is_file(dir)).map(Path::to_path_buf),
+    );
+
+    searched.sort();
+    searched.dedup();
+    result.sort();
+    Ok(result)
+}
+
+/// Returns `true` if a name matches a single-component glob pattern.
+fn fnmatch(pattern: &str, name: &str) -> bool {
+    let pattern: Vec<char> = pattern.chars().collect();
+    let name: Vec<char> = name.chars().collect();
+    fnmatch_chars(&pattern, &name)
+}
+
+fn fnmatch_chars(pattern: &[char], name: &[char]) -> bool {
+    match pattern.first() {
+        None => name.is_empty(),
+        Some('*') => (0..=name.len()).any(|skip| fnmatch_chars(&pattern[1..], &name[skip..])),
+        Some('?') => !name.is_empty() && fnmatch_chars(&pattern[1..], &name[1..]),
+        Some('[') => match (name.first(), match_class(&pattern[1..], name.first().copied())) {
+            (Some(_), Some((true, len))) => fnmatch_chars(&pattern[len + 1..], &name[1..]),
+            (Some(_), Some((false, _))) => false,
+            // An unterminated `[` matches itself.
+            (Some('['), None) => fnmatch_chars(&pattern[1..], &name[1..]),
+            _ => false,
+        },
+        Some(c) => name.first() == Some(c) && fnmatch_chars(&pattern[1..], &name[1..]),
+    }
+}
+
+/// Match a character against the class following a `[`, returning whether it matched and the length of the class
+/// including the closing `]`, or `None` if the class is unterminated.
+fn match_class(class: &[char], c: Option<char>) -> Option<(bool, usize)> {
+    let negated = matches!(class.first(), Some('!' | '^'));
+    let mut i = usize::from(negated);
+    let mut matched = false;
+    let mut first = true;
+
+    while i < class.len() {
+        if class[i] == ']' && !first {
+            return Some((matched != negated, i + 1));
+        }
+
+        if i + 2 < class.len() && class[i + 1] == '-' && class[i + 2] != ']' {
+            matched |= c.is_some_and(|c| class[i] <= c && c <= class[i + 2]);
+            i += 3;
+        } else {
+            matched |= c == Some(class[i]);
+            i += 1;
+        }
+
+        first = false;
+    }
+
+    None
+}
+
+/// Normalize a path lexically, removing `.` components and resolving `..` components against earlier ones.
+/// Returns `true` if a relative path stays within the directory it is relative to: it has no root, prefix, or `..`
+/// components.
+#[cfg(feature = "tar")]
+fn is_enclosed(path: &Path) -> bool {
+    path.components().all(|component| matches!(component, Component::Normal(_) | Component::CurDir))
+}
+
+fn normalize(path: &Path) -> PathBuf {
+    let mut result = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::CurDir => (),
+            Component::ParentDir => {
+                if !result.pop() {
+                    result.push(component);
+                }
+            }
+            component => result.push(component),
+        }
+    }
+
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        crate::{
+            parser::{KConfig, KConfigErrorKind},
+            vfs::{glob, is_glob},
+            DiskFs, FsContext, KConfigFs, MemoryFs, OverlayFs,
+        },
+        std::{
+            collections::HashMap,
+            io::ErrorKind as IoErrorKind,
+            path::{Path, PathBuf},
+        },
+    };
+
+    fn config_names(kconfig: &KConfig) -> Vec<String> {
+        kconfig
+            .blocks
+            .iter()
+            .filter_map(|block| match &*block.borrow() {
+                crate::parser::Block::Config(config) => Some(config.name.to_string()),
+                _ => None,
+            })
+            .collect()
+    }
+
+    #[test_log::test]
+    fn memory_fs_sources() {
+        let fs = MemoryFs::new()
+            .with_file("/idf/Kconfig", "config TOP\n    bool\nsource \"$IDF_PATH/components/Kconfig\"\n")
+            .with_file("/idf/components/Kconfig", "config COMPONENT\n    bool\nrsource \"./sub/Kconfig\"\n")
+            .with_file("/idf/components/sub/Kconfig", "config SUB\n    bool\nosource \"missing/Kconfig\"\n")
+            .with_file("/idf/Kconfig.inline", "source \"$COMPONENT_KCONFIGS_SOURCE_FILE\"\n");
+
+        assert!(fs.is_dir(Path::new("/idf/components")));
+        assert!(fs.is_file(Path::new("/idf/components/../Kconfig")));
+        assert_eq!(
+            fs.read_dir(Path::new("/idf/components")).unwrap(),
+            vec![PathBuf::from("/idf/components/Kconfig"), PathBuf::from("/idf/components/sub")]
+        );
+
+        let context: HashMap<String, String> = [
+            ("IDF_PATH".to_string(), "/idf".to_string()),
+            ("COMPONENT_KCONFIGS_SOURCE_FILE".to_string(), "inline:source \"components/sub/Kconfig\"\n".to_string()),
+        ]
+        .into_iter()
+        .collect();
+        let context = FsContext::new(&context, &fs);
+        let kconfig = KConfig::from_file(Path::new("/idf/Kconfig"), Path::new("/idf"), &context).unwrap();
+        assert_eq!(config_names(&kconfig), vec!["TOP", "COMPONENT", "SUB"]);
+
+        // Inline sources are read through the same filesystem, so the files they source come from memory too.
+        let kconfig = KConfig::from_file(Path::new("/idf/Kconfig.inline"), Path::new("/idf"), &context).unwrap();
+        assert_eq!(config_names(&kconfig), vec!["SUB"]);
+
+        let e = KConfig::from_file(Path::new("/idf/missing"), Path::new("/idf"), &context).unwrap_err();
+        assert!(matches!(&e.kind, KConfigErrorKind::Io(io) if io.kind() == IoErrorKind::NotFound));
+    }
+
+    #[test_log::test]
+    fn glob_match() {
+        let fs = MemoryFs::new()
+            .with_file("/idf/components/b/Kconfig", "")
+            .with_file("/idf/components/a/Kconfig", "")
+            .with_file("/idf/components/a/Kconfig.projbuild", "")
+            .with_file("/idf/components/.hidden/Kconfig", "")
+            .with_file("/idf/components/c1/Kconfig", "");
+
+        let matches = |pattern: &str| -> Vec<String> {
+            glob(&fs, Path::new(pattern), &mut Vec::new())
+                .unwrap()
+                .iter()
+                .map(|path| path.display().to_string())
+                .collect()
+        };
+
+        assert_eq!(
+            matches("/idf/components/*/Kconfig"),
+            vec!["/idf/components/a/Kconfig", "/idf/components/b/Kconfig", "/idf/components/c1/Kconfig"]
+        );
+        assert_eq!(
+            matches("/idf/components/?/Kconfig*"),
+            vec!["/idf/components/a/Kconfig", "/idf/components/a/Kconfig.projbuild", "/idf/components/b/Kconfig"]
+        );
+        assert_eq!(
+            matches("/idf/components/[!a]*/Kconfig"),
+            vec!["/idf/components/b/Kconfig", "/idf/components/c1/Kconfig"]
+        );
+        assert_eq!(
+            matches("/idf/components/[a-b]/Kconfig"),
+            vec!["/idf/components/a/Kconfig", "/idf/components/b/Kconfig"]
+        );
+        assert_eq!(matches("/idf/components/.*/Kconfig"), vec!["/idf/components/.hidden/Kconfig"]);
+        assert!(matches("/idf/missing/*/Kconfig").is_empty());
+
+        let mut searched = Vec::new();
+        glob(&fs, Path::new("/idf/components/*/Kconfig.projbuild"), &mut searched).unwrap();
+        assert_eq!(
+            searched,
+            vec![
+                PathBuf::from("/idf/components"),
+                PathBuf::from("/idf/components/b"),
+                PathBuf::from("/idf/components/c1")
+            ]
+        );
+
+        let mut searched = Vec::new();
+        glob(&fs, Path::new("/idf/missing/*/Kconfig"), &mut searched).unwrap();
+        assert_eq!(searched, vec![PathBuf::from("/idf/missing")]);
+        assert!(is_glob("a/*/Kconfig"));
+        assert!(!is_glob("a/b/Kconfig"));
+    }
+
+    #[test_log::test]
+    fn overlay_fs() {
+        let upper = MemoryFs::new().with_file("/idf/Kconfig", "config PATCHED\n    bool\n");
+        let lower = MemoryFs::new()
+            .with_file("/idf/Kconfig", "config ORIGINAL\n    bool\n")
+            .with_file("/idf/other/Kconfig", "config OTHER\n    bool\n");
+        let fs = OverlayFs::new().with_layer(&upper).with_layer(&lower).with_layer(DiskFs);
+
+        assert_eq!(fs.read_to_string(Path::new("/idf/Kconfig")).unwrap(), "config PATCHED\n    bool\n");
+        assert!(fs.is_file(Path::new("/idf/other/Kconfig")));
+        assert_eq!(
+            fs.read_dir(Path::new("/idf")).unwrap(),
+            vec![PathBuf::from("/idf/Kconfig"), PathBuf::from("/idf/other")]
+        );
+        assert_eq!(fs.read_to_string(Path::new("/idf/none")).unwrap_err().kind(), IoErrorKind::NotFound);
+    }
+
+    #[cfg(feature = "tar")]
+    #[test_log::test]
+    fn tar_fs() {
+        let mut builder = tar::Builder::new(Vec::new());
+        let contents = b"config FROM_TAR\n    bool\n";
+        let mut header = tar::Header::new_gnu();
+        header.set_size(contents.len() as u64);
+        header.set_mode(0o644);
+        header.set_cksum();
+        builder.append_data(&mut header, "components/Kconfig", &contents[..]).unwrap();
+        let archive = builder.into_inner().unwrap();
+
+        let mut fs = MemoryFs::new();
+        fs.load_tar(&archive[..], Path::new("/idf")).unwrap();
+        assert_eq!(fs.read_to_string(Path::new("/idf/components/Kconfig")).unwrap(), "config FROM_TAR\n    bool\n");
+
+        // The builder refuses these paths, so write them into the header directly.
+        let mut builder = tar::Builder::new(Vec::new());
+        for name in ["../escape/Kconfig", "/etc/Kconfig", "components/../../Kconfig"] {
+            let mut header = tar::Header::new_gnu();
+            header.as_gnu_mut().unwrap().name[..name.len()].copy_from_slice(name.as_bytes());
+            header.set_size(contents.len() as u64);
+            header.set_mode(0o644);
+            header.set_cksum();
+            builder.append(&header, &contents[..]).unwrap();
+        }
+        let archive = builder.into_inner().unwrap();
+
+        let mut fs = MemoryFs::new();
+        fs.load_tar(&archive[..], Path::new("/idf")).unwrap();
+        assert!(fs.is_empty());
+    }
+
+    #[cfg(feature = "zip")]
+    #[test_log::test]
+    fn zip_fs() {
+        use std::io::{Cursor, Write};
+
+        let mut writer = zip::ZipWriter::new(Cursor::new(Vec::new()));
+        writer.start_file("components/Kconfig", zip::write::SimpleFileOptions::default()).unwrap();
+        writer.write_all(b"config FROM_ZIP\n    bool\n").unwrap();
+        let archive = writer.finish().unwrap();
+
+        let mut fs = MemoryFs::new();
+        fs.load_zip(archive, Path::new("/idf")).unwrap();
+        assert_eq!(fs.read_to_string(Path::new("/idf/components/Kconfig")).unwrap(), "config FROM_ZIP\n    bool\n");
+    }
+}
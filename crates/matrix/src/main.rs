@@ -0,0 +1,168 @@
+//! Evaluate a Kconfig tree for each ESP-IDF target and report which options exist, are visible, and what values they
+//! take on each chip.
+//!
+//! The report is written as CSV, JSON, or Markdown. With `--target-specific`, only options that differ between the
+//! targets are included.
+
+use {
+    clap::{builder::PossibleValue, Parser, ValueEnum},
+    log::error,
+    modular_esp_idf_kconfig_lib::{
+        eval::{Evaluation, SymbolTable},
+        parser::KConfig,
+        sdkconfig::{Renames, SdkConfig},
+        srctree, ContextArgs, Target, TargetMatrix,
+    },
+    std::{
+        fs::File,
+        io::{stdout, BufWriter, Write},
+        path::{Path, PathBuf},
+        process::ExitCode,
+    },
+};
+
+#[derive(Clone, Copy, Debug, Default)]
+enum OutputFormat {
+    /// Output as CSV.
+    #[default]
+    Csv,
+
+    /// Output as JSON.
+    Json,
+
+    /// Output as a Markdown table.
+    Markdown,
+}
+
+impl ValueEnum for OutputFormat {
+    fn value_variants<'a>() -> &'a [Self] {
+        &[Self::Csv, Self::Json, Self::Markdown]
+    }
+
+    fn to_possible_value(&self) -> Option<PossibleValue> {
+        Some(match self {
+            Self::Csv => PossibleValue::new("csv").help("Output as CSV"),
+            Self::Json => PossibleValue::new("json").help("Output as JSON"),
+            Self::Markdown => PossibleValue::new("markdown").help("Output as a Markdown table"),
+        })
+    }
+}
+
+/// Command line options for the matrix.
+#[derive(Debug, Parser)]
+#[command(version, about)]
+struct Options {
+    /// The top-level Kconfig file.
+    #[arg(long, default_value = "Kconfig")]
+    kconfig: PathBuf,
+
+    /// The targets to evaluate, separated by commas. Defaults to the targets found in `$IDF_PATH/components/soc`, or all
+    /// known targets if `IDF_PATH` is not set. The names are checked after the targets in `IDF_PATH` are discovered, so
+    /// targets this tool does not know about can be named.
+    #[arg(long, value_delimiter = ',')]
+    targets: Vec<String>,
+
+    /// `sdkconfig.defaults` files to apply to every target. As in ESP-IDF, `FILE.TARGET` is also applied for each
+    /// target if it exists.
+    #[arg(long)]
+    defaults: Vec<PathBuf>,
+
+    /// `sdkconfig.rename` files listing deprecated option names.
+    #[arg(long)]
+    sdkconfig_rename: Vec<PathBuf>,
+
+    /// The variables to evaluate the Kconfig files with. `IDF_TARGET` is set for each target.
+    #[command(flatten)]
+    env: ContextArgs,
+
+    /// The output format.
+    #[arg(long, default_value = "csv")]
+    format: OutputFormat,
+
+    /// Only include options that differ between the targets.
+    #[arg(long)]
+    target_specific: bool,
+
+    /// The file to write the report to instead of standard output.
+    #[arg(long, short)]
+    output: Option<PathBuf>,
+}
+
+fn main() -> ExitCode {
+    env_logger::init();
+    let options = Options::parse();
+
+    match run(&options) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            error!("{e}");
+            ExitCode::FAILURE
+        }
+    }
+}
+
+fn run(options: &Options) -> Result<(), Box<dyn std::error::Error>> {
+    let context = options.env.context()?;
+    let base_dir = srctree(&context)?;
+
+    let mut renames = Renames::new();
+    for filename in &options.sdkconfig_rename {
+        renames.extend(Renames::from_file(filename)?)?;
+    }
+
+    // Discovery registers the targets in the ESP-IDF tree, so it has to happen before the target names are parsed.
+    let discovered = match context.get("IDF_PATH") {
+        Some(idf_path) => Target::discover(Path::new(idf_path))?,
+        None => Target::all(),
+    };
+
+    let mut targets = Vec::new();
+    for name in &options.targets {
+        targets.push(name.parse::<Target>()?);
+    }
+
+    if targets.is_empty() {
+        targets = discovered;
+    }
+
+    let mut matrix = TargetMatrix::new();
+    for target in targets {
+        // The tree is loaded for each target since `source` statements and macros may depend on IDF_TARGET.
+        let mut context = context.clone();
+        context.insert("IDF_TARGET".to_string(), target.config_name().to_string());
+
+        let kconfig = KConfig::from_file(&options.kconfig, &base_dir, &context)?;
+        let mut eval = Evaluation::new(SymbolTable::new(&kconfig));
+
+        for defaults in &options.defaults {
+            let mut target_defaults = defaults.clone().into_os_string();
+            target_defaults.push(format!(".{}", target.config_name()));
+            let target_defaults = PathBuf::from(target_defaults);
+
+            for filename in [defaults, &target_defaults] {
+                if filename == defaults || filename.exists() {
+                    let mut sdkconfig = SdkConfig::from_file(filename)?;
+                    renames.apply(&mut sdkconfig);
+                    eval.load_sdkconfig(&sdkconfig);
+                }
+            }
+        }
+
+        eval.evaluate(&context)?;
+        matrix.add_target(target, &eval);
+    }
+
+    let mut writer: BufWriter<Box<dyn Write>> = BufWriter::new(match &options.output {
+        Some(output) => Box::new(File::create(output)?),
+        None => Box::new(stdout()),
+    });
+
+    match options.format {
+        OutputFormat::Csv => matrix.write_csv(&mut writer, options.target_specific)?,
+        OutputFormat::Json => matrix.write_json(&mut writer, options.target_specific)?,
+        OutputFormat::Markdown => matrix.write_markdown(&mut writer, options.target_specific)?,
+    }
+
+    writer.flush()?;
+    Ok(())
+}
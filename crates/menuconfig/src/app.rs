@@ -0,0 +1,984 @@
+//! Menu navigation and editing state, independent of the terminal.
+
+use {
+    crossterm::event::{KeyCode, KeyEvent, KeyModifiers},
+    modular_esp_idf_kconfig_lib::{
+        eval::{parse_int, Evaluation, MenuItem, MenuNode, Value},
+        parser::{Located, Tristate, Type},
+        sdkconfig::SdkConfigWriter,
+        Context,
+    },
+    ratatui::widgets::ListState,
+    std::{
+        fs::File,
+        io::{BufWriter, Result as IoResult, Write},
+        path::PathBuf,
+    },
+};
+
+/// What the user is currently doing.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub(crate) enum Mode {
+    /// Moving through the menus.
+    Browse,
+
+    /// Reading the help for the selected entry.
+    Help {
+        /// The number of lines scrolled past.
+        scroll: u16,
+    },
+
+    /// Entering a value for an `int`, `hex`, or `string` symbol.
+    Edit {
+        /// The symbol being edited.
+        symbol: usize,
+
+        /// The text entered so far.
+        input: String,
+
+        /// Why the last attempt to accept the input failed, if it did.
+        error: Option<String>,
+    },
+
+    /// Searching for symbols by name or prompt.
+    Search {
+        /// The search text.
+        query: String,
+
+        /// The symbols matching the query.
+        results: Vec<usize>,
+
+        /// The index of the selected result.
+        selected: usize,
+    },
+
+    /// Asking whether to save changes before exiting.
+    ConfirmQuit,
+}
+
+/// The state of the menu interface.
+pub(crate) struct App<C> {
+    eval: Evaluation,
+    context: C,
+    writer: SdkConfigWriter,
+
+    /// The `sdkconfig` file to save to.
+    config: PathBuf,
+
+    /// The child indices leading from the top of the menu tree to the menu or choice being shown.
+    path: Vec<usize>,
+
+    /// The selected row at each level of `path`, so it can be restored when going back.
+    selected: Vec<usize>,
+
+    /// The list widget state for the menu being shown.
+    pub(crate) list_state: ListState,
+
+    /// Whether entries that are not visible are shown anyway.
+    pub(crate) show_all: bool,
+
+    /// What the user is currently doing.
+    pub(crate) mode: Mode,
+
+    /// Whether there are unsaved changes.
+    pub(crate) modified: bool,
+
+    /// A message to show in the status line.
+    pub(crate) status: Option<String>,
+
+    /// Whether the application should exit.
+    pub(crate) quit: bool,
+}
+
+/// A row in the menu being shown.
+pub(crate) struct Entry<'a> {
+    /// The node for the row.
+    pub(crate) node: &'a MenuNode,
+
+    /// The index of the node among its siblings.
+    pub(crate) index: usize,
+
+    /// Whether the entry is visible (and hence can be changed).
+    pub(crate) visible: bool,
+}
+
+impl<C> App<C>
+where
+    C: Context,
+{
+    /// Create the interface for an evaluated configuration.
+    pub(crate) fn new(eval: Evaluation, context: C, writer: SdkConfigWriter, config: PathBuf) -> Self {
+        Self {
+            eval,
+            context,
+            writer,
+            config,
+            path: Vec::new(),
+            selected: vec![0],
+            list_state: ListState::default(),
+            show_all: false,
+            mode: Mode::Browse,
+            modified: false,
+            status: None,
+            quit: false,
+        }
+    }
+
+    /// Returns the evaluated configuration.
+    #[inline(always)]
+    pub(crate) fn eval(&self) -> &Evaluation {
+        &self.eval
+    }
+
+    /// Returns the `sdkconfig` file being edited.
+    #[inline(always)]
+    pub(crate) fn config(&self) -> &PathBuf {
+        &self.config
+    }
+
+    /// Returns the index of the selected row.
+    #[inline(always)]
+    pub(crate) fn selected(&self) -> usize {
+        *self.selected.last().unwrap()
+    }
+
+    /// Returns the titles of the menus and choices leading to the one being shown.
+    pub(crate) fn breadcrumbs(&self) -> Vec<String> {
+        let mut nodes = self.eval.table().menu();
+        let mut result = Vec::with_capacity(self.path.len());
+
+        for &index in &self.path {
+            let node = &nodes[index];
+            result.push(self.title(node));
+            nodes = &node.children;
+        }
+
+        result
+    }
+
+    /// Returns the nodes of the menu or choice being shown.
+    fn nodes(&self) -> &[MenuNode] {
+        let mut nodes = self.eval.table().menu();
+        for &index in &self.path {
+            nodes = &nodes[index].children;
+        }
+        nodes
+    }
+
+    /// Returns the node for the menu or choice being shown, or `None` at the top level.
+    fn parent(&self) -> Option<&MenuNode> {
+        let (&last, rest) = self.path.split_last()?;
+        let mut nodes = self.eval.table().menu();
+        for &index in rest {
+            nodes = &nodes[index].children;
+        }
+        Some(&nodes[last])
+    }
+
+    /// Returns the rows of the menu being shown.
+    pub(crate) fn entries(&self) -> Vec<Entry<'_>> {
+        self.nodes()
+            .iter()
+            .enumerate()
+            .filter(|(_, node)| self.has_prompt(node))
+            .map(|(index, node)| Entry {
+                node,
+                index,
+                visible: self.is_visible(node),
+            })
+            .filter(|entry| entry.visible || self.show_all)
+            .collect()
+    }
+
+    /// Returns the selected row, if the menu is not empty.
+    pub(crate) fn selected_entry(&self) -> Option<Entry<'_>> {
+        let selected = self.selected();
+        self.entries().into_iter().nth(selected)
+    }
+
+    /// Indicates whether a node has a prompt and can be shown.
+    fn has_prompt(&self, node: &MenuNode) -> bool {
+        match &node.item {
+            MenuItem::Menu {
+                ..
+            } => true,
+            MenuItem::Choice(choice) => self.eval.table().choices()[*choice].choice.prompt.is_some(),
+            MenuItem::Symbol {
+                symbol,
+                definition,
+            } => self.eval.table().symbols()[*symbol].definitions[*definition].config.prompt.is_some(),
+        }
+    }
+
+    /// Indicates whether a node is visible.
+    pub(crate) fn is_visible(&self, node: &MenuNode) -> bool {
+        match &node.item {
+            MenuItem::Menu {
+                id,
+                ..
+            } => {
+                let state = self.eval.menu_state(*id);
+                state.dependency != Tristate::False && state.visibility != Tristate::False
+            }
+            MenuItem::Choice(choice) => self.eval.choice_state(*choice).visibility != Tristate::False,
+            MenuItem::Symbol {
+                symbol,
+                ..
+            } => self.eval.symbol_state(*symbol).visibility != Tristate::False,
+        }
+    }
+
+    /// Returns the title of a node.
+    pub(crate) fn title(&self, node: &MenuNode) -> String {
+        let table = self.eval.table();
+        match &node.item {
+            MenuItem::Menu {
+                prompt,
+                ..
+            } => prompt.to_string(),
+            MenuItem::Choice(choice) => {
+                table.choices()[*choice].choice.prompt.as_ref().map(|p| p.title.to_string()).unwrap_or_default()
+            }
+            MenuItem::Symbol {
+                symbol,
+                definition,
+            } => table.symbols()[*symbol].definitions[*definition]
+                .config
+                .prompt
+                .as_ref()
+                .map(|p| p.title.to_string())
+                .unwrap_or_else(|| table.symbols()[*symbol].name.clone()),
+        }
+    }
+
+    /// Returns the text for a row: a marker showing the value, the title, and a suffix.
+    pub(crate) fn label(&self, node: &MenuNode) -> String {
+        let table = self.eval.table();
+        let title = self.title(node);
+
+        match &node.item {
+            MenuItem::Menu {
+                ..
+            } => format!("    {title}  --->"),
+
+            MenuItem::Choice(choice) => {
+                let selection = self
+                    .eval
+                    .choice_state(*choice)
+                    .selection
+                    .and_then(|member| {
+                        node.children
+                            .iter()
+                            .find(|child| matches!(child.item, MenuItem::Symbol { symbol, .. } if symbol == member))
+                    })
+                    .map(|child| format!(" ({})", self.title(child)))
+                    .unwrap_or_default();
+                format!("    {title}{selection}  --->")
+            }
+
+            MenuItem::Symbol {
+                symbol,
+                ..
+            } => {
+                let info = &table.symbols()[*symbol];
+                let value = &self.eval.symbol_state(*symbol).value;
+
+                let marker = match (info.choice.is_some(), info.r#type, value) {
+                    (true, _, Value::Tristate(Tristate::False)) => "( )".to_string(),
+                    (true, _, _) => "(X)".to_string(),
+                    (false, Type::Tristate, Value::Tristate(Tristate::Maybe)) => "<M>".to_string(),
+                    (false, Type::Tristate, Value::Tristate(Tristate::True)) => "<*>".to_string(),
+                    (false, Type::Tristate, _) => "< >".to_string(),
+                    (false, _, Value::Tristate(Tristate::False)) => "[ ]".to_string(),
+                    (false, _, Value::Tristate(_)) => "[*]".to_string(),
+                    (false, _, Value::String(s)) => format!("({s})"),
+                };
+
+                format!("{marker} {title}")
+            }
+        }
+    }
+
+    /// Returns the help text for a node: its name, type, value, dependencies, location, and help.
+    pub(crate) fn help(&self, node: &MenuNode) -> String {
+        let table = self.eval.table();
+        let mut lines = Vec::new();
+
+        match &node.item {
+            MenuItem::Menu {
+                prompt,
+                visibility,
+                ..
+            } => {
+                lines.push(format!("Menu: {prompt}"));
+                if let Some(visibility) = visibility {
+                    lines.push(format!("Visible if: {}", visibility.expr));
+                }
+            }
+
+            MenuItem::Choice(choice) => {
+                let info = &table.choices()[*choice];
+                lines.push(format!("Choice: {}", info.choice.name.as_str()));
+                if let Some(help) = &info.choice.help {
+                    lines.push(String::new());
+                    lines.extend(help.trim_end().lines().map(str::to_string));
+                    lines.push(String::new());
+                }
+            }
+
+            MenuItem::Symbol {
+                symbol,
+                definition,
+            } => {
+                let info = &table.symbols()[*symbol];
+                let def = &info.definitions[*definition];
+                let state = self.eval.symbol_state(*symbol);
+
+                lines.push(format!("CONFIG_{}", info.name));
+                lines.push(String::new());
+
+                match &def.config.help {
+                    Some(help) => lines.extend(help.trim_end().lines().map(str::to_string)),
+                    None => lines.push("There is no help available for this option.".to_string()),
+                }
+
+                lines.push(String::new());
+                lines.push(format!("Type: {}", info.r#type));
+                lines.push(format!("Value: {}", state.value));
+                if let Some((low, high)) = state.range {
+                    lines.push(format!("Range: {}", range_text(info.r#type, low, high)));
+                }
+                lines.push(format!("Defined at {}", def.config.name.location()));
+                if !def.menu_path.is_empty() {
+                    lines.push(format!("Menu path: {}", def.menu_path.join(" > ")));
+                }
+            }
+        }
+
+        if let Some(dependency) = &node.dependency {
+            lines.push(format!("Depends on: {}", dependency.expr));
+        }
+
+        lines.join("\n")
+    }
+
+    /// Handle a key press.
+    pub(crate) fn handle_key(&mut self, key: KeyEvent) {
+        if key.modifiers.contains(KeyModifiers::CONTROL) && key.code == KeyCode::Char('c') {
+            self.quit = true;
+            return;
+        }
+
+        match self.mode.clone() {
+            Mode::Browse => self.browse_key(key),
+            Mode::Help {
+                scroll,
+            } => match key.code {
+                KeyCode::Up | KeyCode::Char('k') => {
+                    self.mode = Mode::Help {
+                        scroll: scroll.saturating_sub(1),
+                    }
+                }
+                KeyCode::Down | KeyCode::Char('j') => {
+                    self.mode = Mode::Help {
+                        scroll: scroll.saturating_add(1),
+                    }
+                }
+                _ => self.mode = Mode::Browse,
+            },
+            Mode::Edit {
+                symbol,
+                mut input,
+                error,
+            } => match key.code {
+                KeyCode::Esc => self.mode = Mode::Browse,
+                KeyCode::Enter => self.commit_edit(symbol, input),
+                KeyCode::Backspace => {
+                    input.pop();
+                    self.mode = Mode::Edit {
+                        symbol,
+                        input,
+                        error,
+                    };
+                }
+                KeyCode::Char(c) => {
+                    input.push(c);
+                    self.mode = Mode::Edit {
+                        symbol,
+                        input,
+                        error,
+                    };
+                }
+                _ => (),
+            },
+            Mode::Search {
+                mut query,
+                results,
+                selected,
+            } => match key.code {
+                KeyCode::Esc => self.mode = Mode::Browse,
+                KeyCode::Enter => {
+                    if let Some(&symbol) = results.get(selected) {
+                        self.jump_to(symbol);
+                    }
+                }
+                KeyCode::Up => {
+                    self.mode = Mode::Search {
+                        query,
+                        results,
+                        selected: selected.saturating_sub(1),
+                    }
+                }
+                KeyCode::Down => {
+                    let selected = (selected + 1).min(results.len().saturating_sub(1));
+                    self.mode = Mode::Search {
+                        query,
+                        results,
+                        selected,
+                    };
+                }
+                KeyCode::Backspace => {
+                    query.pop();
+                    self.search(query);
+                }
+                KeyCode::Char(c) => {
+                    query.push(c);
+                    self.search(query);
+                }
+                _ => (),
+            },
+            Mode::ConfirmQuit => match key.code {
+                KeyCode::Char('y') | KeyCode::Char('Y') => {
+                    if self.save() {
+                        self.quit = true;
+                    } else {
+                        self.mode = Mode::Browse;
+                    }
+                }
+                KeyCode::Char('n') | KeyCode::Char('N') => self.quit = true,
+                _ => self.mode = Mode::Browse,
+            },
+        }
+    }
+
+    /// Handle a key press while browsing.
+    fn browse_key(&mut self, key: KeyEvent) {
+        self.status = None;
+        let count = self.entries().len();
+
+        match key.code {
+            KeyCode::Up | KeyCode::Char('k') => self.select(self.selected().saturating_sub(1)),
+            KeyCode::Down | KeyCode::Char('j') => self.select(self.selected() + 1),
+            KeyCode::PageUp => self.select(self.selected().saturating_sub(10)),
+            KeyCode::PageDown => self.select(self.selected() + 10),
+            KeyCode::Home | KeyCode::Char('g') => self.select(0),
+            KeyCode::End | KeyCode::Char('G') => self.select(count.saturating_sub(1)),
+            KeyCode::Enter | KeyCode::Right | KeyCode::Char('l') => self.activate(),
+            KeyCode::Char(' ') => self.toggle(),
+            KeyCode::Char('y') => self.set_bool(true),
+            KeyCode::Char('n') => self.set_bool(false),
+            KeyCode::Esc | KeyCode::Left | KeyCode::Backspace | KeyCode::Char('h') => self.back(),
+            KeyCode::Char('?') if self.selected_entry().is_some() => {
+                self.mode = Mode::Help {
+                    scroll: 0,
+                };
+            }
+            KeyCode::Char('/') => self.search(String::new()),
+            KeyCode::Char('a') => {
+                self.show_all = !self.show_all;
+                self.select(0);
+            }
+            KeyCode::Char('s') => {
+                self.save();
+            }
+            KeyCode::Char('q') => self.request_quit(),
+            _ => (),
+        }
+    }
+
+    /// Select a row, clamping to the rows available.
+    fn select(&mut self, row: usize) {
+        let count = self.entries().len();
+        *self.selected.last_mut().unwrap() = row.min(count.saturating_sub(1));
+    }
+
+    /// Open the selected menu or choice, or change the selected symbol.
+    fn activate(&mut self) {
+        let Some(entry) = self.selected_entry() else {
+            return;
+        };
+
+        match &entry.node.item {
+            MenuItem::Menu {
+                ..
+            }
+            | MenuItem::Choice(_) => {
+                let index = entry.index;
+                self.path.push(index);
+                self.selected.push(0);
+                self.list_state = ListState::default();
+            }
+            MenuItem::Symbol {
+                symbol,
+                ..
+            } => {
+                let symbol = *symbol;
+                if !entry.visible {
+                    self.status = Some("This option cannot be changed (its dependencies are not met)".to_string());
+                    return;
+                }
+
+                match self.eval.table().symbols()[symbol].r#type {
+                    Type::Bool | Type::Tristate => self.toggle(),
+                    _ => {
+                        let input = match &self.eval.symbol_state(symbol).value {
+                            Value::String(s) => s.clone(),
+                            value => value.to_string(),
+                        };
+                        self.mode = Mode::Edit {
+                            symbol,
+                            input,
+                            error: None,
+                        };
+                    }
+                }
+            }
+        }
+    }
+
+    /// Toggle the selected `bool` symbol or choose the selected choice member.
+    fn toggle(&mut self) {
+        let Some(entry) = self.selected_entry() else {
+            return;
+        };
+
+        let MenuItem::Symbol {
+            symbol,
+            ..
+        } = entry.node.item
+        else {
+            return self.activate();
+        };
+
+        let info = &self.eval.table().symbols()[symbol];
+        if !matches!(info.r#type, Type::Bool | Type::Tristate) {
+            return self.activate();
+        }
+
+        let enabled = self.eval.symbol_state(symbol).value.tristate() != Tristate::False;
+        if info.choice.is_some() {
+            self.set_bool(true);
+        } else {
+            self.set_bool(!enabled);
+        }
+    }
+
+    /// Set the selected `bool` symbol.
+    fn set_bool(&mut self, value: bool) {
+        let Some(entry) = self.selected_entry() else {
+            return;
+        };
+
+        let MenuItem::Symbol {
+            symbol,
+            ..
+        } = entry.node.item
+        else {
+            return;
+        };
+
+        let info = &self.eval.table().symbols()[symbol];
+        if !matches!(info.r#type, Type::Bool | Type::Tristate) {
+            return;
+        }
+
+        if !entry.visible {
+            self.status = Some("This option cannot be changed (its dependencies are not met)".to_string());
+            return;
+        }
+
+        let in_choice = info.choice.is_some();
+        if in_choice && !value {
+            // A choice member is turned off by choosing another member.
+            return;
+        }
+
+        let name = info.name.clone();
+        self.set_value(
+            &name,
+            if value {
+                "y"
+            } else {
+                "n"
+            },
+        );
+
+        if self.eval.symbol_state(symbol).value.tristate() != Tristate::from(value) {
+            self.status = Some(format!("{name} is selected by another option and cannot be turned off"));
+        }
+
+        // Choosing a choice member returns to the enclosing menu.
+        if in_choice && self.parent().map(|p| matches!(p.item, MenuItem::Choice(_))).unwrap_or(false) {
+            self.back();
+        }
+    }
+
+    /// Set a user value and re-evaluate the configuration.
+    fn set_value(&mut self, name: &str, value: &str) {
+        self.eval.set_user_value(name, value);
+        self.modified = true;
+
+        if let Err(e) = self.eval.evaluate(&self.context) {
+            self.status = Some(e.to_string());
+        }
+
+        self.select(self.selected());
+    }
+
+    /// Validate and accept the value entered for a symbol.
+    fn commit_edit(&mut self, symbol: usize, input: String) {
+        let info = &self.eval.table().symbols()[symbol];
+        let r#type = info.r#type;
+        let name = info.name.clone();
+
+        let value = match r#type {
+            Type::Int | Type::Hex => {
+                let value = match (parse_int(&input, r#type), self.eval.symbol_state(symbol).range) {
+                    (None, _) => Err(format!("{input:?} is not a valid {type} value")),
+                    (Some(n), _) if r#type == Type::Hex && n < 0 => Err("Hex values cannot be negative".to_string()),
+                    (Some(n), Some((low, high))) if n < low || n > high => {
+                        Err(format!("Value must be in the range {}", range_text(r#type, low, high)))
+                    }
+                    (Some(n), _) if r#type == Type::Hex => Ok(format!("{n:#x}")),
+                    (Some(_), _) => Ok(input.trim().to_string()),
+                };
+
+                match value {
+                    Ok(value) => value,
+                    Err(error) => {
+                        self.mode = Mode::Edit {
+                            symbol,
+                            input,
+                            error: Some(error),
+                        };
+                        return;
+                    }
+                }
+            }
+            _ => input,
+        };
+
+        self.mode = Mode::Browse;
+        self.set_value(&name, &value);
+    }
+
+    /// Go back to the enclosing menu, or ask to quit at the top level.
+    fn back(&mut self) {
+        if self.path.pop().is_some() {
+            self.selected.pop();
+            self.list_state = ListState::default();
+            self.select(self.selected());
+        } else {
+            self.request_quit();
+        }
+    }
+
+    /// Quit, asking to save first if there are unsaved changes.
+    fn request_quit(&mut self) {
+        if self.modified {
+            self.mode = Mode::ConfirmQuit;
+        } else {
+            self.quit = true;
+        }
+    }
+
+    /// Update the search results for a query. Symbols match if their name or any of their prompts contains the query,
+    /// ignoring case.
+    fn search(&mut self, query: String) {
+        let needle = query.to_lowercase();
+        let results = if needle.is_empty() {
+            Vec::new()
+        } else {
+            self.eval
+                .table()
+                .symbols()
+                .iter()
+                .enumerate()
+                .filter(|(_, symbol)| {
+                    symbol.name.to_lowercase().contains(&needle)
+                        || symbol.definitions.iter().any(|def| {
+                            def.config
+                                .prompt
+                                .as_ref()
+                                .map(|p| p.title.to_lowercase().contains(&needle))
+                                .unwrap_or(false)
+                        })
+                })
+                .map(|(id, _)| id)
+                .collect()
+        };
+
+        self.mode = Mode::Search {
+            query,
+            results,
+            selected: 0,
+        };
+    }
+
+    /// Show the menu containing a symbol, with the symbol selected.
+    fn jump_to(&mut self, symbol: usize) {
+        self.mode = Mode::Browse;
+
+        let Some(path) = self.find_symbol(self.eval.table().menu(), symbol) else {
+            self.status =
+                Some(format!("{} has no prompt and cannot be shown", self.eval.table().symbols()[symbol].name));
+            return;
+        };
+
+        let (&index, parents) = path.split_last().unwrap();
+        if !self.is_visible_path(&path) {
+            self.show_all = true;
+        }
+
+        self.path = parents.to_vec();
+        self.selected = vec![0; self.path.len() + 1];
+        self.list_state = ListState::default();
+
+        let row = self.entries().iter().position(|entry| entry.index == index).unwrap_or(0);
+        self.select(row);
+    }
+
+    /// Returns the child indices leading to the first node with a prompt for a symbol.
+    fn find_symbol(&self, nodes: &[MenuNode], symbol: usize) -> Option<Vec<usize>> {
+        for (index, node) in nodes.iter().enumerate() {
+            if let MenuItem::Symbol {
+                symbol: s,
+                ..
+            } = node.item
+            {
+                if s == symbol && self.has_prompt(node) {
+                    return Some(vec![index]);
+                }
+            }
+
+            if let Some(mut path) = self.find_symbol(&node.children, symbol) {
+                path.insert(0, index);
+                return Some(path);
+            }
+        }
+
+        None
+    }
+
+    /// Indicates whether every node along a path is visible.
+    fn is_visible_path(&self, path: &[usize]) -> bool {
+        let mut nodes = self.eval.table().menu();
+        for &index in path {
+            if !self.is_visible(&nodes[index]) {
+                return false;
+            }
+            nodes = &nodes[index].children;
+        }
+        true
+    }
+
+    /// Save the configuration, returning whether it succeeded.
+    pub(crate) fn save(&mut self) -> bool {
+        match self.write_config() {
+            Ok(()) => {
+                self.modified = false;
+                self.status = Some(format!("Configuration saved to {}", self.config.display()));
+                true
+            }
+            Err(e) => {
+                self.status = Some(format!("Unable to save {}: {e}", self.config.display()));
+                false
+            }
+        }
+    }
+
+    fn write_config(&self) -> IoResult<()> {
+        let mut writer = BufWriter::new(File::create(&self.config)?);
+        self.writer.write_config(&self.eval, &mut writer)?;
+        writer.flush()
+    }
+}
+
+/// Format a range for display, in hex for `hex` symbols.
+pub(crate) fn range_text(r#type: Type, low: i128, high: i128) -> String {
+    match r#type {
+        Type::Hex => format!("[{low:#x}, {high:#x}]"),
+        _ => format!("[{low}, {high}]"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        crate::app::{App, Mode},
+        crossterm::event::{KeyCode, KeyEvent},
+        modular_esp_idf_kconfig_lib::{
+            eval::{Evaluation, SymbolTable},
+            parser::{KConfig, PeekableChars},
+            sdkconfig::SdkConfigWriter,
+        },
+        ratatui::{backend::TestBackend, Terminal},
+        std::{collections::HashMap, env, fs, path::Path},
+    };
+
+    const KCONFIG: &str = r##"
+mainmenu "Test Configuration"
+
+menu "Serial"
+    config UART
+        bool "Enable UART"
+        help
+            Enables the UART driver.
+
+    config BAUD
+        int "Baud rate"
+        depends on UART
+        range 1200 115200
+        default 9600
+
+    choice PARITY
+        prompt "Parity"
+        depends on UART
+
+        config PARITY_NONE
+            bool "None"
+        config PARITY_EVEN
+            bool "Even"
+    endchoice
+endmenu
+
+config NAME
+    string "Device name"
+    default "device"
+
+config BASE
+    hex "Base address"
+    default 0x1000
+"##;
+
+    fn app(config: &Path) -> App<HashMap<String, String>> {
+        let context = HashMap::new();
+        let kconfig =
+            KConfig::from_str(PeekableChars::new(KCONFIG, Path::new("test")), Path::new("/tmp"), &context).unwrap();
+        let mut eval = Evaluation::new(SymbolTable::new(&kconfig));
+        eval.evaluate(&context).unwrap();
+        App::new(eval, context, SdkConfigWriter::new(), config.to_path_buf())
+    }
+
+    fn press(app: &mut App<HashMap<String, String>>, keys: &[KeyCode]) {
+        for &key in keys {
+            app.handle_key(KeyEvent::from(key));
+        }
+    }
+
+    fn type_text(app: &mut App<HashMap<String, String>>, text: &str) {
+        for c in text.chars() {
+            app.handle_key(KeyEvent::from(KeyCode::Char(c)));
+        }
+    }
+
+    fn value(app: &App<HashMap<String, String>>, name: &str) -> String {
+        app.eval().value(name).unwrap().to_string()
+    }
+
+    #[test_log::test]
+    fn menuconfig_navigation() {
+        let dir = env::temp_dir().join(format!("kconfig-menuconfig-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let config = dir.join("sdkconfig");
+        let mut app = app(&config);
+
+        // Only UART is visible in the Serial menu until it is enabled.
+        press(&mut app, &[KeyCode::Enter]);
+        assert_eq!(app.breadcrumbs(), vec!["Serial".to_string()]);
+        assert_eq!(app.entries().len(), 1);
+        press(&mut app, &[KeyCode::Char(' ')]);
+        assert_eq!(value(&app, "UART"), "y");
+        assert_eq!(app.entries().len(), 3);
+
+        // Out-of-range and malformed values are rejected.
+        press(&mut app, &[KeyCode::Down, KeyCode::Enter]);
+        assert!(matches!(&app.mode, Mode::Edit { input, .. } if input == "9600"));
+        press(&mut app, &[KeyCode::Backspace; 4]);
+        type_text(&mut app, "999999");
+        press(&mut app, &[KeyCode::Enter]);
+        assert!(matches!(&app.mode, Mode::Edit { error: Some(e), .. } if e.contains("[1200, 115200]")));
+        press(&mut app, &[KeyCode::Backspace; 6]);
+        type_text(&mut app, "0x10");
+        press(&mut app, &[KeyCode::Enter]);
+        assert!(matches!(
+            &app.mode,
+            Mode::Edit {
+                error: Some(_),
+                ..
+            }
+        ));
+        press(&mut app, &[KeyCode::Backspace; 4]);
+        type_text(&mut app, "57600");
+        press(&mut app, &[KeyCode::Enter]);
+        assert_eq!(app.mode, Mode::Browse);
+        assert_eq!(value(&app, "BAUD"), "57600");
+
+        // Choosing a choice member returns to the menu.
+        press(&mut app, &[KeyCode::Down, KeyCode::Enter, KeyCode::Down, KeyCode::Enter]);
+        assert_eq!(value(&app, "PARITY_EVEN"), "y");
+        assert_eq!(app.breadcrumbs(), vec!["Serial".to_string()]);
+        assert!(app.label(app.selected_entry().unwrap().node).contains("(Even)"));
+
+        // Help shows the help text.
+        press(&mut app, &[KeyCode::Home, KeyCode::Char('?')]);
+        assert!(app.help(app.selected_entry().unwrap().node).contains("Enables the UART driver."));
+        press(&mut app, &[KeyCode::Esc, KeyCode::Esc]);
+        assert!(app.breadcrumbs().is_empty());
+
+        // Searching by prompt jumps to the menu containing the option.
+        press(&mut app, &[KeyCode::Char('/')]);
+        type_text(&mut app, "baud");
+        press(&mut app, &[KeyCode::Enter]);
+        assert_eq!(app.breadcrumbs(), vec!["Serial".to_string()]);
+        assert_eq!(app.title(app.selected_entry().unwrap().node), "Baud rate");
+
+        let mut terminal = Terminal::new(TestBackend::new(80, 24)).unwrap();
+        terminal.draw(|frame| crate::ui::draw(frame, &mut app)).unwrap();
+        let screen = format!("{:?}", terminal.backend().buffer());
+        assert!(screen.contains("Test Configuration"));
+        assert!(screen.contains("(57600) Baud rate"));
+
+        // Quitting with unsaved changes asks to save first.
+        press(&mut app, &[KeyCode::Char('q')]);
+        assert_eq!(app.mode, Mode::ConfirmQuit);
+        press(&mut app, &[KeyCode::Char('y')]);
+        assert!(app.quit);
+
+        let saved = fs::read_to_string(&config).unwrap();
+        assert!(saved.contains("CONFIG_UART=y\n"));
+        assert!(saved.contains("CONFIG_BAUD=57600\n"));
+        assert!(saved.contains("CONFIG_PARITY_EVEN=y\n"));
+
+        _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test_log::test]
+    fn menuconfig_hex_input() {
+        let dir = env::temp_dir().join(format!("kconfig-menuconfig-hex-{}", std::process::id()));
+        let mut app = app(&dir.join("sdkconfig"));
+
+        press(&mut app, &[KeyCode::Down, KeyCode::Down, KeyCode::Enter]);
+        assert!(matches!(&app.mode, Mode::Edit { input, .. } if input == "0x1000"));
+        press(&mut app, &[KeyCode::Backspace; 6]);
+        type_text(&mut app, "-10");
+        press(&mut app, &[KeyCode::Enter]);
+        assert!(matches!(&app.mode, Mode::Edit { error: Some(e), .. } if e.contains("negative")));
+
+        press(&mut app, &[KeyCode::Backspace; 3]);
+        type_text(&mut app, "ABC");
+        press(&mut app, &[KeyCode::Enter]);
+        assert_eq!(app.mode, Mode::Browse);
+        assert_eq!(value(&app, "BASE"), "0xabc");
+    }
+}
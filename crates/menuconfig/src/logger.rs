@@ -0,0 +1,107 @@
+//! Logging that doesn't write over the terminal interface.
+
+use {
+    log::{Level, Log, Metadata, Record},
+    std::sync::{
+        atomic::{AtomicBool, Ordering},
+        Mutex,
+    },
+};
+
+/// Forwards log records to `env_logger`, except while the terminal interface is active. Writing to stderr then would
+/// corrupt the screen, so warnings and errors are queued for the status line instead and other records are dropped.
+pub(crate) struct TuiLogger {
+    /// The logger used outside the terminal interface.
+    inner: env_logger::Logger,
+
+    /// Whether the terminal interface is active.
+    active: AtomicBool,
+
+    /// Messages logged while the terminal interface is active that haven't been shown yet.
+    queued: Mutex<Vec<String>>,
+}
+
+impl TuiLogger {
+    /// Create a logger configured from the `RUST_LOG` environment variable.
+    pub(crate) fn from_default_env() -> Self {
+        Self::new(env_logger::Builder::from_default_env().build())
+    }
+
+    fn new(inner: env_logger::Logger) -> Self {
+        Self {
+            inner,
+            active: AtomicBool::new(false),
+            queued: Mutex::new(Vec::new()),
+        }
+    }
+
+    /// Install the logger as the global logger.
+    pub(crate) fn init(self) -> &'static Self {
+        let logger: &'static Self = Box::leak(Box::new(self));
+        log::set_max_level(logger.inner.filter());
+        log::set_logger(logger).expect("a logger has already been installed");
+        logger
+    }
+
+    /// Set whether the terminal interface is active.
+    pub(crate) fn set_active(&self, active: bool) {
+        self.active.store(active, Ordering::Release);
+    }
+
+    /// Returns the most recent message logged while the terminal interface was active, discarding any others.
+    pub(crate) fn take_message(&self) -> Option<String> {
+        let mut queued = self.queued.lock().unwrap();
+        let message = queued.pop();
+        queued.clear();
+        message
+    }
+}
+
+impl Log for TuiLogger {
+    fn enabled(&self, metadata: &Metadata<'_>) -> bool {
+        self.inner.enabled(metadata)
+    }
+
+    fn log(&self, record: &Record<'_>) {
+        if !self.active.load(Ordering::Acquire) {
+            self.inner.log(record);
+        } else if self.inner.matches(record) && record.level() <= Level::Warn {
+            self.queued.lock().unwrap().push(record.args().to_string());
+        }
+    }
+
+    fn flush(&self) {
+        self.inner.flush();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        crate::logger::TuiLogger,
+        log::{Level, LevelFilter, Log, Record},
+    };
+
+    fn log(logger: &TuiLogger, level: Level, message: &str) {
+        logger.log(&Record::builder().level(level).args(format_args!("{message}")).build());
+    }
+
+    #[test_log::test]
+    fn queue_while_active() {
+        let logger = TuiLogger::new(env_logger::Builder::new().filter_level(LevelFilter::Info).is_test(true).build());
+
+        log(&logger, Level::Warn, "before");
+        assert_eq!(logger.take_message(), None);
+
+        logger.set_active(true);
+        log(&logger, Level::Warn, "first");
+        log(&logger, Level::Error, "second");
+        log(&logger, Level::Info, "not shown");
+        assert_eq!(logger.take_message(), Some("second".to_string()));
+        assert_eq!(logger.take_message(), None);
+
+        logger.set_active(false);
+        log(&logger, Level::Error, "after");
+        assert_eq!(logger.take_message(), None);
+    }
+}
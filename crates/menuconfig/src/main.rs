@@ -0,0 +1,111 @@
+//! Edit an sdkconfig file with a terminal menu interface.
+
+mod app;
+mod logger;
+mod ui;
+
+use {
+    crate::{app::App, logger::TuiLogger},
+    clap::Parser,
+    crossterm::event::{self, Event, KeyEventKind},
+    log::error,
+    modular_esp_idf_kconfig_lib::{
+        eval::{Evaluation, SymbolTable},
+        parser::KConfig,
+        sdkconfig::{Renames, SdkConfig, SdkConfigWriter},
+        srctree, ContextArgs,
+    },
+    std::{collections::HashMap, path::PathBuf, process::ExitCode},
+};
+
+/// Command line options for the menu interface.
+#[derive(Debug, Parser)]
+#[command(version, about)]
+struct Options {
+    /// The top-level Kconfig file.
+    #[arg(long, default_value = "Kconfig")]
+    kconfig: PathBuf,
+
+    /// The project configuration (`sdkconfig`) file to edit.
+    #[arg(long, env = "KCONFIG_CONFIG", default_value = "sdkconfig")]
+    config: PathBuf,
+
+    /// `sdkconfig.rename` files listing deprecated option names.
+    #[arg(long)]
+    sdkconfig_rename: Vec<PathBuf>,
+
+    /// The variables to evaluate the Kconfig files with.
+    #[command(flatten)]
+    env: ContextArgs,
+}
+
+fn main() -> ExitCode {
+    let logger = TuiLogger::from_default_env().init();
+    let options = Options::parse();
+
+    let mut app = match load(&options) {
+        Ok(app) => app,
+        Err(e) => {
+            error!("{e}");
+            return ExitCode::FAILURE;
+        }
+    };
+
+    let mut terminal = ratatui::init();
+    logger.set_active(true);
+    let result = (|| -> std::io::Result<()> {
+        while !app.quit {
+            if let Some(message) = logger.take_message() {
+                app.status = Some(message);
+            }
+
+            terminal.draw(|frame| ui::draw(frame, &mut app))?;
+
+            if let Event::Key(key) = event::read()? {
+                if key.kind == KeyEventKind::Press {
+                    app.handle_key(key);
+                }
+            }
+        }
+
+        Ok(())
+    })();
+    logger.set_active(false);
+    ratatui::restore();
+
+    match result {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            error!("{e}");
+            ExitCode::FAILURE
+        }
+    }
+}
+
+/// Read the Kconfig tree and the current configuration.
+fn load(options: &Options) -> Result<App<HashMap<String, String>>, Box<dyn std::error::Error>> {
+    let context = options.env.context()?;
+    let base_dir = srctree(&context)?;
+
+    let kconfig = KConfig::from_file(&options.kconfig, &base_dir, &context)?;
+
+    let mut renames = Renames::new();
+    for filename in &options.sdkconfig_rename {
+        renames.extend(Renames::from_file(filename)?)?;
+    }
+
+    let mut eval = Evaluation::new(SymbolTable::new(&kconfig));
+    if options.config.exists() {
+        let mut sdkconfig = SdkConfig::from_file(&options.config)?;
+        renames.apply(&mut sdkconfig);
+        eval.load_sdkconfig(&sdkconfig);
+    }
+    eval.evaluate(&context)?;
+
+    let writer = SdkConfigWriter {
+        idf_version: None,
+        renames,
+    };
+
+    Ok(App::new(eval, context, writer, options.config.clone()))
+}
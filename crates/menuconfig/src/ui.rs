@@ -0,0 +1,195 @@
+//! Drawing the menu interface.
+
+use {
+    crate::app::{range_text, App, Mode},
+    modular_esp_idf_kconfig_lib::Context,
+    ratatui::{
+        layout::{Constraint, Layout, Rect},
+        style::{Color, Modifier, Style, Stylize},
+        text::{Line, Span},
+        widgets::{Block, Clear, List, ListItem, ListState, Paragraph, Wrap},
+        Frame,
+    },
+};
+
+/// The title shown when the Kconfig tree has no `mainmenu`.
+const DEFAULT_TITLE: &str = "Espressif IoT Development Framework Configuration";
+
+/// Draw the interface.
+pub(crate) fn draw<C: Context>(frame: &mut Frame, app: &mut App<C>) {
+    let [title_area, path_area, list_area, status_area, keys_area] = Layout::vertical([
+        Constraint::Length(1),
+        Constraint::Length(1),
+        Constraint::Min(3),
+        Constraint::Length(1),
+        Constraint::Length(1),
+    ])
+    .areas(frame.area());
+
+    let title = app.eval().table().mainmenu().map(|title| title.to_string()).unwrap_or(DEFAULT_TITLE.to_string());
+    let modified = if app.modified {
+        " [modified]"
+    } else {
+        ""
+    };
+    frame.render_widget(
+        Line::from(format!("{title} ({}){modified}", app.config().display())).bold().reversed(),
+        title_area,
+    );
+
+    let mut breadcrumbs = vec!["Main menu".to_string()];
+    breadcrumbs.extend(app.breadcrumbs());
+    frame.render_widget(Line::from(breadcrumbs.join(" > ")).dim(), path_area);
+
+    draw_menu(frame, app, list_area);
+
+    let status = app.status.clone().unwrap_or_default();
+    frame.render_widget(Line::from(status).yellow(), status_area);
+    frame.render_widget(Line::from(keys_help(&app.mode)).dim(), keys_area);
+
+    match app.mode.clone() {
+        Mode::Browse => (),
+        Mode::Help {
+            scroll,
+        } => draw_help(frame, app, scroll),
+        Mode::Edit {
+            symbol,
+            input,
+            error,
+        } => draw_edit(frame, app, symbol, &input, error.as_deref()),
+        Mode::Search {
+            query,
+            results,
+            selected,
+        } => draw_search(frame, app, &query, &results, selected),
+        Mode::ConfirmQuit => {
+            let area = frame.area().centered(Constraint::Length(50), Constraint::Length(3));
+            frame.render_widget(Clear, area);
+            frame.render_widget(
+                Paragraph::new("Save configuration before exiting? (y/n, Esc to cancel)")
+                    .block(Block::bordered().title(" Unsaved changes ")),
+                area,
+            );
+        }
+    }
+}
+
+/// Draw the entries of the menu being shown.
+fn draw_menu<C: Context>(frame: &mut Frame, app: &mut App<C>, area: Rect) {
+    let items: Vec<ListItem> = app
+        .entries()
+        .iter()
+        .map(|entry| {
+            let style = if entry.visible {
+                Style::default()
+            } else {
+                Style::default().fg(Color::DarkGray)
+            };
+            ListItem::new(Line::styled(app.label(entry.node), style))
+        })
+        .collect();
+
+    let title = app.breadcrumbs().pop().unwrap_or("Main menu".to_string());
+    let list = List::new(items)
+        .block(Block::bordered().title(format!(" {title} ")))
+        .highlight_style(Style::default().add_modifier(Modifier::REVERSED));
+
+    let selected = app.selected();
+    app.list_state.select(Some(selected));
+    frame.render_stateful_widget(list, area, &mut app.list_state);
+}
+
+/// Draw the help for the selected entry.
+fn draw_help<C: Context>(frame: &mut Frame, app: &App<C>, scroll: u16) {
+    let Some(entry) = app.selected_entry() else {
+        return;
+    };
+
+    let area = frame.area().centered(Constraint::Percentage(80), Constraint::Percentage(80));
+    frame.render_widget(Clear, area);
+    frame.render_widget(
+        Paragraph::new(app.help(entry.node))
+            .wrap(Wrap {
+                trim: false,
+            })
+            .scroll((scroll, 0))
+            .block(Block::bordered().title(format!(" {} ", app.title(entry.node)))),
+        area,
+    );
+}
+
+/// Draw the value entry dialog.
+fn draw_edit<C: Context>(frame: &mut Frame, app: &App<C>, symbol: usize, input: &str, error: Option<&str>) {
+    let info = &app.eval().table().symbols()[symbol];
+    let state = app.eval().symbol_state(symbol);
+
+    let mut lines = vec![Line::from(vec![Span::raw("> "), Span::raw(input), Span::raw("_").slow_blink()])];
+    if let Some((low, high)) = state.range {
+        lines.push(Line::from(format!("Range: {}", range_text(info.r#type, low, high))).dim());
+    }
+    if let Some(error) = error {
+        lines.push(Line::from(error.to_string()).red());
+    }
+
+    let height = lines.len() as u16 + 2;
+    let area = frame.area().centered(Constraint::Percentage(70), Constraint::Length(height));
+    frame.render_widget(Clear, area);
+    frame.render_widget(
+        Paragraph::new(lines).block(Block::bordered().title(format!(" {} ({}) ", info.name, info.r#type))),
+        area,
+    );
+}
+
+/// Draw the search dialog and its results.
+fn draw_search<C: Context>(frame: &mut Frame, app: &App<C>, query: &str, results: &[usize], selected: usize) {
+    let area = frame.area().centered(Constraint::Percentage(80), Constraint::Percentage(80));
+    frame.render_widget(Clear, area);
+
+    let block = Block::bordered().title(" Search (name or prompt) ");
+    let inner = block.inner(area);
+    frame.render_widget(block, area);
+
+    let [input_area, results_area] = Layout::vertical([Constraint::Length(1), Constraint::Min(1)]).areas(inner);
+    frame.render_widget(Line::from(vec![Span::raw("/ "), Span::raw(query), Span::raw("_").slow_blink()]), input_area);
+
+    let symbols = app.eval().table().symbols();
+    let items: Vec<ListItem> = results
+        .iter()
+        .map(|&id| {
+            let symbol = &symbols[id];
+            let prompt = symbol
+                .definitions
+                .iter()
+                .find_map(|def| def.config.prompt.as_ref())
+                .map(|prompt| format!(" - {}", prompt.title.as_str()))
+                .unwrap_or_default();
+            ListItem::new(format!("{}{prompt}", symbol.name))
+        })
+        .collect();
+
+    let mut state = ListState::default().with_selected(Some(selected));
+    frame.render_stateful_widget(
+        List::new(items).highlight_style(Style::default().add_modifier(Modifier::REVERSED)),
+        results_area,
+        &mut state,
+    );
+}
+
+/// Returns the key bindings for the current mode.
+fn keys_help(mode: &Mode) -> &'static str {
+    match mode {
+        Mode::Browse => {
+            "Enter: open/change  Space: toggle  Esc: back  ?: help  /: search  a: show all  s: save  q: quit"
+        }
+        Mode::Help {
+            ..
+        } => "Up/Down: scroll  any other key: close",
+        Mode::Edit {
+            ..
+        } => "Enter: accept  Esc: cancel",
+        Mode::Search {
+            ..
+        } => "Up/Down: select  Enter: go to option  Esc: cancel",
+        Mode::ConfirmQuit => "y: save and exit  n: exit without saving  Esc: cancel",
+    }
+}
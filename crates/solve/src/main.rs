@@ -0,0 +1,112 @@
+//! Find the option values to add to a configuration so that a set of goals is met.
+//!
+//! The assignments are printed, or with `--write`, added to the configuration file. The exit status is 1 if some goals
+//! cannot be met.
+
+use {
+    clap::Parser,
+    log::error,
+    modular_esp_idf_kconfig_lib::{
+        eval::{Assignment, Evaluation, SymbolTable},
+        parser::KConfig,
+        sdkconfig::{Renames, SdkConfig, SdkConfigWriter, CONFIG_PREFIX},
+        srctree, ContextArgs,
+    },
+    std::{
+        fs::File,
+        io::{BufWriter, Write},
+        path::PathBuf,
+        process::ExitCode,
+    },
+};
+
+/// Command line options for the solver.
+#[derive(Debug, Parser)]
+#[command(version, about)]
+struct Options {
+    /// The values to reach, as `NAME=VALUE` with or without the `CONFIG_` prefix.
+    #[arg(required = true)]
+    goals: Vec<Assignment>,
+
+    /// The top-level Kconfig file.
+    #[arg(long, default_value = "Kconfig")]
+    kconfig: PathBuf,
+
+    /// The project configuration (`sdkconfig`) file. If it does not exist, the solver starts from the defaults.
+    #[arg(long, env = "KCONFIG_CONFIG", default_value = "sdkconfig")]
+    config: PathBuf,
+
+    /// `sdkconfig.rename` files listing deprecated option names.
+    #[arg(long)]
+    sdkconfig_rename: Vec<PathBuf>,
+
+    /// The variables to evaluate the Kconfig files with.
+    #[command(flatten)]
+    env: ContextArgs,
+
+    /// Add the assignments to the configuration file instead of printing them.
+    #[arg(long)]
+    write: bool,
+}
+
+fn main() -> ExitCode {
+    env_logger::init();
+    let options = Options::parse();
+
+    match run(&options) {
+        Ok(true) => ExitCode::SUCCESS,
+        Ok(false) => ExitCode::from(1),
+        Err(e) => {
+            error!("{e}");
+            ExitCode::FAILURE
+        }
+    }
+}
+
+/// Solve for the goals, returning `true` if they were all met.
+fn run(options: &Options) -> Result<bool, Box<dyn std::error::Error>> {
+    let context = options.env.context()?;
+    let base_dir = srctree(&context)?;
+
+    let kconfig = KConfig::from_file(&options.kconfig, &base_dir, &context)?;
+
+    let mut renames = Renames::new();
+    for filename in &options.sdkconfig_rename {
+        renames.extend(Renames::from_file(filename)?)?;
+    }
+
+    let mut eval = Evaluation::new(SymbolTable::new(&kconfig));
+    if options.config.exists() {
+        let mut sdkconfig = SdkConfig::from_file(&options.config)?;
+        renames.apply(&mut sdkconfig);
+        eval.load_sdkconfig(&sdkconfig);
+    }
+    eval.evaluate(&context)?;
+
+    let solution = eval.solve(&options.goals, &context)?;
+    for conflict in &solution.conflicts {
+        eprintln!("Cannot meet {conflict}");
+    }
+
+    if options.write {
+        for assignment in &solution.assignments {
+            eval.set_user_value(&assignment.name, assignment.value.clone());
+        }
+        eval.evaluate(&context)?;
+
+        let writer = SdkConfigWriter {
+            idf_version: None,
+            renames,
+        };
+
+        let mut output = BufWriter::new(File::create(&options.config)?);
+        writer.write_config(&eval, &mut output)?;
+        output.flush()?;
+    } else {
+        for assignment in &solution.assignments {
+            println!("{CONFIG_PREFIX}{assignment}");
+        }
+    }
+
+    Ok(solution.is_satisfied())
+}
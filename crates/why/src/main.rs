@@ -0,0 +1,82 @@
+//! Explain why Kconfig symbols have their values in a configuration.
+
+use {
+    clap::Parser,
+    log::error,
+    modular_esp_idf_kconfig_lib::{
+        eval::{Evaluation, SymbolTable},
+        parser::KConfig,
+        sdkconfig::{Renames, SdkConfig, CONFIG_PREFIX},
+        srctree, ContextArgs,
+    },
+    std::{path::PathBuf, process::ExitCode},
+};
+
+/// Command line options for the explanation.
+#[derive(Debug, Parser)]
+#[command(version, about)]
+struct Options {
+    /// The symbols to explain, with or without the `CONFIG_` prefix.
+    #[arg(required = true)]
+    symbols: Vec<String>,
+
+    /// The top-level Kconfig file.
+    #[arg(long, default_value = "Kconfig")]
+    kconfig: PathBuf,
+
+    /// The project configuration (`sdkconfig`) file. If it does not exist, the defaults are explained.
+    #[arg(long, env = "KCONFIG_CONFIG", default_value = "sdkconfig")]
+    config: PathBuf,
+
+    /// `sdkconfig.rename` files listing deprecated option names.
+    #[arg(long)]
+    sdkconfig_rename: Vec<PathBuf>,
+
+    /// The variables to evaluate the Kconfig files with.
+    #[command(flatten)]
+    env: ContextArgs,
+}
+
+fn main() -> ExitCode {
+    env_logger::init();
+    let options = Options::parse();
+
+    match run(&options) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            error!("{e}");
+            ExitCode::FAILURE
+        }
+    }
+}
+
+fn run(options: &Options) -> Result<(), Box<dyn std::error::Error>> {
+    let context = options.env.context()?;
+    let base_dir = srctree(&context)?;
+
+    let kconfig = KConfig::from_file(&options.kconfig, &base_dir, &context)?;
+
+    let mut renames = Renames::new();
+    for filename in &options.sdkconfig_rename {
+        renames.extend(Renames::from_file(filename)?)?;
+    }
+
+    let mut eval = Evaluation::new(SymbolTable::new(&kconfig));
+    if options.config.exists() {
+        let mut sdkconfig = SdkConfig::from_file(&options.config)?;
+        renames.apply(&mut sdkconfig);
+        eval.load_sdkconfig(&sdkconfig);
+    }
+    eval.evaluate(&context)?;
+
+    for (i, symbol) in options.symbols.iter().enumerate() {
+        if i > 0 {
+            println!();
+        }
+
+        let name = symbol.strip_prefix(CONFIG_PREFIX).unwrap_or(symbol);
+        print!("{}", eval.explain(name, &context)?);
+    }
+
+    Ok(())
+}